@@ -1,52 +1,329 @@
 use crate::{err, error};
 use proc_macro2::TokenStream;
 use std::str::FromStr;
+use syn::parse::{Parse, Parser};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{parse2, Attribute, Expr, ExprLit, Lit, Meta, Path, Result, Token, Type};
+use syn::{parse2, parse_quote, Attribute, Expr, ExprLit, Lit, Meta, Path, Result, Token, Type};
 
 enum BitcodeAttr {
-    BoundType(Type),
+    /// Encodes a struct generated by `bitflags::bitflags!` as its bits with the `bitflags` feature.
+    Bitflags,
+    /// Replaces the inferred `T: Encode`/`T: Decode` bounds (including the ones added by
+    /// `bound_type`) with bounds on the listed types, e.g. `#[bitcode(bound = "")]` for a generic
+    /// that's only used by fields which don't need it to be `Encode`/`Decode`.
+    Bound(Vec<Type>),
+    BoundType(Box<Type>),
+    Context(Box<Type>),
+    /// Decodes a trailing field as `Default::default()` (or the given expression) if the input ends
+    /// before it, so data encoded before the field was added can still be decoded. Only works if
+    /// nothing is encoded after the struct (e.g. it's the type passed to `encode`). Input truncated
+    /// exactly before the field can't be told apart from older data, so it also gets the default.
+    Default(Box<Expr>),
+    /// Encodes an integer field as the difference from the previous value in its column.
+    Delta,
+    /// Doesn't change the generated code. A struct's encoder is just its fields' encoders in order
+    /// (without any prefix), so a nested struct is already encoded exactly as if its fields were
+    /// written in place of it. The attribute documents that guarantee at the use site.
+    Flatten,
+    /// Decodes a type (or field) as `R` and converts it with `From<R>` instead of decoding its
+    /// fields. With `try_from` (`true`) it's converted with `TryFrom<R>` and errors are decode
+    /// errors.
+    From(Box<Type>, bool),
+    /// Encodes a type (or field) by converting a clone of it `Into<R>` instead of encoding its
+    /// fields.
+    Into(Box<Type>),
+    /// The type that data written with an older `version` is decoded as before being converted
+    /// with `From`. It can have its own `previous` to form a chain of versions.
+    Previous(Box<Type>),
+    /// Encodes an integer field as its offset from the start of the range, one byte per column so
+    /// the most significant byte is bit packed. Stored as the start and the inclusive end.
+    Range(Box<(Expr, Expr)>),
+    Repr(Box<Type>),
+    /// Skips a field when encoding and sets it to `Default::default()` (or the given expression)
+    /// when decoding.
+    Skip(Box<Expr>),
+    /// Also doesn't change the generated code since a struct with one field is already encoded
+    /// exactly like that field. Checks that there's exactly one field so adding another one is a
+    /// compile error instead of a silent format change.
+    Transparent,
+    /// Calls `path(&T) -> Result<(), E: Display>` on every decoded value, turning an `Err` into a
+    /// decode error.
+    Validate(Box<Path>),
+    /// Prefixes the encoded columns of a struct or enum with a version byte, which is checked when
+    /// decoding (or dispatched to `previous` if it doesn't match).
+    Version(u8),
+    /// Encodes a field as `module::Repr` by calling `module::encode(&T) -> Repr` and decodes it by
+    /// calling `module::decode(Repr) -> T`. On an enum variant, all of its fields are encoded
+    /// together with `module::encode((&A, &B, ..)) -> Repr` and
+    /// `module::decode(Repr) -> (A, B, ..)`.
+    With(Box<Path>),
 }
 
 impl BitcodeAttr {
     fn new(nested: &Meta) -> Result<Self> {
         let path = path_ident_string(nested.path(), &nested)?;
         match path.as_str() {
-            "bound_type" => match nested {
-                Meta::NameValue(name_value) => {
-                    let expr = &name_value.value;
-                    let str_lit = match expr {
-                        Expr::Lit(ExprLit {
-                            lit: Lit::Str(v), ..
-                        }) => v,
-                        _ => return err(&expr, "expected string e.g. \"T\""),
-                    };
-
-                    let value = TokenStream::from_str(&str_lit.value()).unwrap();
-                    Ok(Self::BoundType(
-                        parse2(value).map_err(|e| error(str_lit, &format!("{e}")))?,
-                    ))
-                }
-                _ => err(&nested, "expected name value"),
+            "bitflags" => match nested {
+                Meta::Path(_) => Ok(Self::Bitflags),
+                _ => err(&nested, "expected path e.g. #[bitcode(bitflags)]"),
             },
+            "bound" => {
+                let types: TokenStream = parse_str(nested, "\"T, U\"")?;
+                let types = Punctuated::<Type, Token![,]>::parse_terminated.parse2(types)?;
+                Ok(Self::Bound(types.into_iter().collect()))
+            }
+            "bound_type" => Ok(Self::BoundType(Box::new(parse_type(nested)?))),
+            "context" => Ok(Self::Context(Box::new(parse_type(nested)?))),
+            "default" => match nested {
+                Meta::Path(_) => Ok(Self::Default(Box::new(parse_quote!(
+                    ::core::default::Default::default()
+                )))),
+                _ => Ok(Self::Default(Box::new(parse_str(
+                    nested,
+                    "\"Default::default()\"",
+                )?))),
+            },
+            "delta" => match nested {
+                Meta::Path(_) => Ok(Self::Delta),
+                _ => err(&nested, "expected path e.g. #[bitcode(delta)]"),
+            },
+            "flatten" => match nested {
+                Meta::Path(_) => Ok(Self::Flatten),
+                _ => err(&nested, "expected path e.g. #[bitcode(flatten)]"),
+            },
+            "from" => Ok(Self::From(Box::new(parse_type(nested)?), false)),
+            "into" => Ok(Self::Into(Box::new(parse_type(nested)?))),
+            "previous" => Ok(Self::Previous(Box::new(parse_type(nested)?))),
+            "range" => {
+                let range: TokenStream = parse_str(nested, "\"0..=4095\"")?;
+                let range = range.to_string();
+                let parse = |s: &str| {
+                    syn::parse_str::<Expr>(s).map_err(|e| error(&nested, &format!("{e}")))
+                };
+                if let Some((start, end)) = range.split_once("..=") {
+                    Ok(Self::Range(Box::new((parse(start)?, parse(end)?))))
+                } else if let Some((start, end)) = range.split_once("..") {
+                    let end = parse(end)?;
+                    Ok(Self::Range(Box::new((
+                        parse(start)?,
+                        parse_quote!(#end - 1),
+                    ))))
+                } else {
+                    err(&nested, "expected range e.g. \"0..=4095\"")
+                }
+            }
+            "repr" => Ok(Self::Repr(Box::new(parse_type(nested)?))),
+            "skip" => match nested {
+                Meta::Path(_) => Ok(Self::Skip(Box::new(parse_quote!(
+                    ::core::default::Default::default()
+                )))),
+                _ => Ok(Self::Skip(Box::new(parse_str(
+                    nested,
+                    "\"Default::default()\"",
+                )?))),
+            },
+            "transparent" => match nested {
+                Meta::Path(_) => Ok(Self::Transparent),
+                _ => err(&nested, "expected path e.g. #[bitcode(transparent)]"),
+            },
+            "try_from" => Ok(Self::From(Box::new(parse_type(nested)?), true)),
+            "validate" => Ok(Self::Validate(Box::new(parse_str(nested, "\"path\"")?))),
+            "version" => Ok(Self::Version(parse_u8(nested, "1")?)),
+            "with" => Ok(Self::With(Box::new(parse_str(nested, "\"module\"")?))),
             _ => err(&nested, "unknown attribute"),
         }
     }
 
     fn apply(self, attrs: &mut BitcodeAttrs, nested: &Meta) -> Result<()> {
         match self {
+            Self::Bitflags => {
+                if let AttrType::Derive { bitflags, .. } = &mut attrs.attr_type {
+                    if *bitflags {
+                        return err(nested, "duplicate");
+                    }
+                    *bitflags = true;
+                    Ok(())
+                } else {
+                    err(nested, "can only apply bitflags to structs")
+                }
+            }
+            Self::Bound(types) => {
+                if let AttrType::Derive { bound, .. } = &mut attrs.attr_type {
+                    if bound.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *bound = Some(types);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply bound to structs and enums")
+                }
+            }
             Self::BoundType(bound_type) => {
                 if let AttrType::Field { bound_type: b, .. } = &mut attrs.attr_type {
                     if b.is_some() {
                         return err(nested, "duplicate");
                     }
-                    *b = Some(bound_type);
+                    *b = Some(*bound_type);
                     Ok(())
                 } else {
                     err(nested, "can only apply bound to fields")
                 }
             }
+            Self::Context(context) => {
+                if let AttrType::Field { context: c, .. } = &mut attrs.attr_type {
+                    if c.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *c = Some(*context);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply context to fields")
+                }
+            }
+            Self::Default(expr) => {
+                if let AttrType::Field { default, .. } = &mut attrs.attr_type {
+                    if default.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *default = Some(*expr);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply default to fields")
+                }
+            }
+            Self::Delta => {
+                if let AttrType::Field { delta, .. } = &mut attrs.attr_type {
+                    if *delta {
+                        return err(nested, "duplicate");
+                    }
+                    *delta = true;
+                    Ok(())
+                } else {
+                    err(nested, "can only apply delta to fields")
+                }
+            }
+            Self::Flatten => {
+                if let AttrType::Field { flatten, .. } = &mut attrs.attr_type {
+                    if *flatten {
+                        return err(nested, "duplicate");
+                    }
+                    *flatten = true;
+                    Ok(())
+                } else {
+                    err(nested, "can only apply flatten to fields")
+                }
+            }
+            Self::From(ty, try_from) => match &mut attrs.attr_type {
+                AttrType::Derive { from, .. } | AttrType::Field { from, .. } => {
+                    if from.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *from = Some((*ty, try_from));
+                    Ok(())
+                }
+                _ => err(
+                    nested,
+                    "can only apply from/try_from to structs, enums and fields",
+                ),
+            },
+            Self::Into(ty) => match &mut attrs.attr_type {
+                AttrType::Derive { into, .. } | AttrType::Field { into, .. } => {
+                    if into.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *into = Some(*ty);
+                    Ok(())
+                }
+                _ => err(nested, "can only apply into to structs, enums and fields"),
+            },
+            Self::Previous(previous) => {
+                if let AttrType::Derive { previous: p, .. } = &mut attrs.attr_type {
+                    if p.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *p = Some(*previous);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply previous to structs and enums")
+                }
+            }
+            Self::Range(range) => {
+                if let AttrType::Field { range: r, .. } = &mut attrs.attr_type {
+                    if r.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *r = Some(*range);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply range to fields")
+                }
+            }
+            Self::Repr(repr) => {
+                if let AttrType::Derive { repr: r, .. } = &mut attrs.attr_type {
+                    if r.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *r = Some(*repr);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply repr to enums")
+                }
+            }
+            Self::Skip(expr) => {
+                if let AttrType::Field { skip, .. } = &mut attrs.attr_type {
+                    if skip.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *skip = Some(*expr);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply skip to fields")
+                }
+            }
+            Self::Transparent => {
+                if let AttrType::Derive { transparent, .. } = &mut attrs.attr_type {
+                    if *transparent {
+                        return err(nested, "duplicate");
+                    }
+                    *transparent = true;
+                    Ok(())
+                } else {
+                    err(nested, "can only apply transparent to structs")
+                }
+            }
+            Self::Validate(path) => {
+                if let AttrType::Derive { validate, .. } = &mut attrs.attr_type {
+                    if validate.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *validate = Some(*path);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply validate to structs and enums")
+                }
+            }
+            Self::Version(version) => {
+                if let AttrType::Derive { version: v, .. } = &mut attrs.attr_type {
+                    if v.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *v = Some(version);
+                    Ok(())
+                } else {
+                    err(nested, "can only apply version to structs and enums")
+                }
+            }
+            Self::With(module) => match &mut attrs.attr_type {
+                AttrType::Field { with, .. } | AttrType::Variant { with } => {
+                    if with.is_some() {
+                        return err(nested, "duplicate");
+                    }
+                    *with = Some(*module);
+                    Ok(())
+                }
+                _ => err(nested, "can only apply with to fields and enum variants"),
+            },
         }
     }
 }
@@ -58,9 +335,36 @@ pub struct BitcodeAttrs {
 
 #[derive(Clone)]
 enum AttrType {
-    Derive,
-    Variant,
-    Field { bound_type: Option<Type> },
+    Derive {
+        bitflags: bool,
+        bound: Option<Vec<Type>>,
+        /// The type and whether it's converted with `TryFrom` instead of `From`.
+        from: Option<(Type, bool)>,
+        into: Option<Type>,
+        previous: Option<Type>,
+        repr: Option<Type>,
+        transparent: bool,
+        validate: Option<Path>,
+        version: Option<u8>,
+    },
+    Variant {
+        with: Option<Path>,
+    },
+    Field {
+        bound_type: Option<Type>,
+        context: Option<Type>,
+        default: Option<Expr>,
+        delta: bool,
+        flatten: bool,
+        /// The type and whether it's converted with `TryFrom` instead of `From`.
+        from: Option<(Type, bool)>,
+        into: Option<Type>,
+        range: Option<(Expr, Expr)>,
+        /// Not an attribute, set if the field's type contains the type being derived.
+        recursive: bool,
+        skip: Option<Expr>,
+        with: Option<Path>,
+    },
 }
 
 impl BitcodeAttrs {
@@ -68,6 +372,20 @@ impl BitcodeAttrs {
         Self { attr_type }
     }
 
+    pub fn bitflags(&self) -> bool {
+        match &self.attr_type {
+            AttrType::Derive { bitflags, .. } => *bitflags,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn bound(&self) -> Option<&[Type]> {
+        match &self.attr_type {
+            AttrType::Derive { bound, .. } => bound.as_deref(),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn bound_type(&self) -> Option<Type> {
         match &self.attr_type {
             AttrType::Field { bound_type, .. } => bound_type.as_ref().cloned(),
@@ -75,21 +393,237 @@ impl BitcodeAttrs {
         }
     }
 
+    pub fn context(&self) -> Option<&Type> {
+        match &self.attr_type {
+            AttrType::Field { context, .. } => context.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn convert_from(&self) -> Option<&(Type, bool)> {
+        match &self.attr_type {
+            AttrType::Derive { from, .. } | AttrType::Field { from, .. } => from.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn convert_into(&self) -> Option<&Type> {
+        match &self.attr_type {
+            AttrType::Derive { into, .. } | AttrType::Field { into, .. } => into.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn previous(&self) -> Option<&Type> {
+        match &self.attr_type {
+            AttrType::Derive { previous, .. } => previous.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn default(&self) -> Option<&Expr> {
+        match &self.attr_type {
+            AttrType::Field { default, .. } => default.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn range(&self) -> Option<&(Expr, Expr)> {
+        match &self.attr_type {
+            AttrType::Field { range, .. } => range.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn delta(&self) -> bool {
+        match &self.attr_type {
+            AttrType::Field { delta, .. } => *delta,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn recursive(&self) -> bool {
+        match &self.attr_type {
+            AttrType::Field { recursive, .. } => *recursive,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn set_recursive(&mut self, value: bool) {
+        match &mut self.attr_type {
+            AttrType::Field { recursive, .. } => *recursive = value,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn repr(&self) -> Option<&Type> {
+        match &self.attr_type {
+            AttrType::Derive { repr, .. } => repr.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn skip(&self) -> Option<&Expr> {
+        match &self.attr_type {
+            AttrType::Field { skip, .. } => skip.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn with(&self) -> Option<&Path> {
+        match &self.attr_type {
+            AttrType::Field { with, .. } | AttrType::Variant { with } => with.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn transparent(&self) -> bool {
+        match &self.attr_type {
+            AttrType::Derive { transparent, .. } => *transparent,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn validate(&self) -> Option<&Path> {
+        match &self.attr_type {
+            AttrType::Derive { validate, .. } => validate.as_ref(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn version(&self) -> Option<u8> {
+        match &self.attr_type {
+            AttrType::Derive { version, .. } => *version,
+            _ => unreachable!(),
+        }
+    }
+
     pub fn parse_derive(attrs: &[Attribute]) -> Result<Self> {
-        let mut ret = Self::new(AttrType::Derive);
+        let mut ret = Self::new(AttrType::Derive {
+            bitflags: false,
+            bound: None,
+            from: None,
+            into: None,
+            previous: None,
+            repr: None,
+            transparent: false,
+            validate: None,
+            version: None,
+        });
         ret.parse_inner(attrs)?;
         Ok(ret)
     }
 
     pub fn parse_variant(attrs: &[Attribute], _derive_attrs: &Self) -> Result<Self> {
-        let mut ret = Self::new(AttrType::Variant);
+        let mut ret = Self::new(AttrType::Variant { with: None });
         ret.parse_inner(attrs)?;
         Ok(ret)
     }
 
+    /// For an enum variant with `with`, the attributes of the field that its fields are encoded as.
+    pub fn with_field(&self) -> Option<Self> {
+        Some(Self::new_field(Some(self.with()?.clone())))
+    }
+
+    fn new_field(with: Option<Path>) -> Self {
+        Self::new(AttrType::Field {
+            bound_type: None,
+            context: None,
+            default: None,
+            delta: false,
+            flatten: false,
+            from: None,
+            into: None,
+            range: None,
+            recursive: false,
+            skip: None,
+            with,
+        })
+    }
+
     pub fn parse_field(attrs: &[Attribute], _parent_attrs: &Self) -> Result<Self> {
-        let mut ret = Self::new(AttrType::Field { bound_type: None });
+        let mut ret = Self::new_field(None);
         ret.parse_inner(attrs)?;
+        if let AttrType::Field {
+            bound_type,
+            context,
+            default,
+            delta,
+            flatten,
+            from,
+            into,
+            range,
+            skip,
+            with,
+            ..
+        } = &ret.attr_type
+        {
+            if let Some(skip) = skip {
+                if bound_type.is_some()
+                    || context.is_some()
+                    || default.is_some()
+                    || *delta
+                    || *flatten
+                    || from.is_some()
+                    || into.is_some()
+                    || range.is_some()
+                    || with.is_some()
+                {
+                    return err(
+                        skip,
+                        "skip can't be combined with bound_type, context, default, delta, \
+                        flatten, from, into, range or with",
+                    );
+                }
+            }
+            if from.is_some() || into.is_some() {
+                let attr = attrs.iter().find(|a| a.path().is_ident("bitcode")).unwrap();
+                // Both are needed since the field's type isn't bounded by Encode or Decode.
+                if from.is_none() || into.is_none() {
+                    return err(
+                        attr,
+                        "into on a field requires from or try_from and vice versa",
+                    );
+                }
+                if bound_type.is_some()
+                    || context.is_some()
+                    || *delta
+                    || range.is_some()
+                    || with.is_some()
+                {
+                    return err(
+                        attr,
+                        "into/from can't be combined with bound_type, context, delta, range \
+                        or with",
+                    );
+                }
+            }
+            if let Some(with) = with {
+                if bound_type.is_some() || context.is_some() || range.is_some() {
+                    return err(
+                        with,
+                        "with can't be combined with bound_type, context or range",
+                    );
+                }
+            }
+            if *delta
+                && (bound_type.is_some() || context.is_some() || range.is_some() || with.is_some())
+            {
+                let attr = attrs.iter().find(|a| a.path().is_ident("bitcode")).unwrap();
+                return err(
+                    attr,
+                    "delta can't be combined with bound_type, context, range or with",
+                );
+            }
+            if let Some((start, _)) = range {
+                if bound_type.is_some() || context.is_some() || *flatten {
+                    return err(
+                        start,
+                        "range can't be combined with bound_type, context or flatten",
+                    );
+                }
+            }
+        }
         Ok(ret)
     }
 
@@ -109,6 +643,42 @@ impl BitcodeAttrs {
     }
 }
 
+fn parse_type(nested: &Meta) -> Result<Type> {
+    parse_str(nested, "\"T\"")
+}
+
+/// Parses the string value of `#[bitcode(name = "value")]` as a `T`.
+fn parse_str<T: Parse>(nested: &Meta, example: &str) -> Result<T> {
+    match nested {
+        Meta::NameValue(name_value) => {
+            let expr = &name_value.value;
+            let str_lit = match expr {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(v), ..
+                }) => v,
+                _ => return err(&expr, &format!("expected string e.g. {example}")),
+            };
+
+            let value = TokenStream::from_str(&str_lit.value()).unwrap();
+            parse2(value).map_err(|e| error(str_lit, &format!("{e}")))
+        }
+        _ => err(&nested, "expected name value"),
+    }
+}
+
+/// Parses the integer value of `#[bitcode(name = 1)]` as a `u8`.
+fn parse_u8(nested: &Meta, example: &str) -> Result<u8> {
+    match nested {
+        Meta::NameValue(name_value) => match &name_value.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(v), ..
+            }) => v.base10_parse(),
+            expr => err(&expr, &format!("expected integer e.g. {example}")),
+        },
+        _ => err(&nested, "expected name value"),
+    }
+}
+
 fn path_ident_string(path: &Path, spanned: &impl Spanned) -> Result<String> {
     if let Some(path) = path.get_ident() {
         Ok(path.to_string())
@@ -15,6 +15,18 @@ impl FieldBounds {
         field_attrs: &BitcodeAttrs,
         bound: syn::Path,
     ) {
+        if field_attrs.context().is_some() {
+            return; // Encoded with EncodeWith/DecodeWith instead of Encode/Decode.
+        }
+        if field_attrs.skip().is_some() {
+            return; // Not encoded.
+        }
+        if field_attrs.with().is_some() {
+            return; // Encoded as the module's Repr instead.
+        }
+        if field_attrs.convert_into().is_some() {
+            return; // Converted to/from the into/from type instead.
+        }
         let bounds = self.bounds.entry(bound).or_default();
         if let Some(bound_type) = field_attrs.bound_type() {
             bounds.1.push(bound_type);
@@ -23,6 +35,12 @@ impl FieldBounds {
         }
     }
 
+    /// Replaces every bound with bounds on `bound_types` (for `#[bitcode(bound = "...")]`).
+    pub fn replace_with(&mut self, bound_types: Vec<syn::Type>, bound: syn::Path) {
+        self.bounds.clear();
+        self.bounds.insert(bound, (vec![], bound_types));
+    }
+
     pub fn added_to(self, mut generics: syn::Generics) -> syn::Generics {
         for (bound, (fields, extra_bound_types)) in self.bounds {
             generics = with_bound(&fields, extra_bound_types, &generics, &bound);
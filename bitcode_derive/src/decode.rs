@@ -1,5 +1,6 @@
+use crate::attribute::BitcodeAttrs;
 use crate::private;
-use crate::shared::{remove_lifetimes, replace_lifetimes, variant_index};
+use crate::shared::{remove_lifetimes, replace_lifetimes, variant_index, MAX_U8_VARIANTS};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 use syn::{
@@ -12,6 +13,27 @@ fn de_lifetime() -> Lifetime {
     parse_quote!('__de) // Must match DE_LIFETIME.
 }
 
+/// Returns the name of a field in `bitcode::Error::path`: `field` for struct fields,
+/// `Variant.field` for enum variant fields and `Variant` for variants with
+/// `#[bitcode(with = "module")]` (which have no `real_field_name`).
+fn field_path(
+    field_name: &TokenStream,
+    global_field_name: &TokenStream,
+    real_field_name: &TokenStream,
+) -> String {
+    let global = global_field_name.to_string();
+    let real = real_field_name.to_string();
+    if real.is_empty() {
+        return global.strip_suffix("_with").unwrap_or(&global).to_owned();
+    }
+    match global.strip_suffix(&field_name.to_string()) {
+        Some(prefix) if !prefix.is_empty() => {
+            format!("{}.{real}", prefix.strip_suffix('_').unwrap_or(prefix))
+        }
+        _ => real,
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum Item {
     Type,
@@ -39,33 +61,140 @@ impl crate::shared::Item for Item {
         global_field_name: TokenStream,
         real_field_name: TokenStream,
         field_type: &Type,
+        field_attrs: &BitcodeAttrs,
     ) -> TokenStream {
-        match self {
+        if let Some(skip) = field_attrs.skip() {
+            return match self {
+                Self::Decode => quote! {
+                    let #field_name = #skip;
+                },
+                Self::DecodeInPlace => {
+                    let de_type = replace_lifetimes(field_type, DE_LIFETIME);
+                    let private = private();
+                    quote! {
+                        #private::uninit_field!(out.#real_field_name: #de_type).write(#skip);
+                    }
+                }
+                _ => quote! {},
+            };
+        }
+        let decoder = if field_attrs.default().is_some() {
+            quote! { __decoder }
+        } else {
+            quote! { self.#global_field_name }
+        };
+        let field_impl = match self {
             Self::Type => {
                 let de_type = replace_lifetimes(field_type, DE_LIFETIME);
                 let private = private();
                 let de = de_lifetime();
-                quote! {
-                    #global_field_name: <#de_type as #private::Decode<#de>>::Decoder,
+                let decoder_type = if let Some(context) = field_attrs.context() {
+                    quote! { #private::ContextDecoder<#de, #de_type, #context> }
+                } else if let Some(module) = field_attrs.with() {
+                    quote! { <#module::Repr as #private::Decode<#de>>::Decoder }
+                } else if let Some((from, try_from)) = field_attrs.convert_from() {
+                    if *try_from {
+                        quote! { #private::TryFromDecoder<#de, #de_type, #from> }
+                    } else {
+                        quote! { #private::FromDecoder<#de, #from> }
+                    }
+                } else if field_attrs.delta() {
+                    quote! { #private::DeltaDecoder<#de, #de_type> }
+                } else if let Some((start, end)) = field_attrs.range() {
+                    quote! {
+                        #private::RangeHintDecoder<#de, {
+                            ((#end) as i128).wrapping_sub((#start) as i128) as u128
+                        }>
+                    }
+                } else if field_attrs.recursive() {
+                    quote! { #private::RecursiveDecoder<#de, #de_type> }
+                } else {
+                    quote! { <#de_type as #private::Decode<#de>>::Decoder }
+                };
+                if field_attrs.default().is_some() {
+                    quote! {
+                        #global_field_name: #private::DefaultDecoder<#decoder_type>,
+                    }
+                } else {
+                    quote! {
+                        #global_field_name: #decoder_type,
+                    }
                 }
             }
             Self::Default => quote! {
-                #global_field_name: Default::default(),
-            },
-            Self::Populate => quote! {
-                self.#global_field_name.populate(input, __length)?;
+                #global_field_name: ::core::default::Default::default(),
             },
+            Self::Populate => {
+                let private = private();
+                let path = field_path(&field_name, &global_field_name, &real_field_name);
+                quote! {
+                    let __remaining = input.len();
+                    self.#global_field_name
+                        .populate(input, __length)
+                        .map_err(|e| #private::field_error(e, #path, __remaining))?;
+                }
+            }
             // Only used by enum variants.
-            Self::Decode => quote! {
-                let #field_name = self.#global_field_name.decode();
-            },
+            Self::Decode => {
+                if let Some(module) = field_attrs.with() {
+                    quote! {
+                        let #field_name = #module::decode(#decoder.decode());
+                    }
+                } else if let Some((start, _)) = field_attrs.range() {
+                    quote! {
+                        let #field_name = ((#start) as i128)
+                            .wrapping_add(#decoder.decode() as i128) as #field_type;
+                    }
+                } else {
+                    quote! {
+                        let #field_name = #decoder.decode();
+                    }
+                }
+            }
+            Self::DecodeInPlace if field_attrs.with().is_some() => {
+                let module = field_attrs.with().unwrap();
+                let de_type = replace_lifetimes(field_type, DE_LIFETIME);
+                let private = private();
+                quote! {
+                    #private::uninit_field!(out.#real_field_name: #de_type)
+                        .write(#module::decode(#decoder.decode()));
+                }
+            }
+            Self::DecodeInPlace if field_attrs.range().is_some() => {
+                let (start, _) = field_attrs.range().unwrap();
+                let private = private();
+                quote! {
+                    #private::uninit_field!(out.#real_field_name: #field_type).write(
+                        ((#start) as i128).wrapping_add(#decoder.decode() as i128) as #field_type
+                    );
+                }
+            }
+            Self::DecodeInPlace => {
+                let de_type = replace_lifetimes(field_type, DE_LIFETIME);
+                let private = private();
+                quote! {
+                    #decoder.decode_in_place(#private::uninit_field!(out.#real_field_name: #de_type));
+                }
+            }
+        };
+
+        let Some(default) = field_attrs.default() else {
+            return field_impl;
+        };
+        // Default fields can only be in structs, so Self::Decode isn't needed.
+        match self {
             Self::DecodeInPlace => {
                 let de_type = replace_lifetimes(field_type, DE_LIFETIME);
                 let private = private();
                 quote! {
-                    self.#global_field_name.decode_in_place(#private::uninit_field!(out.#real_field_name: #de_type));
+                    if let Some(__decoder) = self.#global_field_name.present() {
+                        #field_impl
+                    } else {
+                        #private::uninit_field!(out.#real_field_name: #de_type).write(#default);
+                    }
                 }
             }
+            _ => field_impl,
         }
     }
 
@@ -104,7 +233,11 @@ impl crate::shared::Item for Item {
                     .then(|| {
                         let private = private();
                         let c_style = inners.is_empty();
-                        quote! { variants: #private::VariantDecoder<#de, #variant_count, #c_style>, }
+                        if variant_count > MAX_U8_VARIANTS {
+                            quote! { variants: #private::WideVariantDecoder<#de, #variant_count, #c_style>, }
+                        } else {
+                            quote! { variants: #private::VariantDecoder<#de, #variant_count, #c_style>, }
+                        }
                     })
                     .unwrap_or_default();
                 quote! {
@@ -114,7 +247,7 @@ impl crate::shared::Item for Item {
             }
             Self::Default => {
                 let variants = decode_variants
-                    .then(|| quote! { variants: Default::default(), })
+                    .then(|| quote! { variants: ::core::default::Default::default(), })
                     .unwrap_or_default();
                 let inners: TokenStream = (0..variant_count).map(|i| inner(self, i)).collect();
                 quote! {
@@ -233,11 +366,75 @@ impl crate::shared::Derive<{ Item::COUNT }> for Decode {
         parse_quote!(#private::Decode<#de>)
     }
 
+    fn derive_repr(&self, ident: Ident, repr: &Type, variants: &[&Ident]) -> TokenStream {
+        let private = private();
+        let de = de_lifetime();
+        let consts: Vec<_> = variants
+            .iter()
+            .map(|variant| Ident::new(&format!("__{variant}"), Span::call_site()))
+            .collect();
+        quote! {
+            const _: () = {
+                impl<#de> #private::Decode<#de> for #ident {
+                    type Decoder = #private::ReprDecoder<#de, Self, #repr>;
+                }
+
+                impl #private::FromRepr<#repr> for #ident {
+                    #[inline(always)]
+                    #[allow(non_upper_case_globals)]
+                    fn from_repr(repr: #repr) -> Option<Self> {
+                        #(const #consts: #repr = #ident::#variants as #repr;)*
+                        match repr {
+                            #(#consts => Some(Self::#variants),)*
+                            _ => None,
+                        }
+                    }
+                }
+            };
+        }
+    }
+
+    fn derive_bitflags(&self, ident: Ident) -> TokenStream {
+        let private = private();
+        let de = de_lifetime();
+        quote! {
+            const _: () = {
+                type Bits = <#ident as #private::Flags>::Bits;
+
+                impl<#de> #private::Decode<#de> for #ident {
+                    type Decoder = #private::ReprDecoder<#de, Self, Bits>;
+                }
+
+                impl #private::FromRepr<Bits> for #ident {
+                    #[inline(always)]
+                    fn from_repr(repr: Bits) -> Option<Self> {
+                        <Self as #private::Flags>::from_bits(repr)
+                    }
+                }
+            };
+        }
+    }
+
+    fn derive_convert(&self, ident: &Ident, attrs: &BitcodeAttrs) -> Option<TokenStream> {
+        let private = private();
+        let de = de_lifetime();
+        let decoder = match attrs.convert_from()? {
+            (from, false) => quote! { #private::FromDecoder<#de, #from> },
+            (try_from, true) => quote! { #private::TryFromDecoder<#de, Self, #try_from> },
+        };
+        Some(quote! {
+            impl<#de> #private::Decode<#de> for #ident {
+                type Decoder = #decoder;
+            }
+        })
+    }
+
     fn derive_impl(
         &self,
         output: [TokenStream; Item::COUNT],
         ident: Ident,
         mut generics: Generics,
+        attrs: &BitcodeAttrs,
     ) -> TokenStream {
         let input_generics = generics.clone();
         let (_, input_generics, _) = input_generics.split_for_impl();
@@ -283,19 +480,52 @@ impl crate::shared::Derive<{ Item::COUNT }> for Decode {
             type_body = quote! { __spooky: std::marker::PhantomData<&#de ()>, };
         }
         if default_body.is_empty() {
-            default_body = quote! { __spooky: Default::default(), };
+            default_body = quote! { __spooky: ::core::default::Default::default(), };
         }
 
         let decoder_ident = Ident::new(&format!("{ident}Decoder"), Span::call_site());
         let decoder_ty = quote! { #decoder_ident #decoder_generics };
         let private = private();
+        let (validate_impl, validated_decoder_ty) = if let Some(path) = attrs.validate() {
+            let validate_impl = quote! {
+                impl #impl_generics #private::Validate for #input_ty #where_clause {
+                    fn validate(&self) -> #private::Result<()> {
+                        #path(self).map_err(#private::error_from_display)
+                    }
+                }
+            };
+            let ty = quote! { #private::ValidateDecoder<#input_ty, #decoder_ty> };
+            (validate_impl, ty)
+        } else {
+            (quote! {}, decoder_ty.clone())
+        };
+        let decode_decoder_ty = match (attrs.version(), attrs.previous()) {
+            (Some(version), Some(previous)) => quote! {
+                #private::UpgradeDecoder<#de, #validated_decoder_ty, #previous, #version>
+            },
+            (Some(version), None) => {
+                quote! { #private::VersionDecoder<#validated_decoder_ty, #version> }
+            }
+            _ => validated_decoder_ty,
+        };
+        // Lets UpgradeDecoder require previous to be versioned.
+        let versioned_impl = attrs.version().map(|version| {
+            quote! {
+                impl #impl_generics #private::Versioned for #input_ty #where_clause {
+                    const VERSION: u8 = #version;
+                }
+            }
+        });
 
         quote! {
             const _: () = {
                 impl #impl_generics #private::Decode<#de> for #input_ty #where_clause {
-                    type Decoder = #decoder_ty;
+                    type Decoder = #decode_decoder_ty;
                 }
 
+                #validate_impl
+                #versioned_impl
+
                 #[allow(non_snake_case)]
                 pub struct #decoder_ident #decoder_impl_generics #decoder_where_clause {
                     #type_body
@@ -0,0 +1,181 @@
+use crate::attribute::BitcodeAttrs;
+use crate::shared::MAX_U8_VARIANTS;
+use crate::{error, private};
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::quote;
+use syn::{parse_quote, Generics, Path, Type};
+
+#[derive(Copy, Clone)]
+pub enum Item {
+    Describe,
+}
+impl Item {
+    const ALL: [Self; 1] = [Self::Describe];
+    const COUNT: usize = Self::ALL.len();
+}
+
+/// `Schema::Named(name, Box::new(schema))`.
+fn named(name: &str, schema: TokenStream) -> TokenStream {
+    let private = private();
+    quote! { #private::Schema::Named(#name.into(), Box::new(#schema)) }
+}
+
+/// `Schema::Tuple` of the fields pushed by `do_fields`.
+fn tuple(do_fields: &TokenStream) -> TokenStream {
+    let private = private();
+    quote! {{
+        #[allow(unused_mut)]
+        let mut __fields = vec![];
+        #do_fields
+        #private::Schema::Tuple(__fields)
+    }}
+}
+
+impl crate::shared::Item for Item {
+    fn field_impl(
+        self,
+        _field_name: TokenStream,
+        _global_field_name: TokenStream,
+        real_field_name: TokenStream,
+        field_type: &Type,
+        field_attrs: &BitcodeAttrs,
+    ) -> TokenStream {
+        if field_attrs.skip().is_some() {
+            return quote! {};
+        }
+        let unsupported = if field_attrs.context().is_some() {
+            Some("context")
+        } else if field_attrs.delta() {
+            Some("delta")
+        } else if field_attrs.range().is_some() {
+            Some("range")
+        } else if field_attrs.recursive() {
+            Some("recursive types")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            let message = format!("{unsupported} can't be described");
+            return error(field_type, &message).into_compile_error();
+        }
+
+        let private = private();
+        let schema = if let Some(module) = field_attrs.with() {
+            quote! { <#module::Repr as #private::Describe>::describe() }
+        } else if let Some(into) = field_attrs.convert_into() {
+            quote! { <#into as #private::Describe>::describe() }
+        } else {
+            quote! { <#field_type as #private::Describe>::describe() }
+        };
+        // Tuple fields (and the fields of a with variant) don't have names.
+        let name = real_field_name.to_string();
+        let schema = if name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            named(name.trim_start_matches("r#"), schema)
+        } else {
+            schema
+        };
+        quote! { __fields.push(#schema); }
+    }
+
+    fn struct_impl(
+        self,
+        _ident: &Ident,
+        _destructure_fields: &TokenStream,
+        do_fields: &TokenStream,
+    ) -> TokenStream {
+        tuple(do_fields)
+    }
+
+    fn enum_impl(
+        self,
+        variant_count: usize,
+        pattern: impl Fn(usize) -> TokenStream,
+        inner: impl Fn(Self, usize) -> TokenStream,
+    ) -> TokenStream {
+        if variant_count > MAX_U8_VARIANTS {
+            return quote! {
+                compile_error!("enums with more than 256 variants can't be described")
+            };
+        }
+        let variants = (0..variant_count).map(|i| {
+            // The pattern is `Enum::Variant {..}`.
+            let Some(TokenTree::Ident(variant_name)) = pattern(i).into_iter().nth(3) else {
+                unreachable!();
+            };
+            named(&variant_name.to_string(), tuple(&inner(self, i)))
+        });
+        let private = private();
+        quote! { #private::Schema::Enum(vec![#(#variants),*]) }
+    }
+}
+
+pub struct Describe;
+impl Describe {
+    fn describe_impl(
+        &self,
+        ident: &Ident,
+        generics: &Generics,
+        schema: TokenStream,
+    ) -> TokenStream {
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        let private = private();
+        let schema = named(&ident.to_string(), schema);
+        quote! {
+            const _: () = {
+                impl #impl_generics #private::Describe for #ident #ty_generics #where_clause {
+                    fn describe() -> #private::Schema {
+                        #schema
+                    }
+                }
+            };
+        }
+    }
+}
+
+impl crate::shared::Derive<{ Item::COUNT }> for Describe {
+    type Item = Item;
+    const ALL: [Self::Item; Item::COUNT] = Item::ALL;
+
+    fn bound(&self) -> Path {
+        let private = private();
+        parse_quote!(#private::Describe)
+    }
+
+    fn derive_repr(&self, ident: Ident, repr: &Type, _variants: &[&Ident]) -> TokenStream {
+        let private = private();
+        let schema = quote! { <#repr as #private::Describe>::describe() };
+        self.describe_impl(&ident, &Generics::default(), schema)
+    }
+
+    fn derive_bitflags(&self, ident: Ident) -> TokenStream {
+        let private = private();
+        let schema = quote! {
+            <<#ident as #private::Flags>::Bits as #private::Describe>::describe()
+        };
+        self.describe_impl(&ident, &Generics::default(), schema)
+    }
+
+    fn derive_convert(&self, ident: &Ident, attrs: &BitcodeAttrs) -> Option<TokenStream> {
+        // Both are the type that's encoded.
+        let repr = attrs
+            .convert_into()
+            .or_else(|| attrs.convert_from().map(|(from, _)| from))?;
+        let private = private();
+        let schema = quote! { <#repr as #private::Describe>::describe() };
+        Some(self.describe_impl(ident, &Generics::default(), schema))
+    }
+
+    fn derive_impl(
+        &self,
+        output: [TokenStream; Item::COUNT],
+        ident: Ident,
+        generics: Generics,
+        attrs: &BitcodeAttrs,
+    ) -> TokenStream {
+        if attrs.version().is_some() {
+            return error(&ident, "version can't be described").into_compile_error();
+        }
+        let [schema] = output;
+        self.describe_impl(&ident, &generics, schema)
+    }
+}
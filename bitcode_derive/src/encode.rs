@@ -1,5 +1,6 @@
+use crate::attribute::BitcodeAttrs;
 use crate::private;
-use crate::shared::{remove_lifetimes, replace_lifetimes, variant_index};
+use crate::shared::{remove_lifetimes, replace_lifetimes, variant_index, MAX_U8_VARIANTS};
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
 use syn::{parse_quote, Generics, Path, Type};
@@ -11,15 +12,17 @@ pub enum Item {
     Encode,
     EncodeVectored,
     CollectInto,
+    CollectIntoFlushing,
     Reserve,
 }
 impl Item {
-    const ALL: [Self; 6] = [
+    const ALL: [Self; 7] = [
         Self::Type,
         Self::Default,
         Self::Encode,
         Self::EncodeVectored,
         Self::CollectInto,
+        Self::CollectIntoFlushing,
         Self::Reserve,
     ];
     const COUNT: usize = Self::ALL.len();
@@ -31,18 +34,84 @@ impl crate::shared::Item for Item {
         global_field_name: TokenStream,
         real_field_name: TokenStream,
         field_type: &Type,
+        field_attrs: &BitcodeAttrs,
     ) -> TokenStream {
+        if field_attrs.skip().is_some() {
+            return match self {
+                Self::Encode => quote! { let _ = #field_name; },
+                _ => quote! {},
+            };
+        }
         match self {
             Self::Type => {
                 let static_type = replace_lifetimes(field_type, "static");
                 let private = private();
-                quote! {
-                    #global_field_name: <#static_type as #private::Encode>::Encoder,
+                if let Some(context) = field_attrs.context() {
+                    quote! {
+                        #global_field_name: #private::ContextEncoder<#static_type, #context>,
+                    }
+                } else if let Some(module) = field_attrs.with() {
+                    quote! {
+                        #global_field_name: <#module::Repr as #private::Encode>::Encoder,
+                    }
+                } else if let Some(into) = field_attrs.convert_into() {
+                    quote! {
+                        #global_field_name: #private::IntoEncoder<#static_type, #into>,
+                    }
+                } else if field_attrs.delta() {
+                    quote! {
+                        #global_field_name: #private::DeltaEncoder<#static_type>,
+                    }
+                } else if let Some((start, end)) = field_attrs.range() {
+                    quote! {
+                        #global_field_name: #private::RangeHintEncoder<{
+                            ((#end) as i128).wrapping_sub((#start) as i128) as u128
+                        }>,
+                    }
+                } else if field_attrs.recursive() {
+                    quote! {
+                        #global_field_name: #private::RecursiveEncoder<#static_type>,
+                    }
+                } else {
+                    quote! {
+                        #global_field_name: <#static_type as #private::Encode>::Encoder,
+                    }
                 }
             }
             Self::Default => quote! {
-                #global_field_name: Default::default(),
+                #global_field_name: ::core::default::Default::default(),
             },
+            Self::Encode | Self::EncodeVectored if field_attrs.with().is_some() => {
+                let module = field_attrs.with().unwrap();
+                if matches!(self, Self::EncodeVectored) {
+                    quote! {
+                        for me in i.clone() {
+                            self.#global_field_name.encode(&#module::encode(&me.#real_field_name));
+                        }
+                    }
+                } else {
+                    quote! {
+                        self.#global_field_name.encode(&#module::encode(#field_name));
+                    }
+                }
+            }
+            Self::Encode | Self::EncodeVectored if field_attrs.range().is_some() => {
+                let (start, _) = field_attrs.range().unwrap();
+                let offset = |v| quote! { &((*#v as i128).wrapping_sub((#start) as i128) as u128) };
+                if matches!(self, Self::EncodeVectored) {
+                    let offset = offset(quote! { &me.#real_field_name });
+                    quote! {
+                        for me in i.clone() {
+                            self.#global_field_name.encode(#offset);
+                        }
+                    }
+                } else {
+                    let offset = offset(field_name);
+                    quote! {
+                        self.#global_field_name.encode(#offset);
+                    }
+                }
+            }
             Self::Encode | Self::EncodeVectored => {
                 let static_type = replace_lifetimes(field_type, "static");
                 let value = if &static_type != field_type {
@@ -74,6 +143,9 @@ impl crate::shared::Item for Item {
             Self::CollectInto => quote! {
                 self.#global_field_name.collect_into(out);
             },
+            Self::CollectIntoFlushing => quote! {
+                self.#global_field_name.collect_into_flushing(out, flush);
+            },
             Self::Reserve => quote! {
                 self.#global_field_name.reserve(__additional);
             },
@@ -110,7 +182,11 @@ impl crate::shared::Item for Item {
                 let variants = encode_variants
                     .then(|| {
                         let private = private();
-                        quote! { variants: #private::VariantEncoder<#variant_count>, }
+                        if variant_count > MAX_U8_VARIANTS {
+                            quote! { variants: #private::WideVariantEncoder<#variant_count>, }
+                        } else {
+                            quote! { variants: #private::VariantEncoder<#variant_count>, }
+                        }
                     })
                     .unwrap_or_default();
                 let inners: TokenStream = (0..variant_count).map(|i| inner(self, i)).collect();
@@ -121,7 +197,7 @@ impl crate::shared::Item for Item {
             }
             Self::Default => {
                 let variants = encode_variants
-                    .then(|| quote! { variants: Default::default(), })
+                    .then(|| quote! { variants: ::core::default::Default::default(), })
                     .unwrap_or_default();
                 let inners: TokenStream = (0..variant_count).map(|i| inner(self, i)).collect();
                 quote! {
@@ -190,10 +266,14 @@ impl crate::shared::Item for Item {
                     self.encode(t);
                 }
             },
-            Self::CollectInto => {
+            Self::CollectInto | Self::CollectIntoFlushing => {
                 let variants = encode_variants
                     .then(|| {
-                        quote! { self.variants.collect_into(out); }
+                        if matches!(self, Self::CollectIntoFlushing) {
+                            quote! { self.variants.collect_into_flushing(out, flush); }
+                        } else {
+                            quote! { self.variants.collect_into(out); }
+                        }
                     })
                     .unwrap_or_default();
                 let inners: TokenStream = (0..variant_count).map(|i| inner(self, i)).collect();
@@ -229,11 +309,62 @@ impl crate::shared::Derive<{ Item::COUNT }> for Encode {
         parse_quote!(#private::Encode)
     }
 
+    fn derive_repr(&self, ident: Ident, repr: &Type, variants: &[&Ident]) -> TokenStream {
+        let private = private();
+        quote! {
+            const _: () = {
+                impl #private::Encode for #ident {
+                    type Encoder = #private::ReprEncoder<Self, #repr>;
+                }
+
+                impl #private::ToRepr<#repr> for #ident {
+                    #[inline(always)]
+                    fn to_repr(&self) -> #repr {
+                        match *self {
+                            #(Self::#variants => Self::#variants as #repr,)*
+                        }
+                    }
+                }
+            };
+        }
+    }
+
+    fn derive_bitflags(&self, ident: Ident) -> TokenStream {
+        let private = private();
+        quote! {
+            const _: () = {
+                type Bits = <#ident as #private::Flags>::Bits;
+
+                impl #private::Encode for #ident {
+                    type Encoder = #private::ReprEncoder<Self, Bits>;
+                }
+
+                impl #private::ToRepr<Bits> for #ident {
+                    #[inline(always)]
+                    fn to_repr(&self) -> Bits {
+                        #private::Flags::bits(self)
+                    }
+                }
+            };
+        }
+    }
+
+    fn derive_convert(&self, ident: &Ident, attrs: &BitcodeAttrs) -> Option<TokenStream> {
+        let private = private();
+        let into = attrs.convert_into()?;
+        Some(quote! {
+            impl #private::Encode for #ident {
+                type Encoder = #private::IntoEncoder<Self, #into>;
+            }
+        })
+    }
+
     fn derive_impl(
         &self,
         output: [TokenStream; Item::COUNT],
         ident: Ident,
         mut generics: Generics,
+        attrs: &BitcodeAttrs,
     ) -> TokenStream {
         let input_generics = generics.clone();
         let (impl_generics, input_generics, where_clause) = input_generics.split_for_impl();
@@ -244,16 +375,21 @@ impl crate::shared::Derive<{ Item::COUNT }> for Encode {
         let (encoder_impl_generics, encoder_generics, encoder_where_clause) =
             generics.split_for_impl();
 
-        let [type_body, default_body, encode_body, encode_vectored_body, collect_into_body, reserve_body] =
+        let [type_body, default_body, encode_body, encode_vectored_body, collect_into_body, collect_into_flushing_body, reserve_body] =
             output;
         let encoder_ident = Ident::new(&format!("{ident}Encoder"), Span::call_site());
         let encoder_ty = quote! { #encoder_ident #encoder_generics };
         let private = private();
+        let encode_encoder_ty = if let Some(version) = attrs.version() {
+            quote! { #private::VersionEncoder<#encoder_ty, #version> }
+        } else {
+            encoder_ty.clone()
+        };
 
         quote! {
             const _: () = {
                 impl #impl_generics #private::Encode for #input_ty #where_clause {
-                    type Encoder = #encoder_ty;
+                    type Encoder = #encode_encoder_ty;
                 }
 
                 #[allow(non_snake_case)]
@@ -274,7 +410,7 @@ impl crate::shared::Derive<{ Item::COUNT }> for Encode {
                     #[cfg_attr(not(debug_assertions), inline(always))]
                     fn encode(&mut self, v: &#input_ty) {
                         #[allow(unused_imports)]
-                        use #private::Buffer as _;
+                        use #private::EncoderBuffer as _;
                         #encode_body
                     }
 
@@ -282,16 +418,20 @@ impl crate::shared::Derive<{ Item::COUNT }> for Encode {
                     // #[inline(never)]
                     fn encode_vectored<'__v>(&mut self, i: impl Iterator<Item = &'__v #input_ty> + Clone) where #input_ty: '__v {
                         #[allow(unused_imports)]
-                        use #private::Buffer as _;
+                        use #private::EncoderBuffer as _;
                         #encode_vectored_body
                     }
                 }
 
-                impl #encoder_impl_generics #private::Buffer for #encoder_ty #encoder_where_clause {
+                impl #encoder_impl_generics #private::EncoderBuffer for #encoder_ty #encoder_where_clause {
                     fn collect_into(&mut self, out: &mut Vec<u8>) {
                         #collect_into_body
                     }
 
+                    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+                        #collect_into_flushing_body
+                    }
+
                     fn reserve(&mut self, __additional: std::num::NonZeroUsize) {
                         #reserve_body
                     }
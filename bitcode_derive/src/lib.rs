@@ -1,4 +1,5 @@
 use crate::decode::Decode;
+use crate::describe::Describe;
 use crate::encode::Encode;
 use crate::shared::Derive;
 use proc_macro::TokenStream;
@@ -9,6 +10,7 @@ use syn::{parse_macro_input, DeriveInput, Error};
 mod attribute;
 mod bound;
 mod decode;
+mod describe;
 mod encode;
 mod shared;
 
@@ -25,6 +27,7 @@ macro_rules! derive {
 }
 derive!(derive_encode, Encode);
 derive!(derive_decode, Decode);
+derive!(derive_describe, Describe);
 
 pub(crate) fn error(spanned: &impl Spanned, s: &str) -> Error {
     Error::new(spanned.span(), s.to_owned())
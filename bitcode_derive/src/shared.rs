@@ -1,17 +1,22 @@
 use crate::attribute::BitcodeAttrs;
 use crate::bound::FieldBounds;
 use crate::err;
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, ToTokens};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::visit_mut::VisitMut;
 use syn::{
-    Data, DataStruct, DeriveInput, Field, Fields, GenericParam, Generics, Index, Lifetime, Path,
-    Result, Type, WherePredicate,
+    parse_quote, Data, DataStruct, DeriveInput, Field, Fields, GenericParam, Generics, Index,
+    Lifetime, Path, Result, Type, TypePath, WherePredicate,
 };
 
-type VariantIndex = u8;
-pub fn variant_index(i: usize) -> VariantIndex {
-    i.try_into().unwrap()
+/// Enums with more variants than this encode their variant indices as `u16`s instead of `u8`s.
+pub const MAX_U8_VARIANTS: usize = u8::MAX as usize + 1;
+const MAX_VARIANTS: usize = u16::MAX as usize + 1;
+
+/// Unsuffixed so it can be a `u8` or `u16` depending on the number of variants.
+pub fn variant_index(i: usize) -> Literal {
+    assert!(i < MAX_VARIANTS);
+    Literal::usize_unsuffixed(i)
 }
 
 pub trait Item: Copy + Sized {
@@ -21,6 +26,7 @@ pub trait Item: Copy + Sized {
         global_field_name: TokenStream,
         real_field_name: TokenStream,
         field_type: &Type,
+        field_attrs: &BitcodeAttrs,
     ) -> TokenStream;
 
     fn struct_impl(
@@ -37,11 +43,17 @@ pub trait Item: Copy + Sized {
         inner: impl Fn(Self, usize) -> TokenStream,
     ) -> TokenStream;
 
-    fn field_impls(self, global_prefix: Option<&str>, fields: &Fields) -> TokenStream {
+    fn field_impls(
+        self,
+        global_prefix: Option<&str>,
+        fields: &Fields,
+        field_attrs: &[BitcodeAttrs],
+    ) -> TokenStream {
         fields
             .iter()
+            .zip(field_attrs)
             .enumerate()
-            .map(move |(i, field)| {
+            .map(move |(i, (field, field_attrs))| {
                 let name = field_name(i, field, false);
                 let real_name = field_name(i, field, true);
                 let global_name = global_prefix
@@ -52,10 +64,33 @@ pub trait Item: Copy + Sized {
                     })
                     .unwrap_or_else(|| name.clone());
 
-                self.field_impl(name, global_name, real_name, &field.ty)
+                self.field_impl(name, global_name, real_name, &field.ty, field_attrs)
             })
             .collect()
     }
+
+    /// Encodes all the fields of an enum variant with `#[bitcode(with = "module")]` as a single
+    /// field containing a tuple of them.
+    fn with_variant_impl(
+        self,
+        global_prefix: &str,
+        fields: &Fields,
+        with_field_attrs: &BitcodeAttrs,
+    ) -> TokenStream {
+        let names = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| field_name(i, f, false));
+        let types = fields.iter().map(|f| &f.ty);
+        let global_name = Ident::new(&format!("{global_prefix}with"), Span::call_site());
+        self.field_impl(
+            quote! { (#(#names,)*) },
+            quote! { #global_name },
+            quote! {},
+            &parse_quote! { (#(#types,)*) },
+            with_field_attrs,
+        )
+    }
 }
 
 pub trait Derive<const ITEM_COUNT: usize> {
@@ -65,16 +100,32 @@ pub trait Derive<const ITEM_COUNT: usize> {
     /// `Encode` in `T: Encode`.
     fn bound(&self) -> Path;
 
-    /// Generates the derive implementation.
+    /// Generates the derive implementation. `attrs` are only used for `validate`, `version` and
+    /// `previous`.
     fn derive_impl(
         &self,
         output: [TokenStream; ITEM_COUNT],
         ident: Ident,
         generics: Generics,
+        attrs: &BitcodeAttrs,
     ) -> TokenStream;
 
+    /// Generates the implementation for a fieldless enum with `#[bitcode(repr = "R")]`, which
+    /// encodes each variant as its discriminant casted to `R`.
+    fn derive_repr(&self, ident: Ident, repr: &Type, variants: &[&Ident]) -> TokenStream;
+
+    /// Generates the implementation for a struct with `#[bitcode(bitflags)]`, which encodes it as
+    /// its `bitflags::Flags::Bits` and rejects unknown bits when decoding.
+    fn derive_bitflags(&self, ident: Ident) -> TokenStream;
+
+    /// Generates the implementation for a type with `#[bitcode(into = "R")]` (`Encode`) or
+    /// `#[bitcode(from = "R")]`/`#[bitcode(try_from = "R")]` (`Decode`), which encodes it as `R`.
+    /// Returns `None` if the type doesn't have the attribute for this derive.
+    fn derive_convert(&self, ident: &Ident, attrs: &BitcodeAttrs) -> Option<TokenStream>;
+
     fn field_attrs(
         &self,
+        ident: &Ident,
         fields: &Fields,
         attrs: &BitcodeAttrs,
         bounds: &mut FieldBounds,
@@ -82,7 +133,8 @@ pub trait Derive<const ITEM_COUNT: usize> {
         fields
             .iter()
             .map(|field| {
-                let field_attrs = BitcodeAttrs::parse_field(&field.attrs, attrs)?;
+                let mut field_attrs = BitcodeAttrs::parse_field(&field.attrs, attrs)?;
+                field_attrs.set_recursive(contains_self(&field.ty, ident));
                 bounds.add_bound_type(field.clone(), &field_attrs, self.bound());
                 Ok(field_attrs)
             })
@@ -95,37 +147,153 @@ pub trait Derive<const ITEM_COUNT: usize> {
         syn::visit_mut::visit_data_mut(&mut ReplaceSelves(&ident), &mut input.data);
         let mut bounds = FieldBounds::default();
 
+        if attrs.transparent() && !matches!(input.data, Data::Struct(_)) {
+            return err(&ident, "transparent can only be applied to structs");
+        }
+
+        if attrs.version().is_some() {
+            if attrs.transparent() || attrs.repr().is_some() || attrs.bitflags() {
+                return err(
+                    &ident,
+                    "version can't be combined with transparent, repr or bitflags",
+                );
+            }
+        } else if attrs.previous().is_some() {
+            return err(&ident, "previous requires version");
+        }
+
+        if attrs.validate().is_some() && (attrs.repr().is_some() || attrs.bitflags()) {
+            return err(&ident, "validate can't be combined with repr or bitflags");
+        }
+
+        if attrs.convert_into().is_some() || attrs.convert_from().is_some() {
+            if attrs.bitflags()
+                || attrs.repr().is_some()
+                || attrs.transparent()
+                || attrs.version().is_some()
+                || attrs.validate().is_some()
+            {
+                let others = "bitflags, repr, transparent, version or validate";
+                return err(
+                    &ident,
+                    &format!("into/from can't be combined with {others}"),
+                );
+            }
+            if !input.generics.params.is_empty() {
+                return err(&input.generics, "into/from types can't be generic");
+            }
+            if let Some(output) = self.derive_convert(&ident, &attrs) {
+                return Ok(output);
+            }
+        }
+
+        if attrs.bitflags() {
+            if !matches!(input.data, Data::Struct(_)) {
+                return err(&ident, "bitflags can only be applied to structs");
+            }
+            if attrs.transparent() {
+                return err(&ident, "bitflags can't be combined with transparent");
+            }
+            if !input.generics.params.is_empty() {
+                return err(&input.generics, "bitflags structs can't be generic");
+            }
+            return Ok(self.derive_bitflags(ident));
+        }
+
+        if let Some(repr) = attrs.repr() {
+            let Data::Enum(data_enum) = &input.data else {
+                return err(&ident, "repr can only be applied to enums");
+            };
+            if !input.generics.params.is_empty() {
+                return err(&input.generics, "repr enums can't be generic");
+            }
+            let variants = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_attrs = BitcodeAttrs::parse_variant(&variant.attrs, &attrs)?;
+                    if !matches!(variant.fields, Fields::Unit) || variant_attrs.with().is_some() {
+                        return err(variant, "repr can only be applied to fieldless enums");
+                    }
+                    Ok(&variant.ident)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // `as` would silently truncate discriminants that don't fit in the repr.
+            let fits = variants.iter().map(|variant| {
+                quote_spanned! {variant.span()=>
+                    const _: () = assert!(
+                        #ident::#variant as #repr as i128 == #ident::#variant as i128,
+                        "discriminant doesn't fit in repr"
+                    );
+                }
+            });
+            let fits = quote! { #(#fits)* };
+            let derived = self.derive_repr(ident, repr, &variants);
+            return Ok(quote! { #fits #derived });
+        }
+
         let output = match input.data {
             Data::Struct(DataStruct { ref fields, .. }) => {
-                // Only used for adding `bounds`. Would be used by `#[bitcode(with_serde)]`.
-                let field_attrs = self.field_attrs(fields, &attrs, &mut bounds)?;
-                let _ = field_attrs;
+                let field_attrs = self.field_attrs(&ident, fields, &attrs, &mut bounds)?;
+                // Skipped fields aren't encoded, so they can come after default fields.
+                if let Some((field, _)) = fields
+                    .iter()
+                    .zip(&field_attrs)
+                    .filter(|(_, a)| a.skip().is_none())
+                    .skip_while(|(_, a)| a.default().is_none())
+                    .find(|(_, a)| a.default().is_none())
+                {
+                    return err(field, "fields after a default field must also be default");
+                }
+                if attrs.transparent() {
+                    if fields.len() != 1 {
+                        return err(&ident, "transparent structs must have exactly one field");
+                    }
+                    if field_attrs[0].context().is_some() {
+                        return err(&ident, "transparent structs can't have a context field");
+                    }
+                    if field_attrs[0].skip().is_some() {
+                        return err(&ident, "transparent structs can't have a skipped field");
+                    }
+                }
 
                 let destructure_fields = &destructure_fields(fields);
                 Self::ALL.map(|item| {
-                    let field_impls = item.field_impls(None, fields);
+                    let field_impls = item.field_impls(None, fields, &field_attrs);
                     item.struct_impl(&ident, destructure_fields, &field_impls)
                 })
             }
             Data::Enum(data_enum) => {
-                let max_variants = VariantIndex::MAX as usize + 1;
-                if data_enum.variants.len() > max_variants {
+                if data_enum.variants.len() > MAX_VARIANTS {
                     return err(
                         &ident,
-                        &format!("enums with more than {max_variants} variants are not supported"),
+                        &format!("enums with more than {MAX_VARIANTS} variants are not supported"),
                     );
                 }
 
-                // Only used for adding `bounds`. Would be used by `#[bitcode(with_serde)]`.
                 let variant_attrs = data_enum
                     .variants
                     .iter()
                     .map(|variant| {
                         let attrs = BitcodeAttrs::parse_variant(&variant.attrs, &attrs)?;
-                        self.field_attrs(&variant.fields, &attrs, &mut bounds)
+                        if let Some(with_field) = attrs.with_field() {
+                            let mut field_attrs = variant.fields.iter().flat_map(|f| &f.attrs);
+                            if let Some(attr) = field_attrs.find(|a| a.path().is_ident("bitcode")) {
+                                return err(attr, "fields of a with variant can't have attributes");
+                            }
+                            return Ok((Some(with_field), vec![]));
+                        }
+                        let field_attrs =
+                            self.field_attrs(&ident, &variant.fields, &attrs, &mut bounds)?;
+                        if let Some(i) = field_attrs.iter().position(|a| a.default().is_some()) {
+                            return err(
+                                &variant.fields.iter().nth(i).unwrap(),
+                                "default can only be applied to struct fields",
+                            );
+                        }
+                        Ok((None, field_attrs))
                     })
                     .collect::<Result<Vec<_>>>()?;
-                let _ = variant_attrs;
 
                 Self::ALL.map(|item| {
                     item.enum_impl(
@@ -141,14 +309,28 @@ pub trait Derive<const ITEM_COUNT: usize> {
                         |item, i| {
                             let variant = &data_enum.variants[i];
                             let global_prefix = format!("{}_", &variant.ident);
-                            item.field_impls(Some(&global_prefix), &variant.fields)
+                            match &variant_attrs[i] {
+                                (Some(with_field), _) => item.with_variant_impl(
+                                    &global_prefix,
+                                    &variant.fields,
+                                    with_field,
+                                ),
+                                (None, field_attrs) => item.field_impls(
+                                    Some(&global_prefix),
+                                    &variant.fields,
+                                    field_attrs,
+                                ),
+                            }
                         },
                     )
                 })
             }
             Data::Union(_) => err(&ident, "unions are not supported")?,
         };
-        Ok(self.derive_impl(output, ident, bounds.added_to(input.generics)))
+        if let Some(bound_types) = attrs.bound() {
+            bounds.replace_with(bound_types.to_vec(), self.bound());
+        }
+        Ok(self.derive_impl(output, ident, bounds.added_to(input.generics), &attrs))
     }
 }
 
@@ -209,6 +391,29 @@ impl VisitMut for ReplaceLifetimes<'_> {
     }
 }
 
+/// If `ty` (a field's type after [`ReplaceSelves`]) contains `ident` (the type being derived). Only
+/// paths that are just `ident` count, so `other::Node` in `Node` isn't recursive.
+fn contains_self(ty: &Type, ident: &Ident) -> bool {
+    let mut find = FindSelf(ident, false);
+    find.visit_type_mut(&mut ty.clone());
+    find.1
+}
+
+struct FindSelf<'a>(&'a Ident, bool);
+impl VisitMut for FindSelf<'_> {
+    fn visit_type_path_mut(&mut self, ty: &mut TypePath) {
+        let path = &ty.path;
+        if ty.qself.is_none()
+            && path.leading_colon.is_none()
+            && path.segments.len() == 1
+            && path.segments[0].ident == *self.0
+        {
+            self.1 = true;
+        }
+        syn::visit_mut::visit_type_path_mut(self, ty);
+    }
+}
+
 struct ReplaceSelves<'a>(pub &'a Ident);
 impl VisitMut for ReplaceSelves<'_> {
     fn visit_ident_mut(&mut self, ident: &mut Ident) {
@@ -166,6 +166,28 @@ bench!(serialize, deserialize, bitcode);
 #[cfg(feature = "derive")]
 bench!(encode, decode, bitcode);
 
+/// Encodes with different [`crate::EncodeOptions::vectored_chunk_len`]s to compare their speed.
+#[cfg(feature = "derive")]
+macro_rules! bench_vectored_chunk_len {
+    ($($n:literal),*) => {
+        paste::paste! {
+            $(
+                #[bench]
+                fn [<bench_bitcode_encode_vectored_chunk_len_ $n>](b: &mut test::Bencher) {
+                    let data = bench_data();
+                    let options = crate::EncodeOptions::new().vectored_chunk_len($n);
+                    let mut buffer = crate::Buffer::new();
+                    b.iter(|| {
+                        black_box(buffer.encode_with_options(black_box(&data), &options));
+                    })
+                }
+            )*
+        }
+    }
+}
+#[cfg(feature = "derive")]
+bench_vectored_chunk_len!(1, 4, 16, 64);
+
 #[cfg(test)]
 mod tests {
     use super::*;
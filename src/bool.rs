@@ -1,4 +1,4 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::fast::{CowSlice, NextUnchecked, PushUnchecked, VecImpl};
 use crate::pack::{pack_bools, unpack_bools};
 use std::num::NonZeroUsize;
@@ -18,7 +18,7 @@ impl Encoder<bool> for BoolEncoder {
     }
 }
 
-impl Buffer for BoolEncoder {
+impl EncoderBuffer for BoolEncoder {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         pack_bools(self.0.as_slice(), out);
         self.0.clear();
@@ -61,7 +61,7 @@ mod test {
     fn bench_data() -> Vec<bool> {
         (0..=1000).map(|_| false).collect()
     }
-    crate::bench_encode_decode!(bool_vec: Vec<_>);
+    crate::bench_encode_decode!(bool_vec: Vec<bool>);
 }
 
 #[cfg(test)]
@@ -75,5 +75,5 @@ mod test2 {
             })
             .collect()
     }
-    crate::bench_encode_decode!(bool_vecs: Vec<Vec<_>>);
+    crate::bench_encode_decode!(bool_vecs: Vec<Vec<bool>>);
 }
@@ -1,6 +1,9 @@
 use std::any::TypeId;
 
-/// A buffer for reusing allocations between calls to [`Buffer::encode`] and/or [`Buffer::decode`].
+/// A buffer for reusing allocations between calls to [`Buffer::encode`],
+/// [`Buffer::encode_with_options`] and/or [`Buffer::decode`]. It holds the encoders/decoders of
+/// every type it has been used with, so their allocations are reused too. Each section keeps the
+/// capacity of the largest message so far, so steady-state traffic doesn't allocate.
 /// TODO Send + Sync
 ///
 /// ```rust
@@ -74,7 +77,7 @@ impl Registry {
 
 /// Ignores lifetimes in `T` when determining its [`TypeId`].
 /// https://github.com/rust-lang/rust/issues/41875#issuecomment-317292888
-fn non_static_type_id<T: ?Sized>() -> TypeId {
+pub(crate) fn non_static_type_id<T: ?Sized>() -> TypeId {
     use std::marker::PhantomData;
     trait NonStaticAny {
         fn get_type_id(&self) -> TypeId
@@ -143,6 +146,68 @@ mod tests {
         assert_eq!(b.decode::<bool>(&[1]).unwrap(), true);
     }
 
+    #[test]
+    fn steady_state() {
+        let mut b = Buffer::new();
+        let message = |n: usize| -> Vec<(u32, String)> {
+            (0..n as u32).map(|i| (i, i.to_string())).collect()
+        };
+        b.encode(&message(1000));
+        let ptr = b.out.as_ptr();
+        for n in [1000, 10, 500, 999] {
+            b.encode(&message(n));
+            assert_eq!(b.out.as_ptr(), ptr);
+        }
+    }
+
+    #[test]
+    fn encode_append() {
+        let mut b = Buffer::new();
+        let message =
+            |n: u32| -> Vec<(u32, String)> { (0..n).map(|i| (i, i.to_string())).collect() };
+        let mut out = vec![];
+        for n in [1000, 10, 500] {
+            out.clear();
+            out.push(0xFF); // Header.
+            b.encode_append(&message(n), &mut out);
+            assert_eq!(out[1..], *b.encode(&message(n)));
+            assert_eq!(
+                crate::decode::<Vec<(u32, String)>>(&out[1..]).unwrap(),
+                message(n)
+            );
+        }
+    }
+
+    #[test]
+    fn decode_append() {
+        let mut b = Buffer::new();
+        let mut out = vec!["header".to_owned()];
+        for n in [1000, 0, 10, 999] {
+            let message: Vec<String> = (0..n).map(|i: u32| i.to_string()).collect();
+            out.truncate(1);
+            b.decode_append(&mut out, &crate::encode(&message)).unwrap();
+            assert_eq!(out[0], "header");
+            assert_eq!(out[1..], message);
+        }
+        let ptr = out.as_ptr();
+        let encoded = crate::encode(&vec!["a"; 10]);
+        for _ in 0..10 {
+            out.clear();
+            b.decode_append(&mut out, &encoded).unwrap();
+            assert_eq!(out.as_ptr(), ptr);
+        }
+
+        // Errors don't modify out.
+        assert!(b
+            .decode_append(&mut out, &encoded[..encoded.len() - 1])
+            .is_err());
+        assert_eq!(out.len(), 10);
+        let mut primitives = vec![1u16];
+        b.decode_append(&mut primitives, &crate::encode(&vec![2u16, 3]))
+            .unwrap();
+        assert_eq!(primitives, [1, 2, 3]);
+    }
+
     #[test]
     fn registry() {
         let mut r = Registry::default();
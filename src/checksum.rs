@@ -0,0 +1,110 @@
+use crate::error::{err_kind, ErrorKind};
+use crate::{Decode, Encode, Error};
+
+/// Number of bytes in the checksum appended by [`encode_checked`].
+const CHECKSUM_LEN: usize = 4;
+
+/// Encodes a `T:` [`Encode`] like [`encode`][`crate::encode`] followed by a CRC-32C checksum of
+/// the encoding (as a 4 byte little endian integer), which [`decode_checked`] verifies before
+/// decoding. Meant for data that's stored for a long time, so corruption is reported as an
+/// [`ErrorKind::Checksum`] instead of decoding garbage or failing in some other way.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// use bitcode::ErrorKind;
+///
+/// let mut encoded = bitcode::encode_checked(&vec![1u32, 2, 3]);
+/// assert_eq!(bitcode::decode_checked::<Vec<u32>>(&encoded).unwrap(), [1, 2, 3]);
+///
+/// encoded[1] ^= 1; // Flip a bit.
+/// let error = bitcode::decode_checked::<Vec<u32>>(&encoded).unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::Checksum);
+/// ```
+pub fn encode_checked<T: Encode + ?Sized>(t: &T) -> Vec<u8> {
+    let mut out = vec![];
+    crate::Buffer::new().encode_append(t, &mut out);
+    let checksum = crc32c(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+}
+
+/// Decodes the output of [`encode_checked`] into a `T:` [`Decode`], returning an
+/// [`ErrorKind::Checksum`] error if the checksum doesn't match.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn decode_checked<'a, T: Decode<'a>>(bytes: &'a [u8]) -> Result<T, Error> {
+    crate::decode(verify(bytes)?)
+}
+
+/// Returns the encoding in `bytes` if its checksum matches.
+fn verify(bytes: &[u8]) -> Result<&[u8], Error> {
+    let Some(split) = bytes.len().checked_sub(CHECKSUM_LEN) else {
+        return err_kind(ErrorKind::Eof, "EOF");
+    };
+    let (encoded, checksum) = bytes.split_at(split);
+    if crc32c(encoded).to_le_bytes() != checksum {
+        return err_kind(ErrorKind::Checksum, "checksum mismatch");
+    }
+    Ok(encoded)
+}
+
+/// CRC-32C (Castagnoli), which has better error detection than the original CRC-32.
+fn crc32c(bytes: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0x82F63B78 // Reversed polynomial.
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+    !bytes.iter().fold(!0, |crc, &b| {
+        (crc >> 8) ^ TABLE[((crc ^ b as u32) & 0xFF) as usize]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32c, decode_checked, encode_checked};
+    use crate::{encode, ErrorKind};
+
+    #[test]
+    fn crc32c_check_value() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xE3069283);
+    }
+
+    #[test]
+    fn checked() {
+        let v: Vec<(u16, String)> = (0..100).map(|i| (i, i.to_string())).collect();
+        let encoded = encode_checked(&v);
+        assert_eq!(&encoded[..encoded.len() - 4], encode(&v));
+        assert_eq!(decode_checked::<Vec<(u16, String)>>(&encoded).unwrap(), v);
+
+        // Every single bit flip is detected.
+        for i in 0..encoded.len() * 8 {
+            let mut corrupted = encoded.clone();
+            corrupted[i / 8] ^= 1 << (i % 8);
+            let error = decode_checked::<Vec<(u16, String)>>(&corrupted).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::Checksum);
+        }
+
+        let error = decode_checked::<Vec<(u16, String)>>(&encoded[..3]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        let truncated = &encoded[1..];
+        assert!(decode_checked::<Vec<(u16, String)>>(truncated).is_err());
+        assert_eq!(decode_checked::<()>(&encode_checked(&())).unwrap(), ());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::stream::{DEFAULT_MAX_FRAME_LEN, PREFIX_LEN};
+use crate::{Buffer, DecodeOwned, Encode};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use std::marker::PhantomData;
+
+/// A [`tokio_util::codec`] that encodes/decodes `T`s as frames (in the format of
+/// [`encode_frame`][`crate::encode_frame`]), e.g. for sending them over a TCP stream with
+/// [`Framed`](tokio_util::codec::Framed).
+///
+/// Frames larger than [`BitcodeCodec::max_frame_len`] are rejected before they're buffered, so
+/// a peer can't make the decoder allocate arbitrary amounts of memory.
+///
+/// ```rust
+/// use bitcode::BitcodeCodec;
+/// use bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut codec = BitcodeCodec::<String>::new();
+/// let mut bytes = BytesMut::new();
+/// codec.encode("Hello world!".to_owned(), &mut bytes).unwrap();
+/// assert_eq!(codec.decode(&mut bytes).unwrap().unwrap(), "Hello world!");
+/// assert!(codec.decode(&mut bytes).unwrap().is_none());
+/// ```
+pub struct BitcodeCodec<T> {
+    buffer: Buffer,
+    max_frame_len: usize,
+    _spooky: PhantomData<fn(T) -> T>,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T> Default for BitcodeCodec<T> {
+    fn default() -> Self {
+        Self {
+            buffer: Default::default(),
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            _spooky: PhantomData,
+        }
+    }
+}
+
+impl<T> BitcodeCodec<T> {
+    /// Constructs a new codec with a maximum frame length of 8 MiB.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum length of a frame (excluding its length prefix). Larger frames can't be
+    /// encoded or decoded.
+    pub fn max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+}
+
+fn frame_too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "frame too large")
+}
+
+impl<T: Encode> tokio_util::codec::Encoder<T> for BitcodeCodec<T> {
+    type Error = io::Error;
+
+    fn encode(&mut self, t: T, dst: &mut BytesMut) -> io::Result<()> {
+        let encoded = self.buffer.encode(&t);
+        if encoded.len() > self.max_frame_len {
+            return Err(frame_too_large());
+        }
+        let len = u32::try_from(encoded.len()).map_err(|_| frame_too_large())?;
+        dst.reserve(PREFIX_LEN + encoded.len());
+        dst.put_u32_le(len);
+        dst.extend_from_slice(encoded);
+        Ok(())
+    }
+}
+
+impl<T: DecodeOwned> tokio_util::codec::Decoder for BitcodeCodec<T> {
+    type Item = T;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<T>> {
+        let Some(prefix) = src.get(..PREFIX_LEN) else {
+            return Ok(None);
+        };
+        let len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+        if len > self.max_frame_len {
+            return Err(frame_too_large());
+        }
+        if src.len() < PREFIX_LEN + len {
+            src.reserve(PREFIX_LEN + len - src.len());
+            return Ok(None);
+        }
+        src.advance(PREFIX_LEN);
+        let frame = src.split_to(len);
+        let decoded = self.buffer.decode(&frame);
+        decoded
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_frame;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[test]
+    fn codec() {
+        let values: Vec<Vec<(u16, String)>> = (0..10)
+            .map(|i| (0..i).map(|j: u16| (j, "a".repeat(j as usize))).collect())
+            .collect();
+        let mut codec = BitcodeCodec::new();
+        let mut bytes = BytesMut::new();
+        for v in &values {
+            codec.encode(v.clone(), &mut bytes).unwrap();
+        }
+        let expected: Vec<u8> = values.iter().flat_map(encode_frame).collect();
+        assert_eq!(bytes, expected);
+
+        // Feed a byte at a time.
+        let mut src = BytesMut::new();
+        let mut decoded = vec![];
+        for &b in &expected {
+            src.put_u8(b);
+            while let Some(v) = codec.decode(&mut src).unwrap() {
+                decoded.push(v);
+            }
+        }
+        assert_eq!(decoded, values);
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn max_frame_len() {
+        let mut codec = BitcodeCodec::<String>::new().max_frame_len(10);
+        let mut bytes = BytesMut::new();
+        assert!(codec.encode("a".repeat(100), &mut bytes).is_err());
+        assert!(bytes.is_empty());
+
+        // Rejected as soon as the prefix arrives.
+        let frame = encode_frame(&"a".repeat(100));
+        let mut src = BytesMut::from(&frame[..PREFIX_LEN]);
+        assert!(codec.decode(&mut src).is_err());
+
+        let mut src = BytesMut::from(&encode_frame("abc")[..]);
+        assert_eq!(codec.decode(&mut src).unwrap().unwrap(), "abc");
+    }
+
+    #[test]
+    fn invalid_frame() {
+        let mut src = BytesMut::from(&2u32.to_le_bytes()[..]);
+        src.extend_from_slice(&[0, 0]); // A bool with a trailing byte.
+        src.extend_from_slice(&encode_frame(&true));
+
+        let mut codec = BitcodeCodec::<bool>::new();
+        assert!(codec.decode(&mut src).is_err());
+        assert_eq!(codec.decode(&mut src).unwrap(), Some(true));
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+    }
+}
@@ -4,8 +4,9 @@ use std::num::NonZeroUsize;
 
 pub type Result<T> = std::result::Result<T, crate::Error>;
 
-/// TODO pick different name because it aliases with [`crate::buffer::Buffer`].
-pub trait Buffer {
+/// The buffers of an [`Encoder`]. Not to be confused with [`crate::Buffer`] which is the public API
+/// for reusing these buffers (and decoders) between calls.
+pub trait EncoderBuffer {
     /// Convenience function for `collect_into`.
     fn collect(&mut self) -> Vec<u8> {
         let mut vec = vec![];
@@ -16,6 +17,15 @@ pub trait Buffer {
     /// Collects the buffer into a single `Vec<u8>`. This clears the buffer.
     fn collect_into(&mut self, out: &mut Vec<u8>);
 
+    /// Like `collect_into`, but calls `flush` after collecting each inner buffer, so `flush` can
+    /// take the bytes out of `out` before the next one is collected. Only buffers made of other
+    /// buffers (e.g. structs, tuples, collections and wrappers like `Box`'s) need to override this,
+    /// otherwise their inner buffers are collected all at once.
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.collect_into(out);
+        flush(out);
+    }
+
     /// Reserves space for `additional` calls to `self.encode()`. Takes a [`NonZeroUsize`] to avoid
     /// useless calls.
     fn reserve(&mut self, additional: NonZeroUsize);
@@ -24,7 +34,7 @@ pub trait Buffer {
 /// Iterators passed to [`Encoder::encode_vectored`] must have length <= this.
 pub const MAX_VECTORED_CHUNK: usize = 64;
 
-pub trait Encoder<T: ?Sized>: Buffer + Default {
+pub trait Encoder<T: ?Sized>: EncoderBuffer + Default {
     /// Returns a `VecImpl<T>` if `T` is a type that can be encoded by copying.
     #[inline(always)]
     fn as_primitive(&mut self) -> Option<&mut VecImpl<T>>
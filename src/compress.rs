@@ -0,0 +1,301 @@
+#[cfg(feature = "zstd")]
+use crate::coder::{Encoder, EncoderBuffer};
+use crate::error::{err, err_kind, error, ErrorKind};
+use crate::{DecodeOwned, Encode, Error};
+
+/// A compression algorithm for [`encode_compressed`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// zstd with a compression `level` (e.g. 3). Compresses better than [`Compression::Lz4`].
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+    /// LZ4, which is much faster than [`Compression::Zstd`] but compresses less.
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+// The first byte of the output of encode_compressed.
+const ZSTD: u8 = 0;
+const LZ4: u8 = 1;
+
+const DEFAULT_MAX_DECODED_LEN: usize = 8 * 1024 * 1024;
+
+/// Collected columns are passed to the compression thread in pieces of at least this many bytes.
+#[cfg(feature = "zstd")]
+const PIPELINE_SIZE: usize = 1 << 18;
+
+/// Encodes a `T:` [`Encode`] and compresses it with `compression`. Since values of the same field
+/// are encoded next to each other, the encoding usually compresses well.
+///
+/// The output is a byte identifying the algorithm followed by the compressed encoding, so
+/// [`decode_compressed`] doesn't need to be told which one was used.
+///
+/// With `Compression::Zstd`, columns are compressed on another thread as soon as they're
+/// collected while the remaining columns are being collected, so a multi-megabyte value takes
+/// closer to the longer of the two than to their sum. Values that collect into fewer than 256 KiB
+/// are compressed on the calling thread. LZ4's block format needs the whole encoding at once, so
+/// `Compression::Lz4` compresses it after it's collected.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// use bitcode::Compression;
+///
+/// let names: Vec<String> = (0..1000).map(|i| format!("player_{}", i % 10)).collect();
+/// # #[cfg(all(feature = "zstd", not(miri)))] // Miri can't call zstd's C functions.
+/// let compression = Compression::Zstd { level: 3 };
+/// # #[cfg(not(all(feature = "zstd", not(miri))))]
+/// # let compression = Compression::Lz4;
+/// let compressed = bitcode::encode_compressed(&names, compression);
+/// assert!(compressed.len() < bitcode::encode(&names).len() / 10);
+/// assert_eq!(bitcode::decode_compressed::<Vec<String>>(&compressed).unwrap(), names);
+/// ```
+pub fn encode_compressed<T: Encode + ?Sized>(t: &T, compression: Compression) -> Vec<u8> {
+    match compression {
+        #[cfg(feature = "zstd")]
+        Compression::Zstd { level } => encode_zstd(t, level),
+        #[cfg(feature = "lz4")]
+        Compression::Lz4 => {
+            let compressed = lz4_flex::block::compress_prepend_size(&crate::encode(t));
+            [&[LZ4], compressed.as_slice()].concat()
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn encode_zstd<T: Encode + ?Sized>(t: &T, level: i32) -> Vec<u8> {
+    use std::io::Write;
+    use std::sync::mpsc;
+    const EXPECT: &str = "writing to a Vec can't fail";
+
+    let mut encoder = T::Encoder::default();
+    encoder.reserve(std::num::NonZeroUsize::MIN);
+    encoder.encode(t);
+
+    std::thread::scope(|scope| {
+        // Spawned once the first piece is collected, so small values don't pay for a thread.
+        let mut worker = None;
+        // Pieces are sent back once compressed so their allocations can be reused.
+        let (recycle_sender, recycled) = mpsc::channel::<Vec<u8>>();
+        let mut out = vec![];
+        encoder.collect_into_flushing(&mut out, &mut |out| {
+            if out.len() < PIPELINE_SIZE {
+                return;
+            }
+            let (sender, _) = worker.get_or_insert_with(|| {
+                // Holds 1 piece while another is being compressed and a third is being collected.
+                let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(1);
+                let recycle_sender = recycle_sender.clone();
+                let handle = scope.spawn(move || {
+                    let mut zstd =
+                        zstd::stream::write::Encoder::new(vec![ZSTD], level).expect(EXPECT);
+                    for mut piece in receiver {
+                        zstd.write_all(&piece).expect(EXPECT);
+                        piece.clear();
+                        let _ = recycle_sender.send(piece);
+                    }
+                    zstd.finish().expect(EXPECT)
+                });
+                (sender, handle)
+            });
+            let next = recycled.try_recv().unwrap_or_default();
+            // Only fails if the thread panicked, which join resumes below.
+            let _ = sender.send(std::mem::replace(out, next));
+        });
+
+        if let Some((sender, handle)) = worker {
+            let _ = sender.send(out);
+            drop(sender); // Ends the thread's loop.
+            return handle
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e));
+        }
+        let mut compressed = vec![ZSTD];
+        compressed.reserve(zstd::zstd_safe::compress_bound(out.len()));
+        zstd::stream::copy_encode(out.as_slice(), &mut compressed, level).expect(EXPECT);
+        compressed
+    })
+}
+
+/// Decompresses the output of [`encode_compressed`] and decodes it into a `T:` [`DecodeOwned`].
+/// Returns an [`ErrorKind::Unsupported`] error if it was compressed with an algorithm whose
+/// feature isn't enabled.
+///
+/// Inputs that decompress to more than 8 MiB are rejected, see
+/// [`decode_compressed_with_max_len`].
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn decode_compressed<T: DecodeOwned>(compressed: &[u8]) -> Result<T, Error> {
+    decode_compressed_with_max_len(compressed, DEFAULT_MAX_DECODED_LEN)
+}
+
+/// Like [`decode_compressed`] but inputs that decompress to more than `max_decoded_len` bytes
+/// return an [`ErrorKind::LimitExceeded`] error instead of ones over 8 MiB. Decompression stops
+/// once the limit is exceeded, so a small input can't make it allocate more than that.
+pub fn decode_compressed_with_max_len<T: DecodeOwned>(
+    compressed: &[u8],
+    max_decoded_len: usize,
+) -> Result<T, Error> {
+    let Some((&algorithm, compressed)) = compressed.split_first() else {
+        return err_kind(ErrorKind::Eof, "EOF");
+    };
+    let decompressed = match algorithm {
+        ZSTD => decompress_zstd(compressed, max_decoded_len)?,
+        LZ4 => decompress_lz4(compressed, max_decoded_len)?,
+        _ => return err("invalid compression"),
+    };
+    crate::decode(&decompressed)
+}
+
+#[cfg(feature = "zstd")]
+fn decompress_zstd(compressed: &[u8], max_decoded_len: usize) -> Result<Vec<u8>, Error> {
+    use std::io::Read;
+    let too_large = || err_kind(ErrorKind::LimitExceeded, "zstd frame too large");
+    // Frames encoded by encode_compressed don't have a content size, but others might.
+    match zstd::zstd_safe::get_frame_content_size(compressed) {
+        Ok(Some(len)) if len > max_decoded_len as u64 => return too_large(),
+        Ok(_) => {}
+        Err(_) => return err("invalid zstd frame"),
+    }
+    let invalid = |_| error("invalid zstd frame");
+    let decoder = zstd::stream::read::Decoder::with_buffer(compressed).map_err(invalid)?;
+    let mut out = vec![];
+    // Reads one byte past the limit to tell a frame that's exactly max_decoded_len long apart
+    // from a longer one.
+    let limit = (max_decoded_len as u64).saturating_add(1);
+    decoder.take(limit).read_to_end(&mut out).map_err(invalid)?;
+    if out.len() > max_decoded_len {
+        return too_large();
+    }
+    Ok(out)
+}
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
+    err_kind(ErrorKind::Unsupported, "zstd feature not enabled")
+}
+
+#[cfg(feature = "lz4")]
+fn decompress_lz4(compressed: &[u8], max_decoded_len: usize) -> Result<Vec<u8>, Error> {
+    let invalid = |_| error("invalid lz4 block");
+    let (len, block) = lz4_flex::block::uncompressed_size(compressed).map_err(invalid)?;
+    // A byte of a block decompresses into at most 255 bytes, so a block claiming a larger length
+    // is invalid and can't make us allocate more than that.
+    if len > block.len().saturating_mul(255) {
+        return err("invalid lz4 block");
+    }
+    if len > max_decoded_len {
+        return err_kind(ErrorKind::LimitExceeded, "lz4 block too large");
+    }
+    let mut out = vec![0; len];
+    if lz4_flex::block::decompress_into(block, &mut out).map_err(invalid)? != len {
+        return err("invalid lz4 block");
+    }
+    Ok(out)
+}
+#[cfg(not(feature = "lz4"))]
+fn decompress_lz4(_: &[u8], _: usize) -> Result<Vec<u8>, Error> {
+    err_kind(ErrorKind::Unsupported, "lz4 feature not enabled")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compressions() -> Vec<Compression> {
+        vec![
+            // Miri can't call zstd's C functions.
+            #[cfg(all(feature = "zstd", not(miri)))]
+            Compression::Zstd { level: 3 },
+            #[cfg(all(feature = "zstd", not(miri)))]
+            Compression::Zstd { level: 19 },
+            #[cfg(feature = "lz4")]
+            Compression::Lz4,
+        ]
+    }
+
+    #[test]
+    fn compressed() {
+        type T = Vec<(u32, String, bool)>;
+        let values: T = (0..1000)
+            .map(|i| (i % 7, format!("value {}", i % 13), i % 3 == 0))
+            .collect();
+        let encoded = crate::encode(&values);
+        for compression in compressions() {
+            let compressed = encode_compressed(&values, compression);
+            assert!(compressed.len() < encoded.len() / 2, "{compression:?}");
+            assert_eq!(decode_compressed::<T>(&compressed).unwrap(), values);
+            assert!(decode_compressed::<T>(&compressed[..compressed.len() - 1]).is_err());
+            assert_eq!(
+                decode_compressed::<()>(&encode_compressed(&(), compression)),
+                Ok(())
+            );
+        }
+        assert_eq!(
+            decode_compressed::<T>(&[]).unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+        assert!(decode_compressed::<T>(&[2, 0]).is_err());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // Too slow.
+    fn bomb() {
+        // Repeats every byte so it isn't bit packed but compresses very well.
+        let bytes: Vec<u8> = (0..DEFAULT_MAX_DECODED_LEN).map(|i| i as u8).collect();
+        let encoded_len = crate::encode(&bytes).len();
+        assert!(encoded_len > DEFAULT_MAX_DECODED_LEN);
+        for compression in compressions() {
+            let compressed = encode_compressed(&bytes, compression);
+            assert!(compressed.len() < encoded_len / 200, "{compression:?}");
+            let decode = |max_len| decode_compressed_with_max_len::<Vec<u8>>(&compressed, max_len);
+            let err = decode_compressed::<Vec<u8>>(&compressed).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::LimitExceeded, "{compression:?}");
+            assert!(decode(encoded_len).unwrap() == bytes, "{compression:?}");
+            let err = decode(encoded_len - 1).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::LimitExceeded, "{compression:?}");
+        }
+    }
+
+    #[cfg(all(feature = "zstd", not(miri)))]
+    #[test]
+    fn zstd_pipelined() {
+        // Several columns that are each larger than PIPELINE_SIZE.
+        type T = Vec<(u64, String)>;
+        let values: T = (0..100000u64)
+            .map(|i| (i.wrapping_mul(0x9E3779B97F4A7C15), format!("value {i}")))
+            .collect();
+        let encoded = crate::encode(&values);
+        assert!(encoded.len() > 4 * PIPELINE_SIZE);
+
+        let compressed = encode_compressed(&values, Compression::Zstd { level: 3 });
+        assert_eq!(zstd::decode_all(&compressed[1..]).unwrap(), encoded);
+        let decoded = decode_compressed_with_max_len::<T>(&compressed, encoded.len()).unwrap();
+        assert!(decoded == values);
+    }
+
+    #[cfg(all(feature = "zstd", not(miri)))]
+    #[test]
+    fn zstd_content_size() {
+        // Unlike encode_compressed's, this frame says how long it is so it's rejected up front.
+        let encoded = crate::encode(&vec![0u8; 1000]);
+        let frame = zstd::bulk::compress(&encoded, 3).unwrap();
+        assert!(matches!(
+            zstd::zstd_safe::get_frame_content_size(&frame),
+            Ok(Some(_))
+        ));
+        let compressed = [&[ZSTD], frame.as_slice()].concat();
+        let decode = |max_len| decode_compressed_with_max_len::<Vec<u8>>(&compressed, max_len);
+        assert_eq!(decode(encoded.len()).unwrap(), vec![0u8; 1000]);
+        let err = decode(encoded.len() - 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4_huge_len() {
+        // Claims to decompress into u32::MAX bytes.
+        let compressed = [&[LZ4], u32::MAX.to_le_bytes().as_slice(), &[0x10, 0]].concat();
+        assert!(decode_compressed::<Vec<u8>>(&compressed).is_err());
+    }
+}
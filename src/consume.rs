@@ -1,10 +1,10 @@
-use crate::coder::Result;
-use crate::error::{err, error};
+use crate::coder::{Result, View};
+use crate::error::{err_kind, error_kind, ErrorKind};
 
 /// Attempts to claim `bytes` bytes out of `input`.
 pub fn consume_bytes<'a>(input: &mut &'a [u8], bytes: usize) -> Result<&'a [u8]> {
     if bytes > input.len() {
-        return err("EOF");
+        return err_kind(ErrorKind::Eof, "EOF");
     }
     let (bytes, remaining) = input.split_at(bytes);
     *input = remaining;
@@ -23,7 +23,7 @@ pub fn consume_byte_arrays<'a, const N: usize>(
 ) -> Result<&'a [[u8; N]]> {
     // Avoid * overflow by using / instead.
     if input.len() / N < length {
-        return err("EOF");
+        return err_kind(ErrorKind::Eof, "EOF");
     }
 
     // Safety: input.len() >= mid since we've checked it above.
@@ -34,10 +34,19 @@ pub fn consume_byte_arrays<'a, const N: usize>(
     Ok(bytemuck::cast_slice(bytes))
 }
 
+/// Populates `view` with `length` values from all of `input`, adding the offset where it failed
+/// to any error.
+pub fn populate_all<'a>(view: &mut impl View<'a>, input: &'a [u8], length: usize) -> Result<()> {
+    let mut remaining = input;
+    view.populate(&mut remaining, length)
+        .and_then(|()| expect_eof(remaining))
+        .map_err(|e| e.at_offset(input, remaining))
+}
+
 /// Check if `input` is empty or return error.
 pub fn expect_eof(input: &[u8]) -> Result<()> {
     if cfg!(not(fuzzing)) && !input.is_empty() {
-        err("Expected EOF")
+        err_kind(ErrorKind::TrailingBytes, "Expected EOF")
     } else {
         Ok(())
     }
@@ -47,5 +56,5 @@ pub fn expect_eof(input: &[u8]) -> Result<()> {
 pub fn mul_length(length: usize, x: usize) -> Result<usize> {
     length
         .checked_mul(x)
-        .ok_or_else(|| error("length overflow"))
+        .ok_or_else(|| error_kind(ErrorKind::LengthOverflow, "length overflow"))
 }
@@ -1,4 +1,4 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::consume::mul_length;
 use crate::derive::{Decode, Encode};
 use std::mem::MaybeUninit;
@@ -24,11 +24,15 @@ impl<T: Encode, const N: usize> Encoder<[T; N]> for ArrayEncoder<T, N> {
     }
 }
 
-impl<T: Encode, const N: usize> Buffer for ArrayEncoder<T, N> {
+impl<T: Encode, const N: usize> EncoderBuffer for ArrayEncoder<T, N> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         self.0.collect_into(out);
     }
 
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+
     fn reserve(&mut self, additional: NonZeroUsize) {
         if N == 0 {
             return; // self.0.reserve takes NonZeroUsize and `additional * N == 0`.
@@ -0,0 +1,129 @@
+use crate::coder::{Encoder, EncoderBuffer};
+use crate::derive::smart_ptr::FromDecoder;
+use crate::derive::{Decode, Encode};
+use std::cell::{Cell, RefCell};
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, PoisonError, RwLock};
+
+/// Encodes the value inside a [`Cell`], [`RefCell`], [`Mutex`] or [`RwLock`] by getting, borrowing
+/// or locking it.
+///
+/// **Panics** if a `RefCell` is mutably borrowed. Deadlocks if a `Mutex`/`RwLock` is locked by the
+/// encoding thread. Poisoned locks are encoded anyway.
+pub struct CellEncoder<T: Encode + ?Sized>(T::Encoder);
+
+// Can't derive since it would bound T: Default.
+impl<T: Encode + ?Sized> Default for CellEncoder<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<T: Encode + ?Sized> EncoderBuffer for CellEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+impl<T: Encode + Copy> Encoder<Cell<T>> for CellEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Cell<T>) {
+        self.0.encode(&t.get());
+    }
+}
+impl<T: Encode + ?Sized> Encoder<RefCell<T>> for CellEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &RefCell<T>) {
+        self.0.encode(&t.borrow());
+    }
+}
+impl<T: Encode + ?Sized> Encoder<Mutex<T>> for CellEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Mutex<T>) {
+        self.0
+            .encode(&t.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+}
+impl<T: Encode + ?Sized> Encoder<RwLock<T>> for CellEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &RwLock<T>) {
+        self.0
+            .encode(&t.read().unwrap_or_else(PoisonError::into_inner));
+    }
+}
+
+impl<T: Encode + Copy> Encode for Cell<T> {
+    type Encoder = CellEncoder<T>;
+}
+impl<'a, T: Decode<'a>> Decode<'a> for Cell<T> {
+    type Decoder = FromDecoder<'a, T>;
+}
+
+macro_rules! impl_cell {
+    ($($t:ident),+) => {
+        $(
+            impl<T: Encode + ?Sized> Encode for $t<T> {
+                type Encoder = CellEncoder<T>;
+            }
+            impl<'a, T: Decode<'a>> Decode<'a> for $t<T> {
+                type Decoder = FromDecoder<'a, T>;
+            }
+        )+
+    };
+}
+impl_cell!(RefCell, Mutex, RwLock);
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+    use std::cell::{Cell, RefCell};
+    use std::sync::{Mutex, RwLock};
+
+    #[derive(Encode, Decode)]
+    struct Config {
+        a: Cell<u32>,
+        b: RefCell<String>,
+        c: Mutex<Vec<u8>>,
+        d: RwLock<Option<bool>>,
+    }
+
+    #[test]
+    fn cell() {
+        let config = Config {
+            a: Cell::new(5),
+            b: RefCell::new("b".into()),
+            c: Mutex::new(vec![1, 2, 3]),
+            d: RwLock::new(Some(true)),
+        };
+        let encoded = encode(&config);
+        assert_eq!(encoded, encode(&(5u32, "b", vec![1u8, 2, 3], Some(true))));
+
+        let decoded: Config = decode(&encoded).unwrap();
+        assert_eq!(decoded.a.get(), 5);
+        assert_eq!(*decoded.b.borrow(), "b");
+        assert_eq!(*decoded.c.lock().unwrap(), [1, 2, 3]);
+        assert_eq!(*decoded.d.read().unwrap(), Some(true));
+
+        // Poisoned locks are still encoded.
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = config.c.lock().unwrap();
+            panic!("poison");
+        }));
+        assert!(config.c.is_poisoned());
+        assert_eq!(encode(&config), encoded);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mutably_borrowed() {
+        let cell = RefCell::new(0u8);
+        let _borrow = cell.borrow_mut();
+        encode(&cell);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::variant::{VariantDecoder, VariantEncoder};
+use crate::derive::{Decode, Encode};
+use std::cmp::{Ordering, Reverse};
+use std::mem::MaybeUninit;
+use std::num::NonZeroUsize;
+
+/// Encodes `Reverse<T>` exactly like `T`.
+#[derive(Debug)]
+pub struct ReverseEncoder<T: Encode>(T::Encoder);
+
+// Can't derive since it would bound T: Default.
+impl<T: Encode> Default for ReverseEncoder<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<T: Encode> Encoder<Reverse<T>> for ReverseEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Reverse<T>) {
+        self.0.encode(&t.0);
+    }
+}
+
+impl<T: Encode> EncoderBuffer for ReverseEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+#[derive(Debug)]
+pub struct ReverseDecoder<'a, T: Decode<'a>>(T::Decoder);
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: Decode<'a>> Default for ReverseDecoder<'a, T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<'a, T: Decode<'a>> View<'a> for ReverseDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0.populate(input, length)
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, Reverse<T>> for ReverseDecoder<'a, T> {
+    #[inline(always)]
+    fn decode_in_place(&mut self, out: &mut MaybeUninit<Reverse<T>>) {
+        // Safety: Reverse<T> is #[repr(transparent)].
+        let out = unsafe { &mut *(out as *mut MaybeUninit<Reverse<T>> as *mut MaybeUninit<T>) };
+        self.0.decode_in_place(out);
+    }
+}
+
+impl<T: Encode> Encode for Reverse<T> {
+    type Encoder = ReverseEncoder<T>;
+}
+impl<'a, T: Decode<'a>> Decode<'a> for Reverse<T> {
+    type Decoder = ReverseDecoder<'a, T>;
+}
+
+/// Encodes [`Ordering`] like a `#[derive(Encode)]` enum with the variants `Less`, `Equal` and
+/// `Greater` (packed to at most 2 bits each).
+#[derive(Debug, Default)]
+pub struct OrderingEncoder(VariantEncoder<3>);
+
+impl Encoder<Ordering> for OrderingEncoder {
+    #[inline(always)]
+    fn encode(&mut self, t: &Ordering) {
+        self.0.encode(&((*t as i8 + 1) as u8));
+    }
+}
+
+impl EncoderBuffer for OrderingEncoder {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OrderingDecoder<'a>(VariantDecoder<'a, 3, true>);
+
+impl<'a> View<'a> for OrderingDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0.populate(input, length)
+    }
+}
+
+impl<'a> Decoder<'a, Ordering> for OrderingDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> Ordering {
+        match self.0.decode() {
+            0 => Ordering::Less,
+            1 => Ordering::Equal,
+            _ => Ordering::Greater,
+        }
+    }
+}
+
+impl Encode for Ordering {
+    type Encoder = OrderingEncoder;
+}
+impl<'a> Decode<'a> for Ordering {
+    type Decoder = OrderingDecoder<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::cmp::{Ordering, Reverse};
+    use std::collections::BinaryHeap;
+
+    #[test]
+    fn reverse() {
+        let v: Vec<(u32, String)> = crate::random_data::<u32>(100)
+            .into_iter()
+            .map(|i| (i, i.to_string()))
+            .collect();
+        let reversed: Vec<_> = v.iter().cloned().map(Reverse).collect();
+        let encoded = encode(&reversed);
+        assert_eq!(encoded, encode(&v));
+        assert_eq!(
+            decode::<Vec<Reverse<(u32, String)>>>(&encoded).unwrap(),
+            reversed
+        );
+
+        // Min-heap.
+        let heap: BinaryHeap<Reverse<u32>> = crate::random_data::<u32>(100)
+            .into_iter()
+            .map(Reverse)
+            .collect();
+        let decoded: BinaryHeap<Reverse<u32>> = decode(&encode(&heap)).unwrap();
+        assert_eq!(decoded.into_sorted_vec(), heap.into_sorted_vec());
+    }
+
+    #[test]
+    fn ordering() {
+        #[derive(crate::Encode)]
+        enum MyOrdering {
+            Less,
+            Equal,
+            Greater,
+        }
+
+        let orderings: Vec<Ordering> = crate::random_data::<(u8, u8)>(100)
+            .into_iter()
+            .map(|(a, b)| a.cmp(&b))
+            .collect();
+        let my_orderings: Vec<_> = orderings
+            .iter()
+            .map(|o| match o {
+                Ordering::Less => MyOrdering::Less,
+                Ordering::Equal => MyOrdering::Equal,
+                Ordering::Greater => MyOrdering::Greater,
+            })
+            .collect();
+        let encoded = encode(&orderings);
+        assert_eq!(encoded, encode(&my_orderings));
+        assert_eq!(decode::<Vec<Ordering>>(&encoded).unwrap(), orderings);
+    }
+}
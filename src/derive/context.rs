@@ -0,0 +1,270 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::{Decode, Encode};
+use crate::error::err;
+use std::any::TypeId;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+/// A type which is converted to [`Self::Encoded`] with a `Ctx` when encoded by
+/// [`encode_with_context`][`crate::encode_with_context`].
+///
+/// Use `#[bitcode(context = "Ctx")]` on a field of a `#[derive(Encode)]` type to encode it with
+/// this trait instead of [`Encode`].
+pub trait EncodeWith<Ctx> {
+    /// The type that is actually encoded.
+    type Encoded: Encode;
+
+    /// Converts `self` to the type that is actually encoded, e.g. by remapping an ID or interning a
+    /// string with `ctx`.
+    fn encode_with(&self, ctx: &mut Ctx) -> Self::Encoded;
+}
+
+/// A type which is converted from [`Self::Decoded`] with a `Ctx` when decoded by
+/// [`decode_with_context`][`crate::decode_with_context`].
+///
+/// Use `#[bitcode(context = "Ctx")]` on a field of a `#[derive(Decode)]` type to decode it with
+/// this trait instead of [`Decode`].
+pub trait DecodeWith<'a, Ctx>: Sized {
+    /// The type that is actually decoded.
+    type Decoded: Decode<'a>;
+
+    /// Converts the decoded value into `Self` with `ctx`. Can't error since the input has already
+    /// been validated.
+    fn decode_with(decoded: Self::Decoded, ctx: &mut Ctx) -> Self;
+}
+
+// The context is only accessed by the fields that need it, so passing it through every encoder and
+// decoder isn't worth the extra code.
+thread_local! {
+    static CONTEXT: Cell<Option<(TypeId, NonNull<()>)>> = const { Cell::new(None) };
+}
+
+/// Restores [`CONTEXT`] when dropped (including when unwinding).
+struct Restore(Option<(TypeId, NonNull<()>)>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CONTEXT.with(|c| c.set(self.0));
+    }
+}
+
+/// Calls `f` with `ctx` being returned by [`context`].
+pub(crate) fn with_context<Ctx: 'static, R>(ctx: &mut Ctx, f: impl FnOnce() -> R) -> R {
+    let ctx = (TypeId::of::<Ctx>(), NonNull::from(ctx).cast());
+    let _restore = Restore(CONTEXT.with(|c| c.replace(Some(ctx))));
+    f()
+}
+
+/// Returns the `Ctx` of the innermost [`with_context`] call if it has the same type.
+fn context<Ctx: 'static>() -> Option<NonNull<Ctx>> {
+    CONTEXT
+        .with(Cell::get)
+        .and_then(|(type_id, ptr)| (type_id == TypeId::of::<Ctx>()).then(|| ptr.cast()))
+}
+
+/// Calls `f` with the `Ctx` of the innermost [`with_context`] call.
+#[inline(always)]
+fn use_context<Ctx: 'static, R>(f: impl FnOnce(&mut Ctx) -> R) -> R {
+    let mut ctx = context::<Ctx>().unwrap_or_else(|| {
+        panic!(
+            "missing context {}, use encode_with_context/decode_with_context",
+            std::any::type_name::<Ctx>()
+        )
+    });
+    // Take the context while `f` runs so nested encode/decode calls in `f` can't get a second
+    // `&mut Ctx` (they hit the missing context error instead).
+    let _restore = Restore(CONTEXT.with(|c| c.take()));
+    // Safety: `with_context` holds a `&mut Ctx` which isn't used until `f` returns, and the context
+    // is cleared until `f` returns so this is the only reference to it.
+    f(unsafe { ctx.as_mut() })
+}
+
+pub struct ContextEncoder<T: EncodeWith<Ctx> + ?Sized, Ctx: 'static>(
+    <T::Encoded as Encode>::Encoder,
+    PhantomData<fn(&T)>,
+);
+
+// Can't derive since it would bound T: Default.
+impl<T: EncodeWith<Ctx> + ?Sized, Ctx: 'static> Default for ContextEncoder<T, Ctx> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<T: EncodeWith<Ctx> + ?Sized, Ctx: 'static> Encoder<T> for ContextEncoder<T, Ctx> {
+    #[inline(always)]
+    fn encode(&mut self, t: &T) {
+        let encoded = use_context(|ctx| t.encode_with(ctx));
+        self.0.encode(&encoded);
+    }
+}
+
+impl<T: EncodeWith<Ctx> + ?Sized, Ctx: 'static> EncoderBuffer for ContextEncoder<T, Ctx> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+pub struct ContextDecoder<'a, T: DecodeWith<'a, Ctx>, Ctx: 'static>(
+    <T::Decoded as Decode<'a>>::Decoder,
+    PhantomData<fn() -> T>,
+);
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: DecodeWith<'a, Ctx>, Ctx: 'static> Default for ContextDecoder<'a, T, Ctx> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<'a, T: DecodeWith<'a, Ctx>, Ctx: 'static> View<'a> for ContextDecoder<'a, T, Ctx> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        if context::<Ctx>().is_none() {
+            return err("missing context");
+        }
+        self.0.populate(input, length)
+    }
+}
+
+impl<'a, T: DecodeWith<'a, Ctx>, Ctx: 'static> Decoder<'a, T> for ContextDecoder<'a, T, Ctx> {
+    #[inline(always)]
+    fn decode(&mut self) -> T {
+        let decoded = self.0.decode();
+        use_context(|ctx| T::decode_with(decoded, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeWith, EncodeWith};
+    use crate::{decode, decode_with_context, encode, encode_with_context, Decode, Encode};
+    use std::collections::HashMap;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    struct EntityId(u64);
+
+    /// Maps local ids to network ids when encoding and network ids to local ids when decoding.
+    #[derive(Default)]
+    struct Remap(HashMap<u64, u32>, Vec<u64>);
+
+    impl EncodeWith<Remap> for EntityId {
+        type Encoded = u32;
+        fn encode_with(&self, ctx: &mut Remap) -> u32 {
+            let next = ctx.0.len() as u32;
+            *ctx.0.entry(self.0).or_insert(next)
+        }
+    }
+
+    impl DecodeWith<'_, Remap> for EntityId {
+        type Decoded = u32;
+        fn decode_with(decoded: u32, ctx: &mut Remap) -> Self {
+            EntityId(ctx.1.get(decoded as usize).copied().unwrap_or(u64::MAX))
+        }
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq)]
+    struct Parent {
+        #[bitcode(context = "Remap")]
+        id: EntityId,
+        #[bitcode(context = "Remap")]
+        parent: EntityId,
+        name: String,
+    }
+
+    #[test]
+    fn remap() {
+        let v = vec![
+            Parent {
+                id: EntityId(1000),
+                parent: EntityId(2000),
+                name: "a".into(),
+            },
+            Parent {
+                id: EntityId(3000),
+                parent: EntityId(1000),
+                name: "b".into(),
+            },
+        ];
+        let mut remap = Remap::default();
+        let encoded = encode_with_context(&v, &mut remap);
+        assert_eq!(remap.0.len(), 3);
+
+        // The ids are encoded as network ids.
+        let network: Vec<(u32, u32, String)> = decode(&encoded).unwrap();
+        assert_eq!(network[0].0, remap.0[&1000]);
+        assert_eq!(network[1].0, remap.0[&3000]);
+        assert_eq!(network[1].1, remap.0[&1000]);
+
+        // Decode with the inverse mapping to get the local ids back.
+        let mut local = vec![0; remap.0.len()];
+        for (&k, &v) in &remap.0 {
+            local[v as usize] = k;
+        }
+        let mut remap = Remap(Default::default(), local);
+        let decoded: Vec<Parent> = decode_with_context(&encoded, &mut remap).unwrap();
+        assert_eq!(decoded, v);
+
+        // Missing context.
+        assert!(decode::<Vec<Parent>>(&encoded).is_err());
+        assert!(decode_with_context::<Vec<Parent>, ()>(&encoded, &mut ()).is_err());
+    }
+
+    struct Reentrant;
+
+    impl EncodeWith<Remap> for Reentrant {
+        type Encoded = u32;
+        fn encode_with(&self, ctx: &mut Remap) -> u32 {
+            // Would alias `ctx` if the context was still set.
+            encode(&Parent {
+                id: EntityId(0),
+                parent: EntityId(0),
+                name: String::new(),
+            });
+            ctx.0.len() as u32
+        }
+    }
+
+    impl DecodeWith<'_, Remap> for Reentrant {
+        type Decoded = u32;
+        fn decode_with(_: u32, _: &mut Remap) -> Self {
+            let encoded = encode(&(0u32, 0u32, String::new()));
+            assert!(decode::<Parent>(&encoded).is_err());
+            Reentrant
+        }
+    }
+
+    #[derive(Encode, Decode)]
+    struct Outer(#[bitcode(context = "Remap")] Reentrant);
+
+    #[test]
+    #[should_panic = "missing context"]
+    fn encode_reentrant() {
+        encode_with_context(&Outer(Reentrant), &mut Remap::default());
+    }
+
+    #[test]
+    fn decode_reentrant() {
+        let encoded = encode(&0u32);
+        decode_with_context::<Outer, _>(&encoded, &mut Remap::default()).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "missing context"]
+    fn encode_missing_context() {
+        encode(&Parent {
+            id: EntityId(0),
+            parent: EntityId(0),
+            name: String::new(),
+        });
+    }
+}
@@ -0,0 +1,120 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::{Decode, DecodeOwned, Encode};
+use crate::error::err;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+/// A type that is encoded exactly like [`Self::Repr`]. Implement with [`impl_convert`].
+pub trait Convert: Sized {
+    type Repr: Encode + DecodeOwned;
+    /// If false, [`Self::from_repr`] never returns `None`, so decoding doesn't have to check it.
+    const CHECKED: bool;
+    fn to_repr(&self) -> Self::Repr;
+    fn from_repr(repr: Self::Repr) -> Option<Self>;
+}
+
+pub struct ConvertEncoder<T: Convert>(<T::Repr as Encode>::Encoder, PhantomData<fn(&T)>);
+
+// Can't derive since it would bound T: Default.
+impl<T: Convert> Default for ConvertEncoder<T> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<T: Convert> Encoder<T> for ConvertEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &T) {
+        self.0.encode(&t.to_repr());
+    }
+}
+
+impl<T: Convert> EncoderBuffer for ConvertEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+pub struct ConvertDecoder<'a, T: Convert> {
+    repr: <T::Repr as Decode<'a>>::Decoder,
+    // Only used if T::CHECKED. Kept between calls to reuse its allocations.
+    check: <T::Repr as Decode<'a>>::Decoder,
+    _spooky: PhantomData<fn() -> T>,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T: Convert> Default for ConvertDecoder<'_, T> {
+    fn default() -> Self {
+        Self {
+            repr: Default::default(),
+            check: Default::default(),
+            _spooky: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Convert> View<'a> for ConvertDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        if T::CHECKED {
+            // Decoders can't be cloned, so decode a copy of the input to check every repr.
+            let mut copy = *input;
+            self.check.populate(&mut copy, length)?;
+            if (0..length).any(|_| T::from_repr(self.check.decode()).is_none()) {
+                return err("invalid value");
+            }
+        }
+        self.repr.populate(input, length)
+    }
+}
+
+impl<'a, T: Convert> Decoder<'a, T> for ConvertDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> T {
+        let t = T::from_repr(self.repr.decode());
+        // Safety: populate checked that every repr is valid if T::CHECKED.
+        unsafe { t.unwrap_unchecked() }
+    }
+}
+
+/// Implements [`Encode`]/[`Decode`] for `$t` by converting it to/from `$repr`. The `checked` form
+/// takes a conversion that returns `Option<$t>` for reprs that can be invalid.
+macro_rules! impl_convert {
+    ($t:ty => $repr:ty, |$s:ident| $to:expr, |$r:ident| $from:expr) => {
+        $crate::derive::convert::impl_convert!(
+            @impl false, $t => $repr, |$s| $to, |$r| Some($from)
+        );
+    };
+    (checked $t:ty => $repr:ty, |$s:ident| $to:expr, |$r:ident| $from:expr) => {
+        $crate::derive::convert::impl_convert!(@impl true, $t => $repr, |$s| $to, |$r| $from);
+    };
+    (@impl $checked:literal, $t:ty => $repr:ty, |$s:ident| $to:expr, |$r:ident| $from:expr) => {
+        impl $crate::derive::convert::Convert for $t {
+            type Repr = $repr;
+            const CHECKED: bool = $checked;
+            #[inline(always)]
+            fn to_repr(&self) -> $repr {
+                let $s = self;
+                $to
+            }
+            #[inline(always)]
+            fn from_repr($r: $repr) -> Option<Self> {
+                $from
+            }
+        }
+        impl $crate::derive::Encode for $t {
+            type Encoder = $crate::derive::convert::ConvertEncoder<Self>;
+        }
+        impl<'a> $crate::derive::Decode<'a> for $t {
+            type Decoder = $crate::derive::convert::ConvertDecoder<'a, Self>;
+        }
+    };
+}
+pub(crate) use impl_convert;
@@ -0,0 +1,99 @@
+use crate::coder::{Decoder, Encoder, Result, View};
+use crate::derive::vec::VecEncoder;
+use crate::fast::NextUnchecked;
+use crate::int::IntDecoder;
+use crate::length::LengthDecoder;
+use crate::options::decode_options;
+use std::borrow::Cow;
+
+impl Encoder<Cow<'_, [u8]>> for VecEncoder<u8> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Cow<'_, [u8]>) {
+        self.encode(t.as_ref());
+    }
+}
+
+/// Decodes `Cow<[u8]>`s (encoded like `Vec<u8>`s) by borrowing the input if the bytes weren't
+/// packed, otherwise by copying the unpacked bytes.
+#[derive(Debug, Default)]
+pub struct CowBytesDecoder<'a> {
+    lengths: LengthDecoder<'a>,
+    bytes: IntDecoder<'a, u8>,
+    borrowed: Option<&'a [u8]>,
+}
+
+impl<'a> View<'a> for CowBytesDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.lengths.populate(input, length)?;
+        // Safety: `length` was just passed to populate.
+        let max = decode_options().max_vec_len;
+        unsafe { self.lengths.check_max(length, max, "vec too long")? };
+        let length = self.lengths.length();
+        self.bytes.populate(input, length)?;
+        // Safety: `length` was just passed to populate.
+        self.borrowed = unsafe { self.bytes.borrowed_bytes(length) };
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, Cow<'a, [u8]>> for CowBytesDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> Cow<'a, [u8]> {
+        let length = self.lengths.decode();
+        // Safety: populate ensured the lengths add up to the number of bytes.
+        unsafe {
+            if let Some(borrowed) = &mut self.borrowed {
+                Cow::Borrowed(borrowed.chunk_unchecked(length))
+            } else {
+                Cow::Owned(self.bytes.decode_bytes(length).to_vec())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::borrow::Cow;
+
+    #[test]
+    fn cow_bytes() {
+        // Unpacked bytes are borrowed.
+        let v: Vec<Vec<u8>> = (0..10)
+            .map(|i| (0..i * 30).map(|j| j as u8).collect())
+            .collect();
+        let encoded = encode(&v);
+        let decoded: Vec<Cow<[u8]>> = decode(&encoded).unwrap();
+        assert!(decoded.iter().eq(&v));
+        assert!(decoded.iter().all(|c| matches!(c, Cow::Borrowed(_))));
+        assert_eq!(encode(&decoded), encoded);
+
+        // Packed bytes are copied.
+        let v: Vec<Vec<u8>> = (0..10).map(|i| vec![i % 2; i as usize * 10]).collect();
+        let encoded = encode(&v);
+        let decoded: Vec<Cow<[u8]>> = decode(&encoded).unwrap();
+        assert!(decoded.iter().eq(&v));
+        assert!(decoded.iter().any(|c| matches!(c, Cow::Owned(_))));
+        assert_eq!(encode(&decoded), encoded);
+    }
+
+    #[test]
+    fn cow_str() {
+        let v = vec!["abc".to_owned(), "".to_owned(), "défg".to_owned()];
+        let encoded = encode(&v);
+        let decoded: Vec<Cow<str>> = decode(&encoded).unwrap();
+        assert!(decoded.iter().eq(&v));
+        assert!(decoded.iter().all(|c| matches!(c, Cow::Borrowed(_))));
+        assert_eq!(encode(&decoded), encoded);
+
+        // Only allocates when invalid UTF-8 is replaced.
+        let mut encoded = encode(&vec!["a", "b"]);
+        *encoded.last_mut().unwrap() = 0xFF;
+        assert!(decode::<Vec<Cow<str>>>(&encoded).is_err());
+        let options = crate::DecodeOptions::new().lossy_text(true);
+        let decoded: Vec<Cow<str>> = crate::decode_with_options(&encoded, &options).unwrap();
+        assert_eq!(decoded, ["a", "\u{FFFD}"]);
+        assert!(matches!(decoded[0], Cow::Borrowed(_)));
+        assert!(matches!(decoded[1], Cow::Owned(_)));
+    }
+}
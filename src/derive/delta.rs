@@ -0,0 +1,150 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::consume_byte_arrays;
+use crate::int::{IntDecoder, IntEncoder};
+use crate::pack_ints::Int;
+use std::num::NonZeroUsize;
+
+/// Integers that can be encoded with `#[bitcode(delta)]`.
+pub trait DeltaInt: Int {
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    /// Writes `v` in little endian.
+    fn write(v: Self, out: &mut Vec<u8>);
+    fn read(input: &mut &[u8]) -> Result<Self>;
+}
+
+macro_rules! impl_delta_int {
+    ($($t:ty => $u:ty),+) => {
+        $(
+            impl DeltaInt for $t {
+                #[inline(always)]
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    self.wrapping_add(rhs)
+                }
+                #[inline(always)]
+                fn wrapping_sub(self, rhs: Self) -> Self {
+                    self.wrapping_sub(rhs)
+                }
+                fn write(v: Self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&(v as $u).to_le_bytes());
+                }
+                fn read(input: &mut &[u8]) -> Result<Self> {
+                    Ok(<$u>::from_le_bytes(consume_byte_arrays(input, 1)?[0]) as Self)
+                }
+            }
+        )+
+    };
+}
+impl_delta_int!(u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => u64);
+impl_delta_int!(i8 => i8, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => i64);
+
+/// Used by `#[derive(Encode)]` for fields with `#[bitcode(delta)]`. Encodes the first value in the
+/// column followed by the (wrapping) difference of each value from the previous one, so slowly
+/// increasing values like timestamps or sequence numbers are packed into fewer bytes.
+#[derive(Debug, Default)]
+pub struct DeltaEncoder<T: DeltaInt> {
+    first: Option<T>,
+    deltas: IntEncoder<T>,
+    previous: T,
+}
+
+impl<T: DeltaInt> Encoder<T> for DeltaEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, &t: &T) {
+        if self.first.is_none() {
+            self.first = Some(t);
+            self.previous = t;
+        }
+        self.deltas.encode(&t.wrapping_sub(self.previous));
+        self.previous = t;
+    }
+}
+
+impl<T: DeltaInt> EncoderBuffer for DeltaEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        // The decoder only reads the first value if the column isn't empty.
+        if let Some(first) = self.first.take() {
+            T::write(first, out);
+        }
+        self.deltas.collect_into(out);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.deltas.reserve(additional);
+    }
+}
+
+#[derive(Default)]
+pub struct DeltaDecoder<'a, T: DeltaInt> {
+    deltas: IntDecoder<'a, T>,
+    previous: T,
+}
+
+impl<'a, T: DeltaInt> View<'a> for DeltaDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        if length != 0 {
+            self.previous = T::read(input)?;
+        }
+        self.deltas.populate(input, length)
+    }
+}
+
+impl<'a, T: DeltaInt> Decoder<'a, T> for DeltaDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> T {
+        let delta: T = self.deltas.decode();
+        self.previous = self.previous.wrapping_add(delta);
+        self.previous
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Event {
+        #[bitcode(delta)]
+        timestamp: u64,
+        #[bitcode(delta)]
+        sequence: i32,
+        kind: u8,
+    }
+
+    #[test]
+    fn delta() {
+        let events: Vec<Vec<Event>> = (0..10)
+            .map(|i| {
+                (0..100)
+                    .map(|j| Event {
+                        timestamp: 1_700_000_000_000 + i * 1000 + j * 10,
+                        sequence: (i * 100 + j) as i32 - 500,
+                        kind: j as u8 % 3,
+                    })
+                    .collect()
+            })
+            .collect();
+        let encoded = encode(&events);
+        assert_eq!(decode::<Vec<Vec<Event>>>(&encoded).unwrap(), events);
+
+        // Deltas fit in a byte instead of 8 + 4 bytes.
+        let undelta: Vec<Vec<_>> = events
+            .iter()
+            .map(|e| {
+                e.iter()
+                    .map(|e| (e.timestamp, e.sequence, e.kind))
+                    .collect()
+            })
+            .collect();
+        assert!(encoded.len() < 1500, "{}", encoded.len());
+        assert!(encode(&undelta).len() > 3000);
+
+        // Decreasing values wrap around.
+        let events = [u64::MAX, 0, 5, 2].map(|timestamp| Event {
+            timestamp,
+            sequence: i32::MIN,
+            kind: 0,
+        });
+        assert_eq!(decode::<[Event; 4]>(&encode(&events)).unwrap(), events);
+    }
+}
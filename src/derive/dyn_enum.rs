@@ -0,0 +1,272 @@
+/// Implements [`Encode`][`crate::Encode`] and [`Decode`][`crate::Decode`] for `Box<dyn Trait>`
+/// when all of its implementors are known. It's encoded like an enum with one variant per
+/// implementor, so it's as small and fast as if the field were that enum.
+///
+/// The trait must have [`Any`][`std::any::Any`] as a supertrait so the implementor of each value
+/// can be found by its [`TypeId`][`std::any::TypeId`]. Encoding a value of a type that isn't listed
+/// panics.
+///
+/// **Warning:** The format is subject to change between major versions. Changing the order of the
+/// implementors changes the format.
+///
+/// ```rust
+/// use bitcode::{Decode, Encode};
+/// use std::any::Any;
+///
+/// trait Shape: Any {
+///     fn area(&self) -> f32;
+/// }
+///
+/// #[derive(Encode, Decode)]
+/// struct Circle(f32);
+/// impl Shape for Circle {
+///     fn area(&self) -> f32 {
+///         std::f32::consts::PI * self.0 * self.0
+///     }
+/// }
+///
+/// #[derive(Encode, Decode)]
+/// struct Square(f32);
+/// impl Shape for Square {
+///     fn area(&self) -> f32 {
+///         self.0 * self.0
+///     }
+/// }
+///
+/// bitcode::dyn_enum!(dyn Shape {
+///     Circle(Circle),
+///     Square(Square),
+/// });
+///
+/// let shapes: Vec<Box<dyn Shape>> = vec![Box::new(Circle(1.0)), Box::new(Square(2.0))];
+/// let decoded: Vec<Box<dyn Shape>> = bitcode::decode(&bitcode::encode(&shapes)).unwrap();
+/// assert_eq!(decoded[1].area(), 4.0);
+/// ```
+#[macro_export]
+macro_rules! dyn_enum {
+    (dyn $trait_:path { $($variant:ident($t:ty)),+ $(,)? }) => {
+        const _: () = {
+            use $crate::__private::{
+                invalid_enum_variant, Decode, Decoder, Encode, Encoder, EncoderBuffer, Result,
+                VariantDecoder, VariantEncoder, View,
+            };
+            const __LEN: usize = [$(stringify!($variant)),+].len();
+            // Like enums, a single implementor doesn't encode variants. VariantEncoder requires at
+            // least 2 variants so it's unused in that case.
+            const __VARIANTS: bool = __LEN > 1;
+            const __N: usize = if __VARIANTS { __LEN } else { 2 };
+
+            impl Encode for Box<dyn $trait_> {
+                type Encoder = DynEncoder;
+            }
+
+            #[allow(non_snake_case)]
+            #[derive(Default)]
+            pub struct DynEncoder {
+                variants: VariantEncoder<__N>,
+                $($variant: <$t as Encode>::Encoder,)+
+            }
+
+            impl Encoder<Box<dyn $trait_>> for DynEncoder {
+                #[allow(unused_assignments)]
+                fn encode(&mut self, v: &Box<dyn $trait_>) {
+                    // Calls Any::type_id through the vtable of `dyn $trait_` which returns the
+                    // TypeId of the implementor.
+                    let type_id = std::any::Any::type_id(&**v);
+                    let mut i = 0u8;
+                    $(
+                        if type_id == std::any::TypeId::of::<$t>() {
+                            // Safety: the implementor is $t since the TypeIds are equal.
+                            let t = unsafe { &*(&**v as *const dyn $trait_ as *const $t) };
+                            if __VARIANTS {
+                                self.variants.encode(&i);
+                            }
+                            self.$variant.reserve(std::num::NonZeroUsize::MIN);
+                            self.$variant.encode(t);
+                            return;
+                        }
+                        i += 1;
+                    )+
+                    panic!(
+                        "{} isn't listed in dyn_enum!",
+                        std::any::type_name::<dyn $trait_>()
+                    );
+                }
+            }
+
+            impl EncoderBuffer for DynEncoder {
+                fn collect_into(&mut self, out: &mut Vec<u8>) {
+                    if __VARIANTS {
+                        self.variants.collect_into(out);
+                    }
+                    $(self.$variant.collect_into(out);)+
+                }
+
+                fn collect_into_flushing(
+                    &mut self,
+                    out: &mut Vec<u8>,
+                    flush: &mut dyn FnMut(&mut Vec<u8>),
+                ) {
+                    if __VARIANTS {
+                        self.variants.collect_into_flushing(out, flush);
+                    }
+                    $(self.$variant.collect_into_flushing(out, flush);)+
+                }
+
+                fn reserve(&mut self, additional: std::num::NonZeroUsize) {
+                    if __VARIANTS {
+                        self.variants.reserve(additional);
+                    }
+                }
+            }
+
+            impl<'a> Decode<'a> for Box<dyn $trait_> {
+                type Decoder = DynDecoder<'a>;
+            }
+
+            #[allow(non_snake_case)]
+            #[derive(Default)]
+            pub struct DynDecoder<'a> {
+                variants: VariantDecoder<'a, __N, false>,
+                $($variant: <$t as Decode<'a>>::Decoder,)+
+            }
+
+            impl<'a> View<'a> for DynDecoder<'a> {
+                #[allow(unused_assignments)]
+                fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+                    if __VARIANTS {
+                        self.variants.populate(input, length)?;
+                    }
+                    let mut i = 0u8;
+                    let mut populated = 0;
+                    $(
+                        let n = if __VARIANTS { self.variants.length(i) } else { length };
+                        self.$variant.populate(input, n)?;
+                        populated += n;
+                        i += 1;
+                    )+
+                    if populated != length {
+                        return invalid_enum_variant();
+                    }
+                    Ok(())
+                }
+            }
+
+            impl<'a> Decoder<'a, Box<dyn $trait_>> for DynDecoder<'a> {
+                #[allow(unused_assignments)]
+                fn decode(&mut self) -> Box<dyn $trait_> {
+                    let variant = if __VARIANTS { self.variants.decode() } else { 0 };
+                    let mut i = 0u8;
+                    $(
+                        if variant == i {
+                            return Box::new(self.$variant.decode());
+                        }
+                        i += 1;
+                    )+
+                    // Safety: populate ensures every variant is less than __LEN.
+                    unsafe { std::hint::unreachable_unchecked() }
+                }
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+    use std::any::Any;
+    use std::fmt::Debug;
+
+    trait Shape: Any + Debug {
+        fn area(&self) -> f32;
+    }
+
+    #[derive(Debug, Encode, Decode)]
+    struct Circle {
+        radius: f32,
+    }
+    impl Shape for Circle {
+        fn area(&self) -> f32 {
+            std::f32::consts::PI * self.radius * self.radius
+        }
+    }
+
+    #[derive(Debug, Encode, Decode)]
+    struct Rect(f32, f32);
+    impl Shape for Rect {
+        fn area(&self) -> f32 {
+            self.0 * self.1
+        }
+    }
+
+    #[derive(Debug)]
+    struct Unlisted;
+    impl Shape for Unlisted {
+        fn area(&self) -> f32 {
+            0.0
+        }
+    }
+
+    crate::dyn_enum!(dyn Shape {
+        Circle(Circle),
+        Rect(Rect),
+    });
+
+    trait One: Any {}
+    #[derive(Encode, Decode)]
+    struct OnlyOne(u8);
+    impl One for OnlyOne {}
+    crate::dyn_enum!(dyn One { OnlyOne(OnlyOne) });
+
+    #[derive(Debug, Encode, Decode)]
+    enum Equivalent {
+        Circle(Circle),
+        Rect(Rect),
+    }
+
+    #[derive(Encode, Decode)]
+    enum EquivalentOne {
+        OnlyOne(OnlyOne),
+    }
+
+    #[test]
+    fn dyn_enum() {
+        let shapes: Vec<Box<dyn Shape>> = vec![
+            Box::new(Circle { radius: 1.0 }),
+            Box::new(Rect(2.0, 3.0)),
+            Box::new(Rect(4.0, 5.0)),
+        ];
+        let encoded = encode(&shapes);
+        let decoded: Vec<Box<dyn Shape>> = decode(&encoded).unwrap();
+        let areas = |v: &[Box<dyn Shape>]| v.iter().map(|s| s.area()).collect::<Vec<_>>();
+        assert_eq!(areas(&decoded), areas(&shapes));
+
+        // Encoded exactly like the equivalent enum.
+        let equivalent = vec![
+            Equivalent::Circle(Circle { radius: 1.0 }),
+            Equivalent::Rect(Rect(2.0, 3.0)),
+            Equivalent::Rect(Rect(4.0, 5.0)),
+        ];
+        assert_eq!(encoded, encode(&equivalent));
+
+        let one: Vec<Box<dyn One>> = vec![Box::new(OnlyOne(5)), Box::new(OnlyOne(6))];
+        let encoded = encode(&one);
+        assert_eq!(
+            encoded,
+            encode(&vec![
+                EquivalentOne::OnlyOne(OnlyOne(5)),
+                EquivalentOne::OnlyOne(OnlyOne(6))
+            ])
+        );
+        let decoded: Vec<Box<dyn One>> = decode(&encoded).unwrap();
+        assert_eq!(encode(&decoded), encoded);
+        assert!(decode::<Box<dyn One>>(&[]).is_err());
+    }
+
+    #[test]
+    #[should_panic = "isn't listed in dyn_enum!"]
+    fn unlisted() {
+        let shape: Box<dyn Shape> = Box::new(Unlisted);
+        encode(&shape);
+    }
+}
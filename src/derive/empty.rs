@@ -1,4 +1,6 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::error::{err_kind, ErrorKind};
+use std::convert::Infallible;
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
 
@@ -9,7 +11,7 @@ impl<T> Encoder<PhantomData<T>> for EmptyCoder {
     fn encode(&mut self, _: &PhantomData<T>) {}
 }
 
-impl Buffer for EmptyCoder {
+impl EncoderBuffer for EmptyCoder {
     fn collect_into(&mut self, _: &mut Vec<u8>) {}
     fn reserve(&mut self, _: NonZeroUsize) {}
 }
@@ -25,3 +27,52 @@ impl<'a, T> Decoder<'a, PhantomData<T>> for EmptyCoder {
         PhantomData
     }
 }
+
+/// Like a `#[derive(Encode, Decode)]` enum without any variants, nothing is encoded and decoding
+/// errors if there are any values.
+#[derive(Debug, Default)]
+pub struct InfallibleCoder;
+
+impl Encoder<Infallible> for InfallibleCoder {
+    fn encode(&mut self, t: &Infallible) {
+        match *t {}
+    }
+}
+
+impl EncoderBuffer for InfallibleCoder {
+    fn collect_into(&mut self, _: &mut Vec<u8>) {}
+    fn reserve(&mut self, _: NonZeroUsize) {}
+}
+
+impl<'a> View<'a> for InfallibleCoder {
+    fn populate(&mut self, _: &mut &'a [u8], length: usize) -> Result<()> {
+        if length != 0 {
+            return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, Infallible> for InfallibleCoder {
+    fn decode(&mut self) -> Infallible {
+        // Safety: View::populate will error on length != 0 so decode won't be called.
+        unsafe { std::hint::unreachable_unchecked() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::convert::Infallible;
+
+    #[test]
+    fn infallible() {
+        let v: Vec<Result<u8, Infallible>> = vec![Ok(1), Ok(2)];
+        let encoded = encode(&v);
+        assert_eq!(encoded, encode(&vec![Ok::<u8, ()>(1), Ok(2)]));
+        assert_eq!(decode::<Vec<Result<u8, Infallible>>>(&encoded).unwrap(), v);
+        assert!(decode::<Vec<Infallible>>(&encode(&Vec::<()>::new())).is_ok());
+        assert!(decode::<Vec<Infallible>>(&encode(&vec![()])).is_err());
+        assert!(decode::<Result<u8, Infallible>>(&encode(&Err::<u8, ()>(()))).is_err());
+    }
+}
@@ -1,21 +1,25 @@
 use crate::bool::{BoolDecoder, BoolEncoder};
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::derive::array::{ArrayDecoder, ArrayEncoder};
-use crate::derive::empty::EmptyCoder;
+use crate::derive::cow::CowBytesDecoder;
+use crate::derive::empty::{EmptyCoder, InfallibleCoder};
 use crate::derive::map::{MapDecoder, MapEncoder};
 use crate::derive::option::{OptionDecoder, OptionEncoder};
 use crate::derive::result::{ResultDecoder, ResultEncoder};
-use crate::derive::smart_ptr::{DerefEncoder, FromDecoder};
+use crate::derive::smart_ptr::{DerefEncoder, FromDecoder, SharedSliceDecoder};
 use crate::derive::vec::{VecDecoder, VecEncoder};
 use crate::derive::{Decode, Encode};
-use crate::f32::{F32Decoder, F32Encoder};
-use crate::int::{CheckedIntDecoder, IntDecoder, IntEncoder};
-use crate::str::{StrDecoder, StrEncoder};
+use crate::f32::{F32Decoder, F32Encoder, F64Encoder};
+use crate::int::{CharDecoder, CheckedIntDecoder, IntDecoder, IntEncoder};
+use crate::str::{StrDecoder, StrEncoder, StringDecoder};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::convert::Infallible;
 use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 use std::num::*;
+use std::ops::ControlFlow;
 
 macro_rules! impl_both {
     ($t:ty, $encoder:ident, $decoder:ident) => {
@@ -29,7 +33,7 @@ macro_rules! impl_both {
 }
 impl_both!(bool, BoolEncoder, BoolDecoder);
 impl_both!(f32, F32Encoder, F32Decoder);
-impl_both!(String, StrEncoder, StrDecoder);
+impl_both!(String, StrEncoder, StringDecoder);
 
 macro_rules! impl_int {
     ($($t:ty),+) => {
@@ -45,9 +49,8 @@ macro_rules! impl_int {
 }
 impl_int!(u8, u16, u32, u64, u128, usize);
 impl_int!(i8, i16, i32, i64, i128, isize);
-// TODO F64Encoder (once F32Encoder is sufficiently optimized).
 impl Encode for f64 {
-    type Encoder = IntEncoder<u64>;
+    type Encoder = F64Encoder;
 }
 impl<'a> Decode<'a> for f64 {
     type Decoder = IntDecoder<'a, u64>;
@@ -67,7 +70,31 @@ macro_rules! impl_checked_int {
 }
 impl_checked_int!(NonZeroU8 => u8, NonZeroU16 => u16, NonZeroU32 => u32, NonZeroU64 => u64, NonZeroU128 => u128, NonZeroUsize => usize);
 impl_checked_int!(NonZeroI8 => i8, NonZeroI16 => i16, NonZeroI32 => i32, NonZeroI64 => i64, NonZeroI128 => i128, NonZeroIsize => isize);
-impl_checked_int!(char => u32);
+// Wrapping and Saturating are #[repr(transparent)] so they're encoded exactly like their integers.
+macro_rules! impl_wrapper_int {
+    ($wrapper:ident, $($t:ty),+) => {
+        $(
+            impl Encode for $wrapper<$t> {
+                type Encoder = IntEncoder<$t>;
+            }
+            impl<'a> Decode<'a> for $wrapper<$t> {
+                type Decoder = IntDecoder<'a, $t>;
+            }
+        )+
+    }
+}
+impl_wrapper_int!(Wrapping, u8, u16, u32, u64, u128, usize);
+impl_wrapper_int!(Wrapping, i8, i16, i32, i64, i128, isize);
+#[cfg(feature = "saturating")]
+impl_wrapper_int!(Saturating, u8, u16, u32, u64, u128, usize);
+#[cfg(feature = "saturating")]
+impl_wrapper_int!(Saturating, i8, i16, i32, i64, i128, isize);
+impl Encode for char {
+    type Encoder = IntEncoder<u32>;
+}
+impl<'a> Decode<'a> for char {
+    type Decoder = CharDecoder<'a>;
+}
 
 macro_rules! impl_t {
     ($t:ident, $encoder:ident, $decoder:ident) => {
@@ -85,7 +112,7 @@ impl_t!(Vec, VecEncoder, VecDecoder);
 impl_t!(VecDeque, VecEncoder, VecDecoder);
 
 macro_rules! impl_smart_ptr {
-    ($(::$ptr: ident)*) => {
+    ($(::$ptr: ident)*, $slice_decoder:ident, $str_decoder:ident) => {
         impl<T: Encode + ?Sized> Encode for $(::$ptr)*<T> {
             type Encoder = DerefEncoder<T>;
         }
@@ -95,19 +122,21 @@ macro_rules! impl_smart_ptr {
         }
 
         impl<'a, T: Decode<'a>> Decode<'a> for $(::$ptr)*<[T]> {
-            // TODO avoid Vec<T> allocation for Rc<[T]> and Arc<[T]>.
-            type Decoder = FromDecoder<'a, Vec<T>>;
+            type Decoder = $slice_decoder<'a, T>;
         }
 
         impl<'a> Decode<'a> for $(::$ptr)*<str> {
-            // TODO avoid String allocation for Rc<str> and Arc<str>.
-            type Decoder = FromDecoder<'a, String>;
+            type Decoder = $str_decoder<'a>;
         }
     }
 }
-impl_smart_ptr!(::std::boxed::Box);
-impl_smart_ptr!(::std::rc::Rc);
-impl_smart_ptr!(::std::sync::Arc);
+// Vec<T> -> Box<[T]> and String -> Box<str> don't copy, but Rc and Arc have to be decoded directly
+// into their allocation to avoid copying.
+type FromVecDecoder<'a, T> = FromDecoder<'a, Vec<T>>;
+type FromStringDecoder<'a> = FromDecoder<'a, String>;
+impl_smart_ptr!(::std::boxed::Box, FromVecDecoder, FromStringDecoder);
+impl_smart_ptr!(::std::rc::Rc, SharedSliceDecoder, StringDecoder);
+impl_smart_ptr!(::std::sync::Arc, SharedSliceDecoder, StringDecoder);
 
 impl<T: Encode, const N: usize> Encode for [T; N] {
     type Encoder = ArrayEncoder<T, N>;
@@ -132,6 +161,20 @@ impl<'a> Decode<'a> for &'a str {
     type Decoder = StrDecoder<'a>;
 }
 
+// Borrows the input when possible.
+impl Encode for Cow<'_, str> {
+    type Encoder = StrEncoder;
+}
+impl<'a> Decode<'a> for Cow<'a, str> {
+    type Decoder = StringDecoder<'a>;
+}
+impl Encode for Cow<'_, [u8]> {
+    type Encoder = VecEncoder<u8>;
+}
+impl<'a> Decode<'a> for Cow<'a, [u8]> {
+    type Decoder = CowBytesDecoder<'a>;
+}
+
 impl<T: Encode> Encode for BinaryHeap<T> {
     type Encoder = VecEncoder<T>;
 }
@@ -172,6 +215,18 @@ impl<T: Encode, E: Encode> Encode for std::result::Result<T, E> {
 impl<'a, T: Decode<'a>, E: Decode<'a>> Decode<'a> for std::result::Result<T, E> {
     type Decoder = ResultDecoder<'a, T, E>;
 }
+impl<B: Encode, C: Encode> Encode for ControlFlow<B, C> {
+    type Encoder = ResultEncoder<C, B>;
+}
+impl<'a, B: Decode<'a>, C: Decode<'a>> Decode<'a> for ControlFlow<B, C> {
+    type Decoder = ResultDecoder<'a, C, B>;
+}
+impl Encode for Infallible {
+    type Encoder = InfallibleCoder;
+}
+impl<'a> Decode<'a> for Infallible {
+    type Decoder = InfallibleCoder;
+}
 impl<T> Encode for PhantomData<T> {
     type Encoder = EmptyCoder;
 }
@@ -217,13 +272,19 @@ macro_rules! impl_tuples {
                     }
                 }
 
-                impl<$($name: Encode,)*> Buffer for TupleEncoder<$($name,)*> {
+                impl<$($name: Encode,)*> EncoderBuffer for TupleEncoder<$($name,)*> {
                     fn collect_into(&mut self, out: &mut Vec<u8>) {
                         $(
                             self.$n.collect_into(out);
                         )*
                     }
 
+                    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+                        $(
+                            self.$n.collect_into_flushing(out, flush);
+                        )*
+                    }
+
                     fn reserve(&mut self, length: NonZeroUsize) {
                         $(
                             self.$n.reserve(length);
@@ -0,0 +1,119 @@
+use crate::derive::context::{DecodeWith, EncodeWith};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A context which deduplicates decoded strings, so identical strings share one allocation.
+///
+/// Use `#[bitcode(context = "bitcode::Interner")]` on an `Arc<str>` field and
+/// [`decode_with_context`][`crate::decode_with_context`] (and
+/// [`encode_with_context`][`crate::encode_with_context`]). The strings are encoded like any other
+/// `Arc<str>`. Implement [`DecodeWith`] for other symbol types.
+///
+/// ```rust
+/// use bitcode::{Decode, Encode, Interner};
+/// use std::sync::Arc;
+///
+/// #[derive(Encode, Decode)]
+/// struct Record {
+///     #[bitcode(context = "Interner")]
+///     category: Arc<str>,
+///     value: u32,
+/// }
+///
+/// let records: Vec<Record> = (0..1000)
+///     .map(|i| Record { category: ["a", "b"][i % 2].into(), value: i as u32 })
+///     .collect();
+/// let encoded = bitcode::encode_with_context(&records, &mut Interner::new());
+///
+/// let mut interner = Interner::new();
+/// let decoded: Vec<Record> = bitcode::decode_with_context(&encoded, &mut interner).unwrap();
+/// assert!(Arc::ptr_eq(&decoded[0].category, &decoded[2].category));
+/// assert_eq!(interner.len(), 2);
+/// ```
+#[derive(Debug, Default)]
+pub struct Interner(HashSet<Arc<str>>);
+
+impl Interner {
+    /// Constructs an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of `s`, allocating one if it doesn't exist yet.
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(interned) = self.0.get(s) {
+            return Arc::clone(interned);
+        }
+        let interned: Arc<str> = s.into();
+        self.0.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Returns the number of unique strings.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no strings have been interned.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl EncodeWith<Interner> for Arc<str> {
+    type Encoded = Arc<str>;
+
+    fn encode_with(&self, _: &mut Interner) -> Arc<str> {
+        Arc::clone(self)
+    }
+}
+
+impl<'a> DecodeWith<'a, Interner> for Arc<str> {
+    type Decoded = &'a str;
+
+    fn decode_with(decoded: &'a str, ctx: &mut Interner) -> Self {
+        ctx.intern(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interner;
+    use crate::{decode, decode_with_context, encode, encode_with_context, Decode, Encode};
+    use std::sync::Arc;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Record {
+        #[bitcode(context = "Interner")]
+        category: Arc<str>,
+        id: u32,
+    }
+
+    #[test]
+    fn intern() {
+        let records: Vec<Record> = (0..100)
+            .map(|i| Record {
+                category: ["apple", "banana", "cherry"][i % 3].into(),
+                id: i as u32,
+            })
+            .collect();
+        let encoded = encode_with_context(&records, &mut Interner::new());
+
+        // Encoded like Arc<str>.
+        let plain: Vec<(Arc<str>, u32)> = decode(&encoded).unwrap();
+        assert_eq!(encoded, encode(&plain));
+
+        let mut interner = Interner::new();
+        let decoded: Vec<Record> = decode_with_context(&encoded, &mut interner).unwrap();
+        assert_eq!(decoded, records);
+        assert_eq!(interner.len(), 3);
+        for (i, r) in decoded.iter().enumerate().skip(3) {
+            assert!(Arc::ptr_eq(&r.category, &decoded[i % 3].category));
+        }
+
+        // Reusing the interner shares strings between calls.
+        let again: Vec<Record> = decode_with_context(&encoded, &mut interner).unwrap();
+        assert!(Arc::ptr_eq(&again[0].category, &decoded[0].category));
+        assert_eq!(interner.len(), 3);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::{Decode, Encode};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+/// Used by `#[derive(Encode)]` with `#[bitcode(into = "R")]`. Encodes a clone of each `T`
+/// converted into `R`.
+pub struct IntoEncoder<T, R: Encode>(R::Encoder, PhantomData<fn(&T)>);
+
+// Can't derive since it would bound T: Default.
+impl<T, R: Encode> Default for IntoEncoder<T, R> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<T: Clone + Into<R>, R: Encode> Encoder<T> for IntoEncoder<T, R> {
+    #[inline(always)]
+    fn encode(&mut self, t: &T) {
+        self.0.encode(&t.clone().into());
+    }
+}
+
+impl<T, R: Encode> EncoderBuffer for IntoEncoder<T, R> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+/// Used by `#[derive(Decode)]` with `#[bitcode(try_from = "R")]`. Decodes an `R` and converts it
+/// with [`TryFrom`], turning conversion errors into decode errors.
+pub struct TryFromDecoder<'a, T, R: Decode<'a>> {
+    repr: R::Decoder,
+    // Kept between calls to reuse its allocations.
+    check: R::Decoder,
+    _spooky: PhantomData<fn() -> T>,
+}
+
+// Can't derive since it would bound T: Default.
+impl<'a, T, R: Decode<'a>> Default for TryFromDecoder<'a, T, R> {
+    fn default() -> Self {
+        Self {
+            repr: Default::default(),
+            check: Default::default(),
+            _spooky: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: TryFrom<R>, R: Decode<'a>> View<'a> for TryFromDecoder<'a, T, R>
+where
+    T::Error: std::fmt::Display,
+{
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        // Decoders can't be cloned, so decode a copy of the input to check every repr.
+        let mut copy = *input;
+        self.check.populate(&mut copy, length)?;
+        for _ in 0..length {
+            T::try_from(self.check.decode()).map_err(crate::error::error_from_display)?;
+        }
+        self.repr.populate(input, length)
+    }
+}
+
+impl<'a, T: TryFrom<R>, R: Decode<'a>> Decoder<'a, T> for TryFromDecoder<'a, T, R>
+where
+    T::Error: std::fmt::Display,
+{
+    #[inline(always)]
+    fn decode(&mut self) -> T {
+        let t = T::try_from(self.repr.decode());
+        // Safety: populate checked that every repr can be converted.
+        unsafe { t.unwrap_unchecked() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[derive(Clone, Debug, PartialEq, Encode, Decode)]
+    #[bitcode(into = "u32", from = "u32")]
+    struct Rgb {
+        r: u8,
+        g: u8,
+        b: u8,
+    }
+
+    impl From<Rgb> for u32 {
+        fn from(c: Rgb) -> Self {
+            u32::from_be_bytes([0, c.r, c.g, c.b])
+        }
+    }
+
+    impl From<u32> for Rgb {
+        fn from(v: u32) -> Self {
+            let [_, r, g, b] = v.to_be_bytes();
+            Self { r, g, b }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Encode, Decode)]
+    #[bitcode(into = "String", try_from = "String")]
+    struct Email(String);
+
+    impl From<Email> for String {
+        fn from(e: Email) -> Self {
+            e.0
+        }
+    }
+
+    impl TryFrom<String> for Email {
+        type Error = &'static str;
+
+        fn try_from(s: String) -> Result<Self, Self::Error> {
+            if !s.contains('@') {
+                return Err("missing @");
+            }
+            Ok(Self(s))
+        }
+    }
+
+    #[test]
+    fn into_from() {
+        let colors = vec![
+            Rgb { r: 1, g: 2, b: 3 },
+            Rgb {
+                r: 255,
+                g: 0,
+                b: 128,
+            },
+        ];
+        let encoded = encode(&colors);
+        assert_eq!(encoded, encode(&vec![0x010203u32, 0xFF0080]));
+        assert_eq!(decode::<Vec<Rgb>>(&encoded).unwrap(), colors);
+    }
+
+    #[test]
+    fn try_from() {
+        let emails = vec![Email("a@b.c".into()), Email("d@e.f".into())];
+        let encoded = encode(&emails);
+        assert_eq!(encoded, encode(&vec!["a@b.c", "d@e.f"]));
+        assert_eq!(decode::<Vec<Email>>(&encoded).unwrap(), emails);
+        assert!(decode::<Vec<Email>>(&encode(&vec!["a@b.c", "def"])).is_err());
+
+        // The decoder is reused after a conversion error.
+        let mut buffer = crate::Buffer::new();
+        assert!(buffer.decode::<Email>(&encode("x")).is_err());
+        assert_eq!(buffer.decode::<Vec<Email>>(&encoded).unwrap(), emails);
+    }
+
+    // Neither implements Encode or Decode.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Celsius(f32);
+
+    impl From<Celsius> for f32 {
+        fn from(c: Celsius) -> Self {
+            c.0
+        }
+    }
+
+    impl From<f32> for Celsius {
+        fn from(v: f32) -> Self {
+            Self(v)
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Name(String);
+
+    impl From<Name> for String {
+        fn from(n: Name) -> Self {
+            n.0
+        }
+    }
+
+    impl TryFrom<String> for Name {
+        type Error = &'static str;
+
+        fn try_from(s: String) -> Result<Self, Self::Error> {
+            if s.is_empty() {
+                return Err("empty name");
+            }
+            Ok(Self(s))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Reading {
+        #[bitcode(into = "f32", from = "f32")]
+        temperature: Celsius,
+        #[bitcode(into = "String", try_from = "String")]
+        sensor: Name,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum Event {
+        Reading(#[bitcode(into = "f32", from = "f32")] Celsius),
+        Off,
+    }
+
+    #[test]
+    fn field() {
+        let readings = vec![
+            Reading {
+                temperature: Celsius(21.5),
+                sensor: Name("a".into()),
+            },
+            Reading {
+                temperature: Celsius(-3.0),
+                sensor: Name("b".into()),
+            },
+        ];
+        let encoded = encode(&readings);
+        assert_eq!(encoded, encode(&vec![(21.5f32, "a"), (-3.0, "b")]));
+        assert_eq!(decode::<Vec<Reading>>(&encoded).unwrap(), readings);
+        assert!(decode::<Vec<Reading>>(&encode(&vec![(1.0f32, "a"), (2.0, "")])).is_err());
+
+        let events = vec![Event::Reading(Celsius(1.0)), Event::Off];
+        assert_eq!(decode::<Vec<Event>>(&encode(&events)).unwrap(), events);
+    }
+}
@@ -1,15 +1,17 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::derive::{Decode, Encode};
 use crate::length::{LengthDecoder, LengthEncoder};
+use crate::options::{decode_options, reserve_allocation};
 use std::collections::{BTreeMap, HashMap};
 use std::hash::{BuildHasher, Hash};
 use std::num::NonZeroUsize;
 
 #[derive(Debug)]
 pub struct MapEncoder<K: Encode, V: Encode> {
-    lengths: LengthEncoder,
-    keys: K::Encoder,
-    values: V::Encoder,
+    // pub(crate) for indexmap.rs
+    pub(crate) lengths: LengthEncoder,
+    pub(crate) keys: K::Encoder,
+    pub(crate) values: V::Encoder,
 }
 
 // Can't derive since it would bound K + V: Default.
@@ -23,13 +25,19 @@ impl<K: Encode, V: Encode> Default for MapEncoder<K, V> {
     }
 }
 
-impl<K: Encode, V: Encode> Buffer for MapEncoder<K, V> {
+impl<K: Encode, V: Encode> EncoderBuffer for MapEncoder<K, V> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         self.lengths.collect_into(out);
         self.keys.collect_into(out);
         self.values.collect_into(out);
     }
 
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.lengths.collect_into_flushing(out, flush);
+        self.keys.collect_into_flushing(out, flush);
+        self.values.collect_into_flushing(out, flush);
+    }
+
     fn reserve(&mut self, additional: NonZeroUsize) {
         self.lengths.reserve(additional);
         // We don't know the lengths of the maps, so we can't reserve more.
@@ -38,9 +46,10 @@ impl<K: Encode, V: Encode> Buffer for MapEncoder<K, V> {
 
 #[derive(Debug)]
 pub struct MapDecoder<'a, K: Decode<'a>, V: Decode<'a>> {
-    lengths: LengthDecoder<'a>,
-    keys: K::Decoder,
-    values: V::Decoder,
+    // pub(crate) for indexmap.rs
+    pub(crate) lengths: LengthDecoder<'a>,
+    pub(crate) keys: K::Decoder,
+    pub(crate) values: V::Decoder,
 }
 
 // Can't derive since it would bound K + V: Default.
@@ -57,6 +66,10 @@ impl<'a, K: Decode<'a>, V: Decode<'a>> Default for MapDecoder<'a, K, V> {
 impl<'a, K: Decode<'a>, V: Decode<'a>> View<'a> for MapDecoder<'a, K, V> {
     fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
         self.lengths.populate(input, length)?;
+        // Safety: `length` was just passed to populate.
+        let max = decode_options().max_map_len;
+        unsafe { self.lengths.check_max(length, max, "map too long")? };
+        reserve_allocation::<(K, V)>(self.lengths.length())?;
         self.keys.populate(input, self.lengths.length())?;
         self.values.populate(input, self.lengths.length())
     }
@@ -1,27 +1,83 @@
-use crate::coder::{Buffer, Decoder, Encoder, View};
-use crate::consume::expect_eof;
+use crate::coder::{Decoder, Encoder, EncoderBuffer};
+use crate::consume::populate_all;
+use crate::options::{with_decode_options, with_encode_options};
+use crate::target::EncodeTarget;
 use crate::Error;
+use crate::{DecodeOptions, EncodeOptions};
 use std::num::NonZeroUsize;
 
 mod array;
+mod cell;
+mod cmp;
+#[cfg(feature = "derive")]
+mod context;
+pub(crate) mod convert;
+mod cow;
+#[cfg(feature = "derive")]
+mod delta;
+#[cfg(feature = "derive")]
+mod dyn_enum;
 mod empty;
 mod impls;
-mod map;
+#[cfg(feature = "derive")]
+mod intern;
+#[cfg(feature = "derive")]
+mod into;
+pub(crate) mod map;
+mod net;
 mod option;
+mod range;
+#[cfg(feature = "derive")]
+mod range_hint;
+#[cfg(feature = "derive")]
+mod recursive;
+#[cfg(feature = "derive")]
+mod repr;
 mod result;
 mod smart_ptr;
-mod variant;
+mod time;
+#[cfg(feature = "derive")]
+mod validate;
+pub(crate) mod variant;
 pub(crate) mod vec;
+#[cfg(feature = "derive")]
+mod version;
+
+#[cfg(feature = "derive")]
+pub use context::{DecodeWith, EncodeWith};
+#[cfg(feature = "derive")]
+pub use intern::Interner;
 
 // For derive macro.
 #[cfg(feature = "derive")]
 #[doc(hidden)]
 pub mod __private {
-    pub use crate::coder::{uninit_field, Buffer, Decoder, Encoder, Result, View};
-    pub use crate::derive::variant::{VariantDecoder, VariantEncoder};
+    pub use crate::coder::{uninit_field, Decoder, Encoder, EncoderBuffer, Result, View};
+    pub use crate::derive::context::{ContextDecoder, ContextEncoder};
+    pub use crate::derive::delta::{DeltaDecoder, DeltaEncoder};
+    pub use crate::derive::into::{IntoEncoder, TryFromDecoder};
+    pub use crate::derive::range_hint::{RangeHintDecoder, RangeHintEncoder};
+    pub use crate::derive::recursive::{RecursiveDecoder, RecursiveEncoder};
+    pub use crate::derive::repr::{FromRepr, ReprDecoder, ReprEncoder, ToRepr};
+    pub use crate::derive::smart_ptr::FromDecoder;
+    pub use crate::derive::validate::{Validate, ValidateDecoder};
+    pub use crate::derive::variant::{
+        VariantDecoder, VariantEncoder, WideVariantDecoder, WideVariantEncoder,
+    };
+    pub use crate::derive::version::{
+        DefaultDecoder, UpgradeDecoder, VersionDecoder, VersionEncoder, Versioned,
+    };
     pub use crate::derive::{Decode, Encode};
+    pub use crate::describe::Describe;
+    pub use crate::error::error_from_display;
+    pub use crate::visit::Schema;
+    #[cfg(feature = "bitflags")]
+    pub use bitflags::Flags;
+    pub fn field_error(e: crate::Error, name: &'static str, remaining: usize) -> crate::Error {
+        e.in_field(name, remaining)
+    }
     pub fn invalid_enum_variant<T>() -> Result<T> {
-        crate::error::err("invalid enum variant")
+        crate::error::err_kind(crate::ErrorKind::InvalidVariant, "invalid enum variant")
     }
 }
 
@@ -68,40 +124,147 @@ pub fn encode<T: Encode + ?Sized>(t: &T) -> Vec<u8> {
     encoder.collect()
 }
 
+/// Like [`encode`], but with [`EncodeOptions`].
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn encode_with_options<T: Encode + ?Sized>(t: &T, options: &EncodeOptions) -> Vec<u8> {
+    with_encode_options(options, || encode(t))
+}
+
+/// Like [`encode`], but fields with `#[bitcode(context = "Ctx")]` are encoded with
+/// [`EncodeWith<Ctx>`] and `ctx`.
+///
+/// **Warning:** The format is subject to change between major versions.
+#[cfg(feature = "derive")]
+pub fn encode_with_context<T: Encode + ?Sized, Ctx: 'static>(t: &T, ctx: &mut Ctx) -> Vec<u8> {
+    context::with_context(ctx, || encode(t))
+}
+
 /// Decodes a [`&[u8]`][`prim@slice`] into an instance of `T:` [`Decode`].
 ///
 /// **Warning:** The format is subject to change between major versions.
-pub fn decode<'a, T: Decode<'a>>(mut bytes: &'a [u8]) -> Result<T, Error> {
+pub fn decode<'a, T: Decode<'a>>(bytes: &'a [u8]) -> Result<T, Error> {
     let mut decoder = T::Decoder::default();
-    decoder.populate(&mut bytes, 1)?;
-    expect_eof(bytes)?;
+    populate_all(&mut decoder, bytes, 1)?;
     Ok(decode_inline_never(&mut decoder))
 }
 
+/// Like [`decode`], but with [`DecodeOptions`].
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn decode_with_options<'a, T: Decode<'a>>(
+    bytes: &'a [u8],
+    options: &DecodeOptions,
+) -> Result<T, Error> {
+    with_decode_options(options, || decode(bytes))
+}
+
+/// Like [`decode`], but fields with `#[bitcode(context = "Ctx")]` are decoded with
+/// [`DecodeWith<Ctx>`] and `ctx`.
+///
+/// **Warning:** The format is subject to change between major versions.
+#[cfg(feature = "derive")]
+pub fn decode_with_context<'a, T: Decode<'a>, Ctx: 'static>(
+    bytes: &'a [u8],
+    ctx: &mut Ctx,
+) -> Result<T, Error> {
+    context::with_context(ctx, || decode(bytes))
+}
+
 impl crate::buffer::Buffer {
     /// Like [`encode`], but saves allocations between calls.
     pub fn encode<'a, T: Encode + ?Sized>(&'a mut self, t: &T) -> &'a [u8] {
+        let mut out = std::mem::take(&mut self.out);
+        out.clear();
+        self.encode_append(t, &mut out);
+        self.out = out;
+        self.out.as_slice()
+    }
+
+    /// Like [`Buffer::encode`][`crate::Buffer::encode`], but appends the encoding to `out`, so
+    /// callers can reuse their own output buffers (e.g. a send buffer with a header before the
+    /// message).
+    pub fn encode_append<T: Encode + ?Sized>(&mut self, t: &T, out: &mut Vec<u8>) {
         // Safety: Encoders don't have any lifetimes (they don't contain T either).
         let encoder = unsafe { self.registry.get_non_static::<T::Encoder>() };
         encoder.reserve(NonZeroUsize::new(1).unwrap());
         encode_inline_never(encoder, t);
-        self.out.clear();
-        encoder.collect_into(&mut self.out);
-        self.out.as_slice()
+        encoder.collect_into(out);
+    }
+
+    /// Like [`Buffer::encode`][`crate::Buffer::encode`], but writes the encoding to `target` and
+    /// returns its length. Returns an error if `target` can't fit it.
+    ///
+    /// Encoding into a fixed `&mut [u8]` (e.g. a static packet buffer) doesn't allocate once the
+    /// buffer has encoded a message with at least as many values, since its encoders keep their
+    /// allocations. Warm it up with the largest expected message before allocating becomes an
+    /// issue.
+    pub fn encode_into<T: Encode + ?Sized>(
+        &mut self,
+        t: &T,
+        target: &mut (impl EncodeTarget + ?Sized),
+    ) -> Result<usize, Error> {
+        let encoded = self.encode(t);
+        let Some(out) = target.reserve(encoded.len()) else {
+            return crate::error::err_kind(
+                crate::ErrorKind::TargetTooSmall,
+                "encode target too small",
+            );
+        };
+        out[..encoded.len()].copy_from_slice(encoded);
+        Ok(encoded.len())
+    }
+
+    /// Like [`encode_with_options`], but saves allocations between calls.
+    pub fn encode_with_options<'a, T: Encode + ?Sized>(
+        &'a mut self,
+        t: &T,
+        options: &EncodeOptions,
+    ) -> &'a [u8] {
+        with_encode_options(options, || self.encode(t))
     }
 
     /// Like [`decode`], but saves allocations between calls.
-    pub fn decode<'a, T: Decode<'a>>(&mut self, mut bytes: &'a [u8]) -> Result<T, Error> {
+    pub fn decode<'a, T: Decode<'a>>(&mut self, bytes: &'a [u8]) -> Result<T, Error> {
         // Safety: Decoders have dangling pointers to `bytes` from previous calls which haven't been
         // cleared. This isn't an issue in practice because they remain as pointers in FastSlice and
         // aren't dereferenced. If we wanted to be safer we could clear all the decoders but this
         // would result in lots of extra code to maintain and a performance/binary size hit.
         // To detect misuse we run miri tests/cargo fuzz where bytes goes out of scope between calls.
         let decoder = unsafe { self.registry.get_non_static::<T::Decoder>() };
-        decoder.populate(&mut bytes, 1)?;
-        expect_eof(bytes)?;
+        populate_all(decoder, bytes, 1)?;
         Ok(decode_inline_never(decoder))
     }
+
+    /// Like [`Buffer::decode`][`crate::Buffer::decode`] with `Vec<T>`, but appends the elements to
+    /// `out` instead of returning a new `Vec`, so its capacity can be reused (e.g. by clearing it
+    /// between frames). `out` isn't modified if an error is returned.
+    pub fn decode_append<'a, T: Decode<'a>>(
+        &mut self,
+        out: &mut Vec<T>,
+        bytes: &'a [u8],
+    ) -> Result<(), Error> {
+        // Safety: See Buffer::decode. VecDecoder<T> is the same decoder as Vec<T>'s.
+        let decoder = unsafe { self.registry.get_non_static::<vec::VecDecoder<'a, T>>() };
+        populate_all(decoder, bytes, 1)?;
+        let length = decoder.lengths.decode();
+        out.reserve(length);
+        // Safety: `length` was just decoded from `decoder.lengths` and `out` has room for it.
+        unsafe {
+            decoder.decode_elements(&mut out.spare_capacity_mut()[..length]);
+            out.set_len(out.len() + length);
+        }
+        Ok(())
+    }
+
+    /// Like [`decode_with_options`], but saves allocations between calls.
+    pub fn decode_with_options<'a, T: Decode<'a>>(
+        &mut self,
+        bytes: &'a [u8],
+        options: &DecodeOptions,
+    ) -> Result<T, Error> {
+        with_decode_options(options, || self.decode(bytes))
+    }
 }
 
 #[cfg(test)]
@@ -130,6 +293,405 @@ mod tests {
         test!([], [u8; 0]);
     }
 
+    #[test]
+    fn borrowed() {
+        use std::borrow::Cow;
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct MessageView<'a> {
+            topic: &'a str,
+            tags: Vec<&'a str>,
+            // &[u8] can't be decoded since bytes may be packed, but Cow borrows if they weren't.
+            payload: Cow<'a, [u8]>,
+        }
+
+        let payload: Vec<u8> = (0..=255).collect();
+        let message = MessageView {
+            topic: "events",
+            tags: vec!["a", "b"],
+            payload: Cow::Borrowed(&payload),
+        };
+        let encoded = super::encode(&message);
+        let view: MessageView = super::decode(&encoded).unwrap();
+        assert_eq!(view, message);
+
+        let range = encoded.as_ptr_range();
+        assert!(range.contains(&view.topic.as_ptr()));
+        assert!(view.tags.iter().all(|t| range.contains(&t.as_ptr())));
+        assert!(matches!(view.payload, Cow::Borrowed(p) if range.contains(&p.as_ptr())));
+    }
+
+    #[test]
+    fn flatten() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Header {
+            id: u32,
+            name: String,
+        }
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Nested {
+            #[bitcode(flatten)]
+            header: Header,
+            body: Vec<u8>,
+        }
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Flat {
+            id: u32,
+            name: String,
+            body: Vec<u8>,
+        }
+
+        let nested: Vec<_> = (0..10)
+            .map(|i| Nested {
+                header: Header {
+                    id: i,
+                    name: i.to_string(),
+                },
+                body: vec![i as u8; i as usize],
+            })
+            .collect();
+        let flat: Vec<_> = nested
+            .iter()
+            .map(|n| Flat {
+                id: n.header.id,
+                name: n.header.name.clone(),
+                body: n.body.clone(),
+            })
+            .collect();
+
+        let encoded = super::encode(&nested);
+        assert_eq!(encoded, super::encode(&flat));
+        assert_eq!(super::decode::<Vec<Nested>>(&encoded).unwrap(), nested);
+    }
+
+    #[test]
+    fn transparent() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        #[bitcode(transparent)]
+        struct UserId(u64);
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        #[bitcode(transparent)]
+        struct Name {
+            inner: String,
+        }
+
+        let v: Vec<(UserId, Name)> = (0..10)
+            .map(|i| {
+                (
+                    UserId(i * 1000),
+                    Name {
+                        inner: i.to_string(),
+                    },
+                )
+            })
+            .collect();
+        let inner: Vec<(u64, String)> = v.iter().map(|(id, n)| (id.0, n.inner.clone())).collect();
+
+        let encoded = super::encode(&v);
+        assert_eq!(encoded, super::encode(&inner));
+        assert_eq!(super::decode::<Vec<(UserId, Name)>>(&encoded).unwrap(), v);
+    }
+
+    #[test]
+    fn skip() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Entity {
+            id: u32,
+            #[bitcode(skip)]
+            cache: Option<String>,
+            name: String,
+            #[bitcode(skip = "vec![0; 4]")]
+            scratch: Vec<u8>,
+        }
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        enum Event {
+            Spawn(Entity),
+            Move {
+                id: u32,
+                #[bitcode(skip)]
+                cached_distance: f32,
+            },
+        }
+
+        let entities: Vec<_> = (0..10)
+            .map(|i| Entity {
+                id: i,
+                cache: Some("cached".into()),
+                name: i.to_string(),
+                scratch: vec![1; i as usize],
+            })
+            .collect();
+        let encoded = super::encode(&entities);
+        let fields: Vec<_> = entities.iter().map(|e| (e.id, e.name.clone())).collect();
+        assert_eq!(encoded, super::encode(&fields));
+        let decoded: Vec<Entity> = super::decode(&encoded).unwrap();
+        assert!(decoded
+            .iter()
+            .zip(&entities)
+            .all(|(d, e)| d.id == e.id && d.name == e.name));
+        assert!(decoded
+            .iter()
+            .all(|d| d.cache.is_none() && d.scratch == [0; 4]));
+
+        let events = vec![
+            Event::Move {
+                id: 1,
+                cached_distance: 2.0,
+            },
+            Event::Spawn(Entity {
+                id: 2,
+                cache: None,
+                name: "2".into(),
+                scratch: vec![0; 4],
+            }),
+        ];
+        let decoded: Vec<Event> = super::decode(&super::encode(&events)).unwrap();
+        assert_eq!(
+            decoded[0],
+            Event::Move {
+                id: 1,
+                cached_distance: 0.0
+            }
+        );
+        assert_eq!(decoded[1], events[1]);
+    }
+
+    // Doesn't implement Encode/Decode.
+    #[derive(Debug, PartialEq)]
+    struct Rgb(u8, u8, u8);
+
+    mod rgb_as_u32 {
+        pub type Repr = u32;
+        pub(super) fn encode(rgb: &super::Rgb) -> Repr {
+            u32::from_le_bytes([rgb.0, rgb.1, rgb.2, 0])
+        }
+        pub(super) fn decode(repr: Repr) -> super::Rgb {
+            let [r, g, b, _] = repr.to_le_bytes();
+            super::Rgb(r, g, b)
+        }
+    }
+
+    #[test]
+    fn with() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Pixel {
+            x: u16,
+            #[bitcode(with = "rgb_as_u32")]
+            color: Rgb,
+        }
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        enum Fill {
+            None,
+            Solid(#[bitcode(with = "rgb_as_u32")] Rgb),
+        }
+
+        let pixels: Vec<_> = (0..10u8)
+            .map(|i| Pixel {
+                x: i as u16,
+                color: Rgb(i, i * 2, 255 - i),
+            })
+            .collect();
+        let encoded = super::encode(&pixels);
+        let reprs: Vec<_> = pixels
+            .iter()
+            .map(|p| (p.x, rgb_as_u32::encode(&p.color)))
+            .collect();
+        assert_eq!(encoded, super::encode(&reprs));
+        assert_eq!(super::decode::<Vec<Pixel>>(&encoded).unwrap(), pixels);
+
+        let fills = vec![Fill::None, Fill::Solid(Rgb(1, 2, 3))];
+        assert_eq!(
+            super::decode::<Vec<Fill>>(&super::encode(&fills)).unwrap(),
+            fills
+        );
+    }
+
+    #[test]
+    fn with_variant() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        enum Payload {
+            Raw(Vec<u8>),
+            #[bitcode(with = "run_length")]
+            Compressed {
+                byte: u8,
+                len: usize,
+            },
+            #[bitcode(with = "rgb_tuple")]
+            Color(Rgb),
+        }
+
+        // Only the repeated bytes are encoded, not the length.
+        mod run_length {
+            pub type Repr = u8;
+            pub fn encode((byte, _): (&u8, &usize)) -> Repr {
+                *byte
+            }
+            pub fn decode(byte: Repr) -> (u8, usize) {
+                (byte, 4)
+            }
+        }
+
+        mod rgb_tuple {
+            pub type Repr = u32;
+            pub(super) fn encode((rgb,): (&super::Rgb,)) -> Repr {
+                super::rgb_as_u32::encode(rgb)
+            }
+            pub(super) fn decode(repr: Repr) -> (super::Rgb,) {
+                (super::rgb_as_u32::decode(repr),)
+            }
+        }
+
+        let payloads = vec![
+            Payload::Raw(vec![1, 2, 3]),
+            Payload::Compressed { byte: 5, len: 4 },
+            Payload::Color(Rgb(1, 2, 3)),
+            Payload::Compressed { byte: 6, len: 4 },
+        ];
+        let encoded = super::encode(&payloads);
+        assert_eq!(super::decode::<Vec<Payload>>(&encoded).unwrap(), payloads);
+    }
+
+    #[test]
+    fn cfg() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Player {
+            id: u32,
+            // Doesn't exist, but is never compiled.
+            #[cfg(any())]
+            never: DoesNotExist,
+            #[cfg(debug_assertions)]
+            debug_name: String,
+            #[cfg_attr(all(), bitcode(skip))]
+            scratch: u8,
+        }
+
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        enum Message {
+            Join(Player),
+            #[cfg(any())]
+            Never(DoesNotExist),
+            Leave(u32, #[cfg(any())] DoesNotExist),
+        }
+
+        let player = Player {
+            id: 5,
+            #[cfg(debug_assertions)]
+            debug_name: "5".into(),
+            scratch: 0,
+        };
+        #[cfg(debug_assertions)]
+        let expected = super::encode(&(5u32, "5"));
+        #[cfg(not(debug_assertions))]
+        let expected = super::encode(&5u32);
+        assert_eq!(super::encode(&player), expected);
+
+        let messages = vec![Message::Join(player), Message::Leave(5)];
+        assert_eq!(
+            super::decode::<Vec<Message>>(&super::encode(&messages)).unwrap(),
+            messages
+        );
+    }
+
+    #[test]
+    fn error_context() {
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Item(u8, String);
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        enum Slot {
+            Empty,
+            Full { item: Item },
+        }
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct Player {
+            id: u32,
+            inventory: Vec<Slot>,
+        }
+
+        let players = vec![Player {
+            id: 1,
+            inventory: vec![
+                Slot::Empty,
+                Slot::Full {
+                    item: Item(2, "ab".into()),
+                },
+            ],
+        }];
+        let mut encoded = super::encode(&players);
+        *encoded.last_mut().unwrap() = 0xFF; // Invalid UTF-8.
+        let e = super::decode::<Vec<Player>>(&encoded).unwrap_err();
+        assert_eq!(e.kind(), crate::ErrorKind::InvalidUtf8);
+        if cfg!(debug_assertions) {
+            assert_eq!(e.path().as_deref(), Some("inventory.Full.item.1"));
+            let offset = encoded.len() - 3; // Length byte + 2 string bytes.
+            assert_eq!(e.offset(), Some(offset));
+            assert_eq!(
+                e.to_string(),
+                format!("invalid utf8 at inventory.Full.item.1 (byte {offset})")
+            );
+        }
+
+        // Not in a field.
+        let e = super::decode::<Vec<Player>>(&[]).unwrap_err();
+        assert_eq!(e.kind(), crate::ErrorKind::Eof);
+        assert_eq!(e.path(), None);
+        let trailing = [super::encode(&5u8), vec![0]].concat();
+        let e = super::decode::<u8>(&trailing).unwrap_err();
+        assert_eq!(e.path(), None);
+        assert_eq!(e.offset(), cfg!(debug_assertions).then_some(1));
+    }
+
+    #[test]
+    fn phantom_data() {
+        // Only used in PhantomData so it doesn't require T: Encode/Decode.
+        #[derive(Encode, Decode)]
+        struct Handle<T> {
+            id: u64,
+            _marker: std::marker::PhantomData<T>,
+            _fn_marker: std::marker::PhantomData<fn() -> T>,
+        }
+        struct NotEncode;
+
+        let handle = Handle::<NotEncode> {
+            id: 5,
+            _marker: Default::default(),
+            _fn_marker: Default::default(),
+        };
+        let encoded = super::encode(&handle);
+        assert_eq!(encoded, super::encode(&5u64));
+        assert_eq!(super::decode::<Handle<NotEncode>>(&encoded).unwrap().id, 5);
+    }
+
+    #[test]
+    fn bound() {
+        // Id<T> is Encode/Decode for every T, but Handle<T> would infer T: Encode/Decode.
+        #[derive(Encode, Decode)]
+        struct Id<T>(u32, std::marker::PhantomData<T>);
+        #[derive(Encode, Decode)]
+        #[bitcode(bound = "")]
+        struct Handle<T>(Id<T>);
+        #[derive(Encode, Decode)]
+        #[bitcode(bound = "T")]
+        struct Replaced<T>(Id<T>, T);
+        struct NotEncode;
+
+        let handle = Handle::<NotEncode>(Id(5, Default::default()));
+        let encoded = super::encode(&handle);
+        assert_eq!(encoded, super::encode(&5u32));
+        assert_eq!(
+            super::decode::<Handle<NotEncode>>(&encoded).unwrap().0 .0,
+            5
+        );
+
+        let replaced = Replaced(Id(5, Default::default()), 6u8);
+        let decoded: Replaced<u8> = super::decode(&super::encode(&replaced)).unwrap();
+        assert_eq!((decoded.0 .0, decoded.1), (5, 6));
+    }
+
     #[derive(Encode, Decode)]
     enum Never {}
 
@@ -170,6 +732,17 @@ mod tests {
     #[derive(Encode, Decode)]
     struct ConstGeneric<const N: usize>([u8; N]);
 
+    #[derive(Encode, Decode)]
+    struct ConstGenerics<T, const W: usize, const H: usize = 2> {
+        cells: [[T; W]; H],
+    }
+
+    #[derive(Encode, Decode)]
+    enum ConstGenericEnum<'a, const N: usize> {
+        A([&'a str; N]),
+        B { b: [bool; N] },
+    }
+
     #[derive(Encode, Decode)]
     struct Empty;
 
@@ -0,0 +1,54 @@
+use crate::derive::convert::impl_convert;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+// Addresses are encoded as their bytes and the enums like `enum IpAddr { V4(..), V6(..) }` (which
+// is the same as a Result).
+impl_convert!(Ipv4Addr => [u8; 4], |s| s.octets(), |r| r.into());
+impl_convert!(Ipv6Addr => [u8; 16], |s| s.octets(), |r| r.into());
+impl_convert!(IpAddr => std::result::Result<Ipv4Addr, Ipv6Addr>, |s| match *s {
+    IpAddr::V4(v4) => Ok(v4),
+    IpAddr::V6(v6) => Err(v6),
+}, |r| r.map_or_else(IpAddr::V6, IpAddr::V4));
+impl_convert!(SocketAddrV4 => (Ipv4Addr, u16), |s| (*s.ip(), s.port()), |r| Self::new(r.0, r.1));
+impl_convert!(SocketAddrV6 => (Ipv6Addr, u16, u32, u32), |s| {
+    (*s.ip(), s.port(), s.flowinfo(), s.scope_id())
+}, |r| Self::new(r.0, r.1, r.2, r.3));
+impl_convert!(SocketAddr => std::result::Result<SocketAddrV4, SocketAddrV6>, |s| match *s {
+    SocketAddr::V4(v4) => Ok(v4),
+    SocketAddr::V6(v6) => Err(v6),
+}, |r| r.map_or_else(SocketAddr::V6, SocketAddr::V4));
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+    #[test]
+    fn ip_addr() {
+        let v4 = Ipv4Addr::new(192, 168, 0, 1);
+        let v6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        assert_eq!(encode(&v4), encode(&v4.octets()));
+        assert_eq!(encode(&v6), encode(&v6.octets()));
+
+        let v = vec![
+            IpAddr::V4(v4),
+            IpAddr::V6(v6),
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+        ];
+        let encoded = encode(&v);
+        let results: Vec<Result<[u8; 4], [u8; 16]>> =
+            vec![Ok(v4.octets()), Err(v6.octets()), Ok([127, 0, 0, 1])];
+        assert_eq!(encoded, encode(&results));
+        assert_eq!(decode::<Vec<IpAddr>>(&encoded).unwrap(), v);
+    }
+
+    #[test]
+    fn socket_addr() {
+        let v4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 8080);
+        let v6 = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 443, 1, 2);
+        let v = vec![SocketAddr::V4(v4), SocketAddr::V6(v6)];
+        assert_eq!(decode::<Vec<SocketAddr>>(&encode(&v)).unwrap(), v);
+        assert_eq!(decode::<SocketAddrV6>(&encode(&v6)).unwrap(), v6);
+        assert_eq!(encode(&v4), encode(&(v4.ip().octets(), 8080u16)));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::variant::{VariantDecoder, VariantEncoder};
+use crate::derive::{Decode, Encode};
+use std::mem::MaybeUninit;
+use std::num::NonZeroUsize;
+use std::ops::{Bound, Range, RangeFrom, RangeInclusive, RangeTo, RangeToInclusive};
+
+/// Encodes `Range<T>` and `RangeInclusive<T>` like a `(T, T)` of their start and end. Whether a
+/// `RangeInclusive` is exhausted isn't encoded.
+#[derive(Debug)]
+pub struct RangeEncoder<T: Encode> {
+    start: T::Encoder,
+    end: T::Encoder,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T: Encode> Default for RangeEncoder<T> {
+    fn default() -> Self {
+        Self {
+            start: Default::default(),
+            end: Default::default(),
+        }
+    }
+}
+
+impl<T: Encode> Encoder<Range<T>> for RangeEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Range<T>) {
+        self.start.encode(&t.start);
+        self.end.encode(&t.end);
+    }
+}
+
+impl<T: Encode> Encoder<RangeInclusive<T>> for RangeEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &RangeInclusive<T>) {
+        self.start.encode(t.start());
+        self.end.encode(t.end());
+    }
+}
+
+impl<T: Encode> EncoderBuffer for RangeEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.start.collect_into(out);
+        self.end.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.start.collect_into_flushing(out, flush);
+        self.end.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.start.reserve(additional);
+        self.end.reserve(additional);
+    }
+}
+
+#[derive(Debug)]
+pub struct RangeDecoder<'a, T: Decode<'a>> {
+    start: T::Decoder,
+    end: T::Decoder,
+}
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: Decode<'a>> Default for RangeDecoder<'a, T> {
+    fn default() -> Self {
+        Self {
+            start: Default::default(),
+            end: Default::default(),
+        }
+    }
+}
+
+impl<'a, T: Decode<'a>> View<'a> for RangeDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.start.populate(input, length)?;
+        self.end.populate(input, length)
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, Range<T>> for RangeDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> Range<T> {
+        self.start.decode()..self.end.decode()
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, RangeInclusive<T>> for RangeDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> RangeInclusive<T> {
+        self.start.decode()..=self.end.decode()
+    }
+}
+
+/// Encodes `RangeFrom<T>`, `RangeTo<T>` and `RangeToInclusive<T>` like a `T` of their only bound.
+#[derive(Debug)]
+pub struct HalfRangeEncoder<T: Encode>(T::Encoder);
+
+// Can't derive since it would bound T: Default.
+impl<T: Encode> Default for HalfRangeEncoder<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<T: Encode> Encoder<RangeFrom<T>> for HalfRangeEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &RangeFrom<T>) {
+        self.0.encode(&t.start);
+    }
+}
+
+impl<T: Encode> Encoder<RangeTo<T>> for HalfRangeEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &RangeTo<T>) {
+        self.0.encode(&t.end);
+    }
+}
+
+impl<T: Encode> Encoder<RangeToInclusive<T>> for HalfRangeEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &RangeToInclusive<T>) {
+        self.0.encode(&t.end);
+    }
+}
+
+impl<T: Encode> EncoderBuffer for HalfRangeEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+#[derive(Debug)]
+pub struct HalfRangeDecoder<'a, T: Decode<'a>>(T::Decoder);
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: Decode<'a>> Default for HalfRangeDecoder<'a, T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<'a, T: Decode<'a>> View<'a> for HalfRangeDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0.populate(input, length)
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, RangeFrom<T>> for HalfRangeDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> RangeFrom<T> {
+        self.0.decode()..
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, RangeTo<T>> for HalfRangeDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> RangeTo<T> {
+        ..self.0.decode()
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, RangeToInclusive<T>> for HalfRangeDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> RangeToInclusive<T> {
+        ..=self.0.decode()
+    }
+}
+
+/// Encodes `Bound<T>` like an enum with the variants `Included(T)`, `Excluded(T)` and `Unbounded`.
+#[derive(Debug)]
+pub struct BoundEncoder<T: Encode> {
+    variants: VariantEncoder<3>,
+    included: T::Encoder,
+    excluded: T::Encoder,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T: Encode> Default for BoundEncoder<T> {
+    fn default() -> Self {
+        Self {
+            variants: Default::default(),
+            included: Default::default(),
+            excluded: Default::default(),
+        }
+    }
+}
+
+impl<T: Encode> Encoder<Bound<T>> for BoundEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Bound<T>) {
+        match t {
+            Bound::Included(t) => {
+                self.variants.encode(&0);
+                self.included.reserve(NonZeroUsize::new(1).unwrap());
+                self.included.encode(t);
+            }
+            Bound::Excluded(t) => {
+                self.variants.encode(&1);
+                self.excluded.reserve(NonZeroUsize::new(1).unwrap());
+                self.excluded.encode(t);
+            }
+            Bound::Unbounded => self.variants.encode(&2),
+        }
+    }
+}
+
+impl<T: Encode> EncoderBuffer for BoundEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.variants.collect_into(out);
+        self.included.collect_into(out);
+        self.excluded.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.variants.collect_into_flushing(out, flush);
+        self.included.collect_into_flushing(out, flush);
+        self.excluded.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.variants.reserve(additional);
+        // We don't know how many are Included or Excluded, so we can't reserve more.
+    }
+}
+
+#[derive(Debug)]
+pub struct BoundDecoder<'a, T: Decode<'a>> {
+    variants: VariantDecoder<'a, 3, false>,
+    included: T::Decoder,
+    excluded: T::Decoder,
+}
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: Decode<'a>> Default for BoundDecoder<'a, T> {
+    fn default() -> Self {
+        Self {
+            variants: Default::default(),
+            included: Default::default(),
+            excluded: Default::default(),
+        }
+    }
+}
+
+impl<'a, T: Decode<'a>> View<'a> for BoundDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.variants.populate(input, length)?;
+        self.included.populate(input, self.variants.length(0))?;
+        self.excluded.populate(input, self.variants.length(1))
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, Bound<T>> for BoundDecoder<'a, T> {
+    #[inline(always)]
+    fn decode_in_place(&mut self, out: &mut MaybeUninit<Bound<T>>) {
+        out.write(match self.variants.decode() {
+            0 => Bound::Included(self.included.decode()),
+            1 => Bound::Excluded(self.excluded.decode()),
+            _ => Bound::Unbounded,
+        });
+    }
+}
+
+impl<T: Encode> Encode for Range<T> {
+    type Encoder = RangeEncoder<T>;
+}
+impl<'a, T: Decode<'a>> Decode<'a> for Range<T> {
+    type Decoder = RangeDecoder<'a, T>;
+}
+impl<T: Encode> Encode for RangeInclusive<T> {
+    type Encoder = RangeEncoder<T>;
+}
+impl<'a, T: Decode<'a>> Decode<'a> for RangeInclusive<T> {
+    type Decoder = RangeDecoder<'a, T>;
+}
+
+macro_rules! impl_half_range {
+    ($($t:ident),+) => {
+        $(
+            impl<T: Encode> Encode for $t<T> {
+                type Encoder = HalfRangeEncoder<T>;
+            }
+            impl<'a, T: Decode<'a>> Decode<'a> for $t<T> {
+                type Decoder = HalfRangeDecoder<'a, T>;
+            }
+        )+
+    };
+}
+impl_half_range!(RangeFrom, RangeTo, RangeToInclusive);
+
+impl<T: Encode> Encode for Bound<T> {
+    type Encoder = BoundEncoder<T>;
+}
+impl<'a, T: Decode<'a>> Decode<'a> for Bound<T> {
+    type Decoder = BoundDecoder<'a, T>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::ops::Bound;
+
+    #[test]
+    fn range() {
+        let v: Vec<(u32, u32)> = crate::random_data(100);
+        let encoded = encode(&v);
+        let ranges: Vec<_> = v.iter().map(|&(s, e)| s..e).collect();
+        assert_eq!(encode(&ranges), encoded);
+        assert_eq!(
+            decode::<Vec<std::ops::Range<u32>>>(&encoded).unwrap(),
+            ranges
+        );
+        let inclusive: Vec<_> = v.iter().map(|&(s, e)| s..=e).collect();
+        assert_eq!(encode(&inclusive), encoded);
+        assert_eq!(
+            decode::<Vec<std::ops::RangeInclusive<u32>>>(&encoded).unwrap(),
+            inclusive
+        );
+
+        let encoded = encode(&5u8);
+        assert_eq!(encode(&(5u8..)), encoded);
+        assert_eq!(encode(&(..5u8)), encoded);
+        assert_eq!(encode(&(..=5u8)), encoded);
+        assert_eq!(decode::<std::ops::RangeFrom<u8>>(&encoded).unwrap(), 5..);
+        assert_eq!(decode::<std::ops::RangeTo<u8>>(&encoded).unwrap(), ..5);
+        assert_eq!(
+            decode::<std::ops::RangeToInclusive<u8>>(&encoded).unwrap(),
+            ..=5
+        );
+    }
+
+    #[test]
+    fn bound() {
+        #[derive(crate::Encode)]
+        enum MyBound {
+            Included(String),
+            Excluded(String),
+            Unbounded,
+        }
+
+        let v: Vec<(u8, String)> = crate::random_data::<u8>(100)
+            .into_iter()
+            .map(|i| (i % 3, i.to_string()))
+            .collect();
+        let bounds: Vec<_> = v
+            .iter()
+            .map(|(i, s)| match i {
+                0 => Bound::Included(s.clone()),
+                1 => Bound::Excluded(s.clone()),
+                _ => Bound::Unbounded,
+            })
+            .collect();
+        let my_bounds: Vec<_> = v
+            .iter()
+            .map(|(i, s)| match i {
+                0 => MyBound::Included(s.clone()),
+                1 => MyBound::Excluded(s.clone()),
+                _ => MyBound::Unbounded,
+            })
+            .collect();
+        let encoded = encode(&bounds);
+        assert_eq!(encoded, encode(&my_bounds));
+        assert_eq!(decode::<Vec<Bound<String>>>(&encoded).unwrap(), bounds);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::int::{IntDecoder, IntEncoder};
+use std::num::NonZeroUsize;
+
+/// Number of bytes needed to encode offsets up to `span`.
+fn span_bytes(span: u128) -> usize {
+    crate::nightly::div_ceil_usize((u128::BITS - span.leading_zeros()) as usize, 8)
+}
+
+/// Used by `#[derive(Encode)]` for fields with `#[bitcode(range = "A..=B")]`. Encodes each value's
+/// offset from `A` (which is at most `SPAN = B - A`) as one `u8` column per byte. Since each column
+/// is packed separately, the most significant byte only takes as many bits as `SPAN` needs (e.g.
+/// `0..=4095` takes 12 bits per value instead of 16).
+///
+/// **Panics** if a value isn't in the range.
+#[derive(Debug)]
+pub struct RangeHintEncoder<const SPAN: u128>(Vec<IntEncoder<u8>>);
+
+impl<const SPAN: u128> Default for RangeHintEncoder<SPAN> {
+    fn default() -> Self {
+        Self((0..span_bytes(SPAN)).map(|_| Default::default()).collect())
+    }
+}
+
+impl<const SPAN: u128> Encoder<u128> for RangeHintEncoder<SPAN> {
+    #[inline(always)]
+    fn encode(&mut self, &offset: &u128) {
+        assert!(offset <= SPAN, "value outside of #[bitcode(range)]");
+        for (i, bytes) in self.0.iter_mut().enumerate() {
+            bytes.encode(&((offset >> (i * 8)) as u8));
+        }
+    }
+}
+
+impl<const SPAN: u128> EncoderBuffer for RangeHintEncoder<SPAN> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.iter_mut().for_each(|bytes| bytes.collect_into(out));
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0
+            .iter_mut()
+            .for_each(|bytes| bytes.reserve(additional));
+    }
+}
+
+#[derive(Debug)]
+pub struct RangeHintDecoder<'a, const SPAN: u128>(Vec<IntDecoder<'a, u8>>);
+
+impl<const SPAN: u128> Default for RangeHintDecoder<'_, SPAN> {
+    fn default() -> Self {
+        Self((0..span_bytes(SPAN)).map(|_| Default::default()).collect())
+    }
+}
+
+fn decode_offset<'a>(bytes: &mut [IntDecoder<'a, u8>]) -> u128 {
+    bytes.iter_mut().enumerate().fold(0, |offset, (i, bytes)| {
+        let byte: u8 = bytes.decode();
+        offset | (byte as u128) << (i * 8)
+    })
+}
+
+impl<'a, const SPAN: u128> View<'a> for RangeHintDecoder<'a, SPAN> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        for bytes in &mut self.0 {
+            bytes.populate(input, length)?;
+        }
+
+        // Offsets can only be out of range if SPAN doesn't use all the bits of its bytes.
+        if SPAN
+            .checked_add(1)
+            .is_some_and(|n| span_bytes(n) == self.0.len())
+        {
+            let mut decoder: Vec<_> = self.0.iter().map(|b| b.borrowed_clone()).collect();
+            if (0..length).any(|_| decode_offset(&mut decoder) > SPAN) {
+                return crate::error::err("value outside of range");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, const SPAN: u128> Decoder<'a, u128> for RangeHintDecoder<'a, SPAN> {
+    #[inline(always)]
+    fn decode(&mut self) -> u128 {
+        decode_offset(&mut self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Voxel {
+        #[bitcode(range = "0..=4095")]
+        x: u16,
+        #[bitcode(range = "-8..8")]
+        y: i8,
+        #[bitcode(range = "1000..=1000")]
+        z: u32,
+    }
+
+    #[test]
+    fn range_hint() {
+        let voxels: Vec<Voxel> = crate::random_data::<(u16, i8)>(1000)
+            .into_iter()
+            .map(|(x, y)| Voxel {
+                x: x % 4096,
+                y: y % 8,
+                z: 1000,
+            })
+            .collect();
+        let encoded = encode(&voxels);
+        assert_eq!(decode::<Vec<Voxel>>(&encoded).unwrap(), voxels);
+
+        // 12 bits for x, 4 bits for y and nothing for z.
+        let unhinted: Vec<_> = voxels.iter().map(|v| (v.x, v.y, v.z)).collect();
+        assert!(encoded.len() < 2100, "{}", encoded.len());
+        assert!(encode(&unhinted).len() > 2500);
+
+        // Offsets that don't fit in the range are rejected.
+        assert!(decode::<Voxel>(&encode(&(0u8, 16u8, 8u8))).is_err());
+        assert!(decode::<Voxel>(&encode(&(255u8, 15u8, 15u8))).is_ok());
+        assert!(decode::<Voxel>(&encode(&(255u8, 15u8, 16u8))).is_err());
+    }
+
+    #[test]
+    #[should_panic = "value outside of #[bitcode(range)]"]
+    fn range_hint_panics() {
+        encode(&Voxel {
+            x: 0,
+            y: 8,
+            z: 1000,
+        });
+    }
+}
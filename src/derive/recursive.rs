@@ -0,0 +1,189 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::{Decode, Encode};
+use std::cell::Cell;
+use std::mem::MaybeUninit;
+use std::num::NonZeroUsize;
+
+/// Maximum depth of nested [`RecursiveDecoder`]s, so malicious input can't overflow the stack.
+const MAX_DEPTH: usize = 1024;
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Used by `#[derive(Encode)]` for fields whose type contains the type being derived (e.g.
+/// `Box<Self>`). Each level of recursion is encoded by its own encoder, which is boxed so the
+/// encoder doesn't have infinite size and only allocated once that level is reached.
+///
+/// Nothing is written for a level without any values, which is where encoding stops.
+pub struct RecursiveEncoder<T: Encode + ?Sized> {
+    inner: Option<Box<T::Encoder>>,
+    length: usize,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T: Encode + ?Sized> Default for RecursiveEncoder<T> {
+    fn default() -> Self {
+        Self {
+            inner: None,
+            length: 0,
+        }
+    }
+}
+
+impl<T: Encode + ?Sized> Encoder<T> for RecursiveEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, t: &T) {
+        self.length += 1;
+        // Safety: reserve allocated inner.
+        unsafe { self.inner.as_mut().unwrap_unchecked() }.encode(t);
+    }
+}
+
+impl<T: Encode + ?Sized> EncoderBuffer for RecursiveEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        if std::mem::take(&mut self.length) != 0 {
+            self.inner.as_mut().unwrap().collect_into(out);
+        }
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        if std::mem::take(&mut self.length) != 0 {
+            self.inner
+                .as_mut()
+                .unwrap()
+                .collect_into_flushing(out, flush);
+        }
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.inner
+            .get_or_insert_with(Default::default)
+            .reserve(additional);
+    }
+}
+
+pub struct RecursiveDecoder<'a, T: Decode<'a>>(Option<Box<T::Decoder>>);
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: Decode<'a>> Default for RecursiveDecoder<'a, T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<'a, T: Decode<'a>> View<'a> for RecursiveDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        if length == 0 {
+            return Ok(()); // Nothing was written for this level.
+        }
+
+        struct Restore(usize);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                DEPTH.with(|d| d.set(self.0));
+            }
+        }
+        let depth = DEPTH.with(Cell::get);
+        if depth >= MAX_DEPTH {
+            return crate::error::err_kind(
+                crate::ErrorKind::LimitExceeded,
+                "recursion limit exceeded",
+            );
+        }
+        let _restore = Restore(depth);
+        DEPTH.with(|d| d.set(depth + 1));
+
+        self.0
+            .get_or_insert_with(Default::default)
+            .populate(input, length)
+    }
+}
+
+impl<'a, T: Decode<'a>> Decoder<'a, T> for RecursiveDecoder<'a, T> {
+    #[inline(always)]
+    fn decode_in_place(&mut self, out: &mut MaybeUninit<T>) {
+        // Safety: populate allocated the decoder since it can only be called if length != 0.
+        unsafe { self.0.as_mut().unwrap_unchecked() }.decode_in_place(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum Expr {
+        Add(Box<Expr>, Box<Expr>),
+        Neg(Box<Self>),
+        Lit(i64),
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Tree<T> {
+        value: T,
+        children: Vec<Tree<T>>,
+    }
+
+    fn tree(depth: u32) -> Tree<u32> {
+        Tree {
+            value: depth,
+            children: (0..depth).map(tree).collect(),
+        }
+    }
+
+    #[test]
+    fn recursive() {
+        let expr = Expr::Add(
+            Box::new(Expr::Lit(1)),
+            Box::new(Expr::Neg(Box::new(Expr::Add(
+                Box::new(Expr::Lit(2)),
+                Box::new(Expr::Lit(3)),
+            )))),
+        );
+        let exprs = vec![expr, Expr::Lit(4), Expr::Neg(Box::new(Expr::Lit(5)))];
+        assert_eq!(decode::<Vec<Expr>>(&encode(&exprs)).unwrap(), exprs);
+
+        let tree = tree(6);
+        assert_eq!(decode::<Tree<u32>>(&encode(&tree)).unwrap(), tree);
+
+        // Reusing encoders/decoders with different depths.
+        let mut buffer = crate::Buffer::new();
+        for depth in [3, 0, 5, 1] {
+            let tree = self::tree(depth);
+            let encoded = buffer.encode(&tree).to_vec();
+            assert_eq!(encoded, encode(&tree));
+            assert_eq!(buffer.decode::<Tree<u32>>(&encoded).unwrap(), tree);
+        }
+    }
+
+    mod other {
+        #[derive(Debug, PartialEq, crate::Encode, crate::Decode, crate::Describe)]
+        pub struct Node(pub u8);
+    }
+
+    // Describe doesn't support recursive types, so this only compiles if a path that merely ends in
+    // `Node` isn't treated as recursive.
+    #[derive(Debug, PartialEq, Encode, Decode, crate::Describe)]
+    struct Node {
+        inner: other::Node,
+        maybe: Option<self::other::Node>,
+    }
+
+    #[test]
+    fn not_recursive() {
+        let node = Node {
+            inner: other::Node(1),
+            maybe: Some(other::Node(2)),
+        };
+        assert_eq!(decode::<Node>(&encode(&node)).unwrap(), node);
+    }
+
+    #[test]
+    fn recursion_limit() {
+        let nested = |depth| (0..depth).fold(Expr::Lit(0), |expr, _| Expr::Neg(Box::new(expr)));
+        let expr = nested(1000);
+        assert_eq!(decode::<Expr>(&encode(&expr)).unwrap(), expr);
+        assert!(decode::<Expr>(&encode(&nested(1100))).is_err());
+    }
+}
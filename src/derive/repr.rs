@@ -0,0 +1,176 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::int::{IntDecoder, IntEncoder};
+use crate::pack_ints::Int;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+/// Implemented by `#[derive(Encode)]` on fieldless enums with `#[bitcode(repr = "R")]`.
+pub trait ToRepr<R> {
+    /// Returns the discriminant of `self`.
+    fn to_repr(&self) -> R;
+}
+
+/// Implemented by `#[derive(Decode)]` on fieldless enums with `#[bitcode(repr = "R")]`.
+pub trait FromRepr<R>: Sized {
+    /// Returns the variant with the discriminant `repr` (or `None` if there isn't one).
+    fn from_repr(repr: R) -> Option<Self>;
+}
+
+pub struct ReprEncoder<T, R: Int>(IntEncoder<R>, PhantomData<fn(&T)>);
+
+// Can't derive since it would bound T: Default.
+impl<T, R: Int> Default for ReprEncoder<T, R> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<T: ToRepr<R>, R: Int> Encoder<T> for ReprEncoder<T, R> {
+    #[inline(always)]
+    fn encode(&mut self, t: &T) {
+        self.0.encode(&t.to_repr());
+    }
+}
+
+impl<T, R: Int> EncoderBuffer for ReprEncoder<T, R> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+pub struct ReprDecoder<'a, T, R: Int>(IntDecoder<'a, R>, PhantomData<fn() -> T>);
+
+// Can't derive since it would bound T: Default.
+impl<T, R: Int> Default for ReprDecoder<'_, T, R> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<'a, T: FromRepr<R>, R: Int> View<'a> for ReprDecoder<'a, T, R> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0.populate(input, length)?;
+
+        let mut decoder = self.0.borrowed_clone();
+        if (0..length).any(|_| T::from_repr(decoder.decode()).is_none()) {
+            return crate::derive::__private::invalid_enum_variant();
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: FromRepr<R>, R: Int> Decoder<'a, T> for ReprDecoder<'a, T, R> {
+    #[inline(always)]
+    fn decode(&mut self) -> T {
+        // Safety: populate ensures every discriminant has a variant.
+        unsafe { T::from_repr(self.0.decode()).unwrap_unchecked() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[repr(u16)]
+    #[derive(Copy, Clone, Debug, PartialEq, Encode, Decode)]
+    #[bitcode(repr = "u16")]
+    enum Ffi {
+        A = 1,
+        B = 500,
+        C = 0xFFFF,
+    }
+
+    #[derive(Copy, Clone, Debug, PartialEq, Encode, Decode)]
+    #[bitcode(repr = "i8")]
+    enum Signed {
+        A = -128,
+        B = 0,
+        C = 127,
+    }
+
+    #[derive(Encode, Decode)]
+    #[bitcode(repr = "u8")]
+    enum Never {}
+
+    #[test]
+    fn repr() {
+        for v in [Ffi::A, Ffi::B, Ffi::C] {
+            assert_eq!(encode(&v), encode(&(v as u16)));
+            assert_eq!(decode::<Ffi>(&encode(&v)).unwrap(), v);
+        }
+        for v in [Signed::A, Signed::B, Signed::C] {
+            assert_eq!(encode(&v), encode(&(v as i8)));
+            assert_eq!(decode::<Signed>(&encode(&v)).unwrap(), v);
+        }
+        let v = vec![Ffi::C, Ffi::A, Ffi::B, Ffi::A];
+        assert_eq!(decode::<Vec<Ffi>>(&encode(&v)).unwrap(), v);
+
+        // Unknown discriminants are rejected.
+        assert!(decode::<Ffi>(&encode(&0u16)).is_err());
+        assert!(decode::<Vec<Ffi>>(&encode(&vec![1u16, 500, 2])).is_err());
+        assert!(decode::<Signed>(&encode(&1i8)).is_err());
+        assert!(decode::<Never>(&encode(&0u8)).is_err());
+        assert!(decode::<Vec<Never>>(&encode(&Vec::<u8>::new())).is_ok());
+    }
+
+    #[test]
+    fn repr_reordered() {
+        #[derive(Copy, Clone, Debug, PartialEq, Encode, Decode)]
+        #[bitcode(repr = "u8")]
+        enum V1 {
+            A = 3,
+            B = 7,
+            C = 9,
+        }
+
+        // Reordered with B deleted and D added, which doesn't change the encoding of A and C.
+        #[derive(Copy, Clone, Debug, PartialEq, Encode, Decode)]
+        #[bitcode(repr = "u8")]
+        enum V2 {
+            D = 12,
+            C = 9,
+            A = 3,
+        }
+
+        let encoded = encode(&vec![V1::A, V1::C, V1::A]);
+        assert_eq!(decode::<Vec<V2>>(&encoded).unwrap(), [V2::A, V2::C, V2::A]);
+        assert!(decode::<V2>(&encode(&V1::B)).is_err());
+        assert_eq!(decode::<V1>(&encode(&V2::C)).unwrap(), V1::C);
+    }
+
+    #[cfg(feature = "bitflags")]
+    #[test]
+    fn bitflags() {
+        bitflags::bitflags! {
+            #[derive(Copy, Clone, Debug, PartialEq, Encode, Decode, crate::Describe)]
+            #[bitcode(bitflags)]
+            struct Permissions: u8 {
+                const READ = 1;
+                const WRITE = 1 << 1;
+                const EXECUTE = 1 << 2;
+            }
+        }
+
+        let v = vec![
+            Permissions::READ,
+            Permissions::READ | Permissions::WRITE,
+            Permissions::all(),
+            Permissions::empty(),
+        ];
+        let encoded = encode(&v);
+        assert_eq!(encoded, encode(&vec![1u8, 3, 7, 0]));
+        assert_eq!(decode::<Vec<Permissions>>(&encoded).unwrap(), v);
+
+        // Unknown bits are rejected.
+        assert!(decode::<Permissions>(&encode(&8u8)).is_err());
+        assert!(decode::<Vec<Permissions>>(&encode(&vec![1u8, 0xFF])).is_err());
+
+        let schema = <Permissions as crate::Describe>::describe();
+        let bits = crate::Schema::Named("Permissions".into(), Box::new(crate::Schema::U8));
+        assert_eq!(schema, bits);
+    }
+}
@@ -1,9 +1,10 @@
-use crate::coder::{Buffer, Decoder, Encoder, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, View};
 use crate::derive::variant::{VariantDecoder, VariantEncoder};
 use crate::derive::{Decode, Encode};
 use crate::error::Error;
 use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
 
 #[derive(Debug)]
 pub struct ResultEncoder<T: Encode, E: Encode> {
@@ -41,13 +42,19 @@ impl<T: Encode, E: Encode> Encoder<Result<T, E>> for ResultEncoder<T, E> {
     // TODO implement encode_vectored if we can avoid lots of code duplication with OptionEncoder.
 }
 
-impl<T: Encode, E: Encode> Buffer for ResultEncoder<T, E> {
+impl<T: Encode, E: Encode> EncoderBuffer for ResultEncoder<T, E> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         self.variants.collect_into(out);
         self.ok.collect_into(out);
         self.err.collect_into(out);
     }
 
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.variants.collect_into_flushing(out, flush);
+        self.ok.collect_into_flushing(out, flush);
+        self.err.collect_into_flushing(out, flush);
+    }
+
     fn reserve(&mut self, additional: NonZeroUsize) {
         self.variants.reserve(additional);
         // We don't know how many are Ok or Err, so we can't reserve more.
@@ -91,8 +98,61 @@ impl<'a, T: Decode<'a>, E: Decode<'a>> Decoder<'a, Result<T, E>> for ResultDecod
     }
 }
 
+// ControlFlow<B, C> is encoded like Result<C, B> (Continue is Ok and Break is Err).
+impl<B: Encode, C: Encode> Encoder<ControlFlow<B, C>> for ResultEncoder<C, B> {
+    #[inline(always)]
+    fn encode(&mut self, t: &ControlFlow<B, C>) {
+        self.variants.encode(&(t.is_break() as u8));
+        match t {
+            ControlFlow::Continue(t) => {
+                self.ok.reserve(NonZeroUsize::new(1).unwrap());
+                self.ok.encode(t);
+            }
+            ControlFlow::Break(t) => {
+                self.err.reserve(NonZeroUsize::new(1).unwrap());
+                self.err.encode(t);
+            }
+        }
+    }
+}
+
+impl<'a, B: Decode<'a>, C: Decode<'a>> Decoder<'a, ControlFlow<B, C>> for ResultDecoder<'a, C, B> {
+    #[inline(always)]
+    fn decode_in_place(&mut self, out: &mut MaybeUninit<ControlFlow<B, C>>) {
+        if self.variants.decode() == 0 {
+            out.write(ControlFlow::Continue(self.ok.decode()));
+        } else {
+            out.write(ControlFlow::Break(self.err.decode()));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::{decode, encode};
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn control_flow() {
+        let results: Vec<Result<u32, String>> = crate::random_data::<(bool, u32)>(100)
+            .into_iter()
+            .map(|(ok, i)| if ok { Ok(i) } else { Err(i.to_string()) })
+            .collect();
+        let flows: Vec<ControlFlow<String, u32>> = results
+            .iter()
+            .map(|r| match r {
+                Ok(i) => ControlFlow::Continue(*i),
+                Err(s) => ControlFlow::Break(s.clone()),
+            })
+            .collect();
+        let encoded = encode(&flows);
+        assert_eq!(encoded, encode(&results));
+        assert_eq!(
+            decode::<Vec<ControlFlow<String, u32>>>(&encoded).unwrap(),
+            flows
+        );
+    }
+
     fn bench_data() -> Vec<Result<u32, u8>> {
         crate::random_data::<(bool, u32, u8)>(1000)
             .into_iter()
@@ -1,7 +1,11 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::vec::VecDecoder;
 use crate::derive::{Decode, Encode};
+use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct DerefEncoder<T: Encode + ?Sized>(T::Encoder);
 
@@ -19,10 +23,13 @@ impl<D: Deref<Target = T>, T: Encode + ?Sized> Encoder<D> for DerefEncoder<T> {
     }
 }
 
-impl<T: Encode + ?Sized> Buffer for DerefEncoder<T> {
+impl<T: Encode + ?Sized> EncoderBuffer for DerefEncoder<T> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         self.0.collect_into(out);
     }
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.0.collect_into_flushing(out, flush);
+    }
     fn reserve(&mut self, additional: NonZeroUsize) {
         self.0.reserve(additional);
     }
@@ -52,6 +59,49 @@ impl<'a, F: From<T>, T: Decode<'a>> Decoder<'a, F> for FromDecoder<'a, T> {
     }
 }
 
+/// Decodes `Rc<[T]>` and `Arc<[T]>` directly into their allocation instead of decoding a `Vec<T>`
+/// and copying it.
+pub struct SharedSliceDecoder<'a, T: Decode<'a>>(VecDecoder<'a, T>);
+
+// Can't derive since it would bound T: Default.
+impl<'a, T: Decode<'a>> Default for SharedSliceDecoder<'a, T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<'a, T: Decode<'a>> View<'a> for SharedSliceDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0.populate(input, length)
+    }
+}
+
+macro_rules! impl_shared_slice {
+    ($($ptr:ident),+) => {
+        $(
+            impl<'a, T: Decode<'a>> Decoder<'a, $ptr<[T]>> for SharedSliceDecoder<'a, T> {
+                #[inline(always)]
+                fn decode(&mut self) -> $ptr<[T]> {
+                    // TODO use new_uninit_slice once MSRV >= 1.82. Collecting a TrustedLen
+                    // iterator allocates once and the loop writing uninit values is a no-op.
+                    let length = self.0.lengths.decode();
+                    let mut uninit: $ptr<[MaybeUninit<T>]> =
+                        std::iter::repeat_with(MaybeUninit::uninit).take(length).collect();
+                    // Safety: `uninit` was just allocated so it isn't shared. Its length was just
+                    // decoded from `self.0.lengths` and all its elements are initialized by
+                    // `decode_elements`. `MaybeUninit<T>` has the same layout as `T`.
+                    unsafe {
+                        let out = $ptr::get_mut(&mut uninit).unwrap_unchecked();
+                        self.0.decode_elements(out);
+                        $ptr::from_raw($ptr::into_raw(uninit) as *const [T])
+                    }
+                }
+            }
+        )+
+    };
+}
+impl_shared_slice!(Rc, Arc);
+
 #[cfg(test)]
 mod tests {
     use crate::{decode, encode};
@@ -68,6 +118,36 @@ mod tests {
         assert_eq!(decode::<Box<[u8]>>(&encode(&v)).unwrap(), v);
     }
 
+    #[test]
+    fn shared_slice() {
+        use std::rc::Rc;
+        use std::sync::Arc;
+
+        let v: Vec<Vec<(u8, String)>> = (0..10)
+            .map(|i| (0..i).map(|j| (j, j.to_string())).collect())
+            .collect();
+        let encoded = encode(&v);
+        let rc: Vec<Rc<[(u8, String)]>> = decode(&encoded).unwrap();
+        assert!(rc.iter().map(|v| &**v).eq(v.iter().map(Vec::as_slice)));
+        let arc: Vec<Arc<[(u8, String)]>> = decode(&encoded).unwrap();
+        assert!(arc.iter().map(|v| &**v).eq(v.iter().map(Vec::as_slice)));
+
+        // Primitives are memcpy-ed.
+        let v: Vec<u32> = (0..1000).collect();
+        assert_eq!(*decode::<Arc<[u32]>>(&encode(&v)).unwrap(), *v);
+        assert_eq!(encode(&Rc::<[u32]>::from(v.as_slice())), encode(&v));
+    }
+
+    #[test]
+    fn shared_str() {
+        use std::rc::Rc;
+        use std::sync::Arc;
+
+        let v = "shared".to_string();
+        assert_eq!(&*decode::<Rc<str>>(&encode(&v)).unwrap(), v);
+        assert_eq!(&*decode::<Arc<str>>(&encode(&v)).unwrap(), v);
+    }
+
     #[test]
     fn box_str() {
         let v = "box".to_string().into_boxed_str();
@@ -0,0 +1,181 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::derive::{Decode, Encode};
+use crate::error::err;
+use crate::int::{IntDecoder, IntEncoder};
+use crate::pack_ints::Int;
+use std::num::NonZeroUsize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// Encodes `(S, u32)`s of seconds and nanoseconds, which is the same as encoding the tuple.
+#[derive(Default)]
+pub struct TimeEncoder<S> {
+    secs: IntEncoder<S>,
+    nanos: IntEncoder<u32>,
+}
+
+impl<S: Int> TimeEncoder<S> {
+    #[inline(always)]
+    fn encode_parts(&mut self, secs: S, nanos: u32) {
+        self.secs.encode(&secs);
+        self.nanos.encode(&nanos);
+    }
+}
+
+impl<S: Int> EncoderBuffer for TimeEncoder<S> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.secs.collect_into(out);
+        self.nanos.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.secs.collect_into_flushing(out, flush);
+        self.nanos.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.secs.reserve(additional);
+        self.nanos.reserve(additional);
+    }
+}
+
+impl Encoder<Duration> for TimeEncoder<u64> {
+    #[inline(always)]
+    fn encode(&mut self, t: &Duration) {
+        self.encode_parts(t.as_secs(), t.subsec_nanos());
+    }
+}
+
+/// `SystemTime`s are encoded as the seconds since [`UNIX_EPOCH`] (negative if before it) and the
+/// nanoseconds after that second.
+impl Encoder<SystemTime> for TimeEncoder<i64> {
+    #[inline(always)]
+    fn encode(&mut self, t: &SystemTime) {
+        let secs = |d: Duration| i64::try_from(d.as_secs()).expect("SystemTime out of range");
+        let (secs, nanos) = match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => (secs(d), d.subsec_nanos()),
+            Err(e) => {
+                let d = e.duration();
+                match d.subsec_nanos() {
+                    0 => (-secs(d), 0),
+                    nanos => (-secs(d) - 1, NANOS_PER_SEC - nanos),
+                }
+            }
+        };
+        self.encode_parts(secs, nanos);
+    }
+}
+
+#[derive(Default)]
+pub struct TimeDecoder<'a, S: Int> {
+    secs: IntDecoder<'a, S>,
+    nanos: IntDecoder<'a, u32>,
+}
+
+fn system_time(secs: i64, nanos: u32) -> Option<SystemTime> {
+    let after_secs = if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs()))
+    };
+    after_secs?.checked_add(Duration::from_nanos(nanos as u64))
+}
+
+impl<'a> View<'a> for TimeDecoder<'a, u64> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.secs.populate(input, length)?;
+        self.nanos.populate(input, length)?;
+
+        let mut nanos = self.nanos.borrowed_clone();
+        if (0..length).any(|_| Decoder::<u32>::decode(&mut nanos) >= NANOS_PER_SEC) {
+            return err("invalid Duration");
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, Duration> for TimeDecoder<'a, u64> {
+    #[inline(always)]
+    fn decode(&mut self) -> Duration {
+        // Can't overflow since populate checked nanos < NANOS_PER_SEC.
+        Duration::new(self.secs.decode(), self.nanos.decode())
+    }
+}
+
+impl<'a> View<'a> for TimeDecoder<'a, i64> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.secs.populate(input, length)?;
+        self.nanos.populate(input, length)?;
+
+        let (mut secs, mut nanos) = (self.secs.borrowed_clone(), self.nanos.borrowed_clone());
+        if (0..length).any(|_| {
+            let nanos = nanos.decode();
+            nanos >= NANOS_PER_SEC || system_time(secs.decode(), nanos).is_none()
+        }) {
+            return err("invalid SystemTime");
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, SystemTime> for TimeDecoder<'a, i64> {
+    #[inline(always)]
+    fn decode(&mut self) -> SystemTime {
+        let t = system_time(self.secs.decode(), self.nanos.decode());
+        // Safety: populate checked that every SystemTime is in range.
+        unsafe { t.unwrap_unchecked() }
+    }
+}
+
+impl Encode for Duration {
+    type Encoder = TimeEncoder<u64>;
+}
+impl<'a> Decode<'a> for Duration {
+    type Decoder = TimeDecoder<'a, u64>;
+}
+
+impl Encode for SystemTime {
+    type Encoder = TimeEncoder<i64>;
+}
+impl<'a> Decode<'a> for SystemTime {
+    type Decoder = TimeDecoder<'a, i64>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn duration() {
+        let v = [
+            Duration::ZERO,
+            Duration::from_millis(1500),
+            Duration::new(u64::MAX, 999_999_999),
+        ];
+        let encoded = encode(&v);
+        assert_eq!(encoded, encode(&v.map(|d| (d.as_secs(), d.subsec_nanos()))));
+        assert_eq!(decode::<[Duration; 3]>(&encoded).unwrap(), v);
+
+        assert!(decode::<Duration>(&encode(&(0u64, 1_000_000_000u32))).is_err());
+    }
+
+    #[test]
+    fn system_time() {
+        let v = [
+            UNIX_EPOCH,
+            SystemTime::now(),
+            UNIX_EPOCH + Duration::new(1, 5),
+            UNIX_EPOCH - Duration::new(1, 0),
+            UNIX_EPOCH - Duration::new(1, 5),
+        ];
+        assert_eq!(decode::<[SystemTime; 5]>(&encode(&v)).unwrap(), v);
+        assert_eq!(
+            encode(&v[3..]),
+            encode(&[(-1i64, 0u32), (-2, 999_999_995)][..])
+        );
+
+        assert!(decode::<SystemTime>(&encode(&(0i64, 1_000_000_000u32))).is_err());
+    }
+}
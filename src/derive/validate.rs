@@ -0,0 +1,109 @@
+use crate::coder::{Decoder, Result, View};
+use std::marker::PhantomData;
+
+/// Implemented by `#[derive(Decode)]` with `#[bitcode(validate = "path")]`.
+pub trait Validate {
+    /// Calls `path(self)` and converts its error into a decode error.
+    fn validate(&self) -> Result<()>;
+}
+
+/// Used by `#[derive(Decode)]` with `#[bitcode(validate = "path")]`. Since decoding can't fail
+/// after `populate`, `populate` decodes and validates all the values up front and `decode` returns
+/// them in order.
+pub struct ValidateDecoder<T, D> {
+    inner: D,
+    values: std::vec::IntoIter<T>,
+    _spooky: PhantomData<fn() -> T>,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T, D: Default> Default for ValidateDecoder<T, D> {
+    fn default() -> Self {
+        Self {
+            inner: Default::default(),
+            values: Vec::new().into_iter(),
+            _spooky: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: Validate, D: Decoder<'a, T>> View<'a> for ValidateDecoder<T, D> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.inner.populate(input, length)?;
+        let values: Vec<T> = (0..length).map(|_| self.inner.decode()).collect();
+        values.iter().try_for_each(T::validate)?;
+        self.values = values.into_iter();
+        Ok(())
+    }
+}
+
+impl<'a, T: Validate, D: Decoder<'a, T>> Decoder<'a, T> for ValidateDecoder<T, D> {
+    #[inline(always)]
+    fn decode(&mut self) -> T {
+        // Safety: populate decoded `length` values.
+        unsafe { self.values.next().unwrap_unchecked() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bitcode(validate = "Header::check")]
+    struct Header {
+        len: u32,
+        capacity: u32,
+    }
+
+    impl Header {
+        fn check(&self) -> Result<(), String> {
+            if self.len > self.capacity {
+                return Err(format!("len {} > capacity {}", self.len, self.capacity));
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bitcode(validate = "check_sorted")]
+    enum Keys<T: Ord> {
+        Sorted(Vec<T>),
+        Empty,
+    }
+
+    fn check_sorted<T: Ord>(keys: &Keys<T>) -> Result<(), &'static str> {
+        match keys {
+            Keys::Sorted(v) if !v.windows(2).all(|w| w[0] <= w[1]) => Err("not sorted"),
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let headers = vec![
+            Header {
+                len: 1,
+                capacity: 2,
+            },
+            Header {
+                len: 2,
+                capacity: 2,
+            },
+        ];
+        assert_eq!(decode::<Vec<Header>>(&encode(&headers)).unwrap(), headers);
+        let invalid = encode(&vec![(1u32, 2u32), (3, 2)]);
+        assert!(decode::<Vec<Header>>(&invalid).is_err());
+
+        let keys = vec![Keys::Sorted(vec![1u8, 2, 3]), Keys::Empty];
+        let encoded = encode(&keys);
+        assert_eq!(decode::<Vec<Keys<u8>>>(&encoded).unwrap(), keys);
+        assert!(decode::<Keys<u8>>(&encode(&Keys::Sorted(vec![2u8, 1]))).is_err());
+
+        // Values that were validated are decoded in order with a reused decoder.
+        let mut buffer = crate::Buffer::new();
+        for _ in 0..2 {
+            assert_eq!(buffer.decode::<Vec<Keys<u8>>>(&encoded).unwrap(), keys);
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::error::{err_kind, ErrorKind};
 use crate::fast::{CowSlice, NextUnchecked, PushUnchecked, VecImpl};
+use crate::int::{IntDecoder, IntEncoder};
 use crate::pack::{pack_bytes_less_than, unpack_bytes_less_than};
 use std::num::NonZeroUsize;
 
@@ -13,7 +15,7 @@ impl<const N: usize> Encoder<u8> for VariantEncoder<N> {
     }
 }
 
-impl<const N: usize> Buffer for VariantEncoder<N> {
+impl<const N: usize> EncoderBuffer for VariantEncoder<N> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         assert!(N >= 2);
         pack_bytes_less_than::<N>(self.0.as_slice(), out);
@@ -46,6 +48,11 @@ impl<'a, const N: usize> VariantDecoder<'a, N, false> {
     pub fn length(&self, variant_index: u8) -> usize {
         self.histogram[variant_index as usize]
     }
+
+    /// Returns the length of every variant (for [`crate::visit`]).
+    pub(crate) fn lengths(&self) -> &[usize] {
+        &self.histogram
+    }
 }
 
 impl<'a, const N: usize, const C_STYLE: bool> View<'a> for VariantDecoder<'a, N, C_STYLE> {
@@ -68,6 +75,80 @@ impl<'a, const N: usize, const C_STYLE: bool> Decoder<'a, u8> for VariantDecoder
     }
 }
 
+/// Like [`VariantEncoder`], but for enums with more than 256 variants (encoded as packed `u16`s).
+#[cfg_attr(not(feature = "derive"), allow(unused))] // Only used by the derive macro.
+#[derive(Debug, Default)]
+pub struct WideVariantEncoder<const N: usize>(IntEncoder<u16>);
+
+impl<const N: usize> Encoder<u16> for WideVariantEncoder<N> {
+    #[inline(always)]
+    fn encode(&mut self, v: &u16) {
+        self.0.encode(v);
+    }
+}
+
+impl<const N: usize> EncoderBuffer for WideVariantEncoder<N> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+/// Like [`VariantDecoder`], but for enums with more than 256 variants (encoded as packed `u16`s).
+#[cfg_attr(not(feature = "derive"), allow(unused))] // Only used by the derive macro.
+#[derive(Debug, Default)]
+pub struct WideVariantDecoder<'a, const N: usize, const C_STYLE: bool> {
+    variants: IntDecoder<'a, u16>,
+    histogram: Vec<usize>, // Not required if C_STYLE.
+}
+
+#[cfg_attr(not(feature = "derive"), allow(unused))]
+impl<'a, const N: usize> WideVariantDecoder<'a, N, false> {
+    pub fn length(&self, variant_index: u16) -> usize {
+        self.histogram[variant_index as usize]
+    }
+}
+
+impl<'a, const N: usize, const C_STYLE: bool> View<'a> for WideVariantDecoder<'a, N, C_STYLE> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.variants.populate(input, length)?;
+
+        let mut decoder = self.variants.borrowed_clone();
+        let mut variants = (0..length).map(|_| {
+            let v: u16 = decoder.decode();
+            v as usize
+        });
+        if C_STYLE {
+            if variants.any(|v| v >= N) {
+                return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
+            }
+        } else {
+            self.histogram.clear();
+            self.histogram.resize(N, 0);
+            for v in variants {
+                let Some(count) = self.histogram.get_mut(v) else {
+                    return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
+                };
+                *count += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, const N: usize, const C_STYLE: bool> Decoder<'a, u16>
+    for WideVariantDecoder<'a, N, C_STYLE>
+{
+    // Guaranteed to output numbers less than N.
+    #[inline(always)]
+    fn decode(&mut self) -> u16 {
+        self.variants.decode()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{decode, encode};
@@ -124,6 +205,44 @@ mod tests {
         assert!(matches!(decode(&encode(&Enum1::F)), Ok(Enum1::F)));
     }
 
+    // Generates an enum with a variant for every pair of `$a` and `$b` (and the `$extra` variants).
+    macro_rules! wide_enum {
+        ($name:ident [$($v:ident)*] [] $bs:tt $($extra:tt)*) => {
+            #[derive(Debug, PartialEq, crate::Encode, crate::Decode)]
+            enum $name {
+                $($v,)*
+                $($extra)*
+            }
+        };
+        ($name:ident [$($v:ident)*] [$a:ident $($as:ident)*] [$($b:ident)*] $($extra:tt)*) => {
+            paste::paste! {
+                wide_enum!($name [$($v)* $([<$a $b>])*] [$($as)*] [$($b)*] $($extra)*);
+            }
+        };
+    }
+    wide_enum!(
+        WideC [] [A B C D E F G H I J K L M N O P Q R S T] [A B C D E F G H I J K L M N O P]
+    );
+    wide_enum!(
+        Wide [] [A B C D E F G H I J K L M N O P Q R S T] [A B C D E F G H I J K L M N O P]
+        Payload(u32),
+    );
+
+    #[test]
+    fn test_wide_enum() {
+        // 320 variants are encoded as u16s.
+        assert_eq!(encode(&WideC::TP), encode(&319u16));
+        assert_eq!(decode::<WideC>(&encode(&319u16)), Ok(WideC::TP));
+        assert!(decode::<WideC>(&encode(&320u16)).is_err());
+
+        let v = vec![WideC::AA, WideC::TP, WideC::JF, WideC::AA];
+        assert_eq!(decode::<Vec<WideC>>(&encode(&v)).unwrap(), v);
+
+        let v = vec![Wide::AB, Wide::Payload(5), Wide::TP, Wide::Payload(6)];
+        assert_eq!(decode::<Vec<Wide>>(&encode(&v)).unwrap(), v);
+        assert!(decode::<Wide>(&encode(&321u16)).is_err());
+    }
+
     #[derive(Debug, PartialEq, crate::Encode, crate::Decode)]
     enum BoolEnum {
         True,
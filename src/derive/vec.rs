@@ -1,18 +1,66 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View, MAX_VECTORED_CHUNK};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View, MAX_VECTORED_CHUNK};
 use crate::derive::{Decode, Encode};
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::{record, Path};
 use crate::length::{LengthDecoder, LengthEncoder};
+use crate::options::{decode_options, encode_options, reserve_allocation};
 use std::collections::{BTreeSet, BinaryHeap, HashSet, LinkedList, VecDeque};
 use std::hash::{BuildHasher, Hash};
 use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
-use std::ptr::NonNull;
 
 #[derive(Debug)]
 pub struct VecEncoder<T: Encode> {
     // pub(crate) for arrayvec.rs
     pub(crate) lengths: LengthEncoder,
     pub(crate) elements: T::Encoder,
-    vectored_impl: Option<NonNull<()>>,
+    vectored_impl: VectoredImpl,
+    /// [`EncodeOptions::vectored_chunk_len`][`crate::EncodeOptions::vectored_chunk_len`], read in
+    /// reserve (which is always called before encode) instead of in every encode.
+    chunk_len: usize,
+}
+
+/// Which function [`VecEncoder::encode_vectored`] calls when elements are primitives. It's picked
+/// on the first call and escalates to longer `MaxLen`s (and eventually `Fallback`) when it
+/// encounters a slice that is too long.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+enum VectoredImpl {
+    #[default]
+    Unset,
+    MaxLen1,
+    MaxLen2,
+    MaxLen4,
+    MaxLen8,
+    MaxLen16,
+    MaxLen32,
+    MaxLen64,
+    Fallback,
+}
+
+impl VectoredImpl {
+    /// Starts at the pointer size (assumed to be 8 bytes) to not be wasteful.
+    fn initial(size: usize) -> Self {
+        match (8 / size.max(1)).max(1) {
+            1 => Self::MaxLen1,
+            2 => Self::MaxLen2,
+            4 => Self::MaxLen4,
+            8 => Self::MaxLen8,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Doubles the max length unless it would copy more than 64 bytes.
+    fn escalate(self, size: usize) -> Self {
+        match self {
+            Self::MaxLen1 if size <= 32 => Self::MaxLen2,
+            Self::MaxLen2 if size <= 16 => Self::MaxLen4,
+            Self::MaxLen4 if size <= 8 => Self::MaxLen8,
+            Self::MaxLen8 if size <= 4 => Self::MaxLen16,
+            Self::MaxLen16 if size <= 2 => Self::MaxLen32,
+            Self::MaxLen32 if size <= 1 => Self::MaxLen64,
+            _ => Self::Fallback,
+        }
+    }
 }
 
 // Can't derive since it would bound T: Default.
@@ -22,19 +70,26 @@ impl<T: Encode> Default for VecEncoder<T> {
             lengths: Default::default(),
             elements: Default::default(),
             vectored_impl: Default::default(),
+            chunk_len: MAX_VECTORED_CHUNK,
         }
     }
 }
 
-impl<T: Encode> Buffer for VecEncoder<T> {
+impl<T: Encode> EncoderBuffer for VecEncoder<T> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         self.lengths.collect_into(out);
         self.elements.collect_into(out);
     }
 
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.lengths.collect_into_flushing(out, flush);
+        self.elements.collect_into_flushing(out, flush);
+    }
+
     fn reserve(&mut self, additional: NonZeroUsize) {
         self.lengths.reserve(additional);
         // We don't know the lengths of the vectors, so we can't reserve more.
+        self.chunk_len = encode_options().vectored_chunk_len;
     }
 }
 
@@ -113,39 +168,56 @@ impl<T: Encode> VecEncoder<T> {
                     dst = dst.add(n);
                 },
             ) {
-                // Use fallback for impls that copy more than 64 bytes.
-                let size = std::mem::size_of::<T>();
-                self.vectored_impl = NonNull::new(match N {
-                    1 if size <= 32 => Self::encode_vectored_max_len::<I, 2>,
-                    2 if size <= 16 => Self::encode_vectored_max_len::<I, 4>,
-                    4 if size <= 8 => Self::encode_vectored_max_len::<I, 8>,
-                    8 if size <= 4 => Self::encode_vectored_max_len::<I, 16>,
-                    16 if size <= 2 => Self::encode_vectored_max_len::<I, 32>,
-                    32 if size <= 1 => Self::encode_vectored_max_len::<I, 64>,
-                    _ => Self::encode_vectored_fallback::<I>,
-                } as *mut ());
-                let f: fn(&mut Self, i: I) = std::mem::transmute(self.vectored_impl);
-                f(self, i);
+                self.vectored_impl = self.vectored_impl.escalate(std::mem::size_of::<T>());
+                self.encode_vectored_dispatch(i);
                 return;
             }
             primitives.set_end_ptr(dst);
         }
+        #[cfg(feature = "diagnostics")]
+        record::<T>(false, Path::WildCopy);
+    }
+
+    /// Calls the function picked by [`Self::vectored_impl`].
+    #[inline(always)]
+    fn encode_vectored_dispatch<'a, I: Iterator<Item = &'a [T]> + Clone>(&mut self, i: I)
+    where
+        T: 'a,
+    {
+        if self.vectored_impl == VectoredImpl::Unset {
+            self.vectored_impl = VectoredImpl::initial(std::mem::size_of::<T>());
+        }
+        match self.vectored_impl {
+            VectoredImpl::Unset => unreachable!(),
+            VectoredImpl::MaxLen1 => self.encode_vectored_max_len::<I, 1>(i),
+            VectoredImpl::MaxLen2 => self.encode_vectored_max_len::<I, 2>(i),
+            VectoredImpl::MaxLen4 => self.encode_vectored_max_len::<I, 4>(i),
+            VectoredImpl::MaxLen8 => self.encode_vectored_max_len::<I, 8>(i),
+            VectoredImpl::MaxLen16 => self.encode_vectored_max_len::<I, 16>(i),
+            VectoredImpl::MaxLen32 => self.encode_vectored_max_len::<I, 32>(i),
+            VectoredImpl::MaxLen64 => self.encode_vectored_max_len::<I, 64>(i),
+            VectoredImpl::Fallback => self.encode_vectored_fallback(i),
+        }
     }
 
     /// Fallback for when length > [`Self::encode_vectored_max_len`]'s max_len.
     #[inline(never)]
-    fn encode_vectored_fallback<'a, I: Iterator<Item = &'a [T]>>(&mut self, i: I)
+    fn encode_vectored_fallback<'a, I: Iterator<Item = &'a [T]> + Clone>(&mut self, i: I)
     where
         T: 'a,
     {
+        #[cfg(feature = "diagnostics")]
+        record::<T>(false, Path::Fallback);
         let primitives = self.elements.as_primitive().unwrap();
+        // Reserve the whole chunk at once instead of reserving each slice (e.g. each string).
+        primitives.reserve(i.clone().map(<[T]>::len).sum());
+        let mut dst = primitives.end_ptr();
         self.lengths.encode_vectored_fallback(i, |s| unsafe {
             let n = s.len();
-            primitives.reserve(n);
-            let ptr = primitives.end_ptr();
-            copy_nonoverlapping_unaligned(s.as_ptr(), ptr, n);
-            primitives.set_end_ptr(ptr.add(n));
+            copy_nonoverlapping_unaligned(s.as_ptr(), dst, n);
+            dst = dst.add(n);
         });
+        primitives.set_end_ptr(dst);
     }
 }
 
@@ -156,6 +228,8 @@ impl<T: Encode> Encoder<[T]> for VecEncoder<T> {
         self.lengths.encode(&n);
 
         if let Some(primitive) = self.elements.as_primitive() {
+            #[cfg(feature = "diagnostics")]
+            record::<T>(false, Path::Memcpy);
             primitive.reserve(n);
             unsafe {
                 let ptr = primitive.end_ptr();
@@ -163,9 +237,12 @@ impl<T: Encode> Encoder<[T]> for VecEncoder<T> {
                 primitive.set_end_ptr(ptr.add(n));
             }
         } else if let Some(n) = NonZeroUsize::new(n) {
+            #[cfg(feature = "diagnostics")]
+            record::<T>(false, Path::PerElement);
             self.elements.reserve(n);
-            // Uses chunks to keep everything in the CPU cache. TODO pick optimal chunk size.
-            for chunk in v.chunks(MAX_VECTORED_CHUNK) {
+            // Uses chunks to keep everything in the CPU cache.
+            debug_assert!((1..=MAX_VECTORED_CHUNK).contains(&self.chunk_len));
+            for chunk in v.chunks(self.chunk_len) {
                 self.elements.encode_vectored(chunk.iter());
             }
         }
@@ -177,29 +254,7 @@ impl<T: Encode> Encoder<[T]> for VecEncoder<T> {
         [T]: 'a,
     {
         if self.elements.as_primitive().is_some() {
-            /// Convert impl trait to named generic type.
-            #[inline(always)]
-            fn inner<'a, T: Encode + 'a, I: Iterator<Item = &'a [T]> + Clone>(
-                me: &mut VecEncoder<T>,
-                i: I,
-            ) {
-                // We can't set this in the Default constructor because we don't have the type I.
-                if me.vectored_impl.is_none() {
-                    // Use match to avoid "use of generic parameter from outer function".
-                    // Start at the pointer size (assumed to be 8 bytes) to not be wasteful.
-                    me.vectored_impl = NonNull::new(match (8 / std::mem::size_of::<T>()).max(1) {
-                        1 => VecEncoder::encode_vectored_max_len::<I, 1>,
-                        2 => VecEncoder::encode_vectored_max_len::<I, 2>,
-                        4 => VecEncoder::encode_vectored_max_len::<I, 4>,
-                        8 => VecEncoder::encode_vectored_max_len::<I, 8>,
-                        _ => unreachable!(),
-                    } as *mut ());
-                }
-                let f: fn(&mut VecEncoder<T>, i: I) =
-                    unsafe { std::mem::transmute(me.vectored_impl) };
-                f(me, i);
-            }
-            inner(self, i);
+            self.encode_vectored_dispatch(i);
         } else {
             for v in i {
                 self.encode(v);
@@ -228,6 +283,10 @@ impl<'a, T: Decode<'a>> Default for VecDecoder<'a, T> {
 impl<'a, T: Decode<'a>> View<'a> for VecDecoder<'a, T> {
     fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
         self.lengths.populate(input, length)?;
+        // Safety: `length` was just passed to populate.
+        let max = decode_options().max_vec_len;
+        unsafe { self.lengths.check_max(length, max, "vec too long")? };
+        reserve_allocation::<T>(self.lengths.length())?;
         self.elements.populate(input, self.lengths.length())
     }
 }
@@ -300,19 +359,38 @@ impl<'a, T: Decode<'a>> Decoder<'a, Vec<T>> for VecDecoder<'a, T> {
         }
 
         let v = out.write(Vec::with_capacity(length));
+        // Safety: `length` was just decoded from `self.lengths`.
+        unsafe { self.decode_elements(&mut v.spare_capacity_mut()[..length]) };
+        unsafe { v.set_len(length) };
+    }
+}
+
+impl<'a, T: Decode<'a>> VecDecoder<'a, T> {
+    /// Decodes the elements of a sequence into `out`. Used by `Vec<T>` and `Rc<[T]>`/`Arc<[T]>`
+    /// (which are decoded directly into their allocation).
+    /// Safety: `out.len()` must be the length that was just decoded from `self.lengths`.
+    #[inline(always)]
+    pub(crate) unsafe fn decode_elements(&mut self, out: &mut [MaybeUninit<T>]) {
+        let length = out.len();
         if let Some(primitive) = self.elements.as_primitive_ptr() {
+            #[cfg(feature = "diagnostics")]
+            record::<T>(true, Path::Memcpy);
             unsafe {
-                copy_nonoverlapping_unaligned(primitive as *const T, v.as_mut_ptr(), length);
+                copy_nonoverlapping_unaligned(
+                    primitive as *const T,
+                    out.as_mut_ptr() as *mut T,
+                    length,
+                );
                 self.elements.as_primitive_advance(length);
             }
         } else {
-            let spare = v.spare_capacity_mut();
+            #[cfg(feature = "diagnostics")]
+            record::<T>(true, Path::PerElement);
             for i in 0..length {
-                let out = unsafe { spare.get_unchecked_mut(i) };
+                let out = unsafe { out.get_unchecked_mut(i) };
                 self.elements.decode_in_place(out);
             }
         }
-        unsafe { v.set_len(length) };
     }
 }
 
@@ -365,7 +443,172 @@ impl<'a, T: Decode<'a>> Decoder<'a, VecDeque<T>> for VecDecoder<'a, T> {
 
 #[cfg(test)]
 mod test {
+    use super::{VecDecoder, VecEncoder, VectoredImpl};
+    use crate::coder::{Decoder, Encoder, EncoderBuffer, View};
+    use crate::{DecodeOwned, Encode};
     use std::collections::*;
+    use std::fmt::Debug;
+    use std::num::NonZeroUsize;
+
+    fn vectored_escalation<T: Encode + DecodeOwned + From<u8> + Debug + PartialEq>(
+        expected: &[VectoredImpl],
+    ) {
+        let mut encoder = VecEncoder::<T>::default();
+        let mut vecs: Vec<Vec<T>> = vec![];
+        let mut seen = vec![];
+        for len in [0, 1, 2, 3, 5, 9, 17, 33, 65, 200, 1] {
+            let batch: Vec<Vec<T>> = (0..3)
+                .map(|i| (0..len).map(|j| T::from((i + j) as u8)).collect())
+                .collect();
+            encoder.reserve(NonZeroUsize::new(batch.len()).unwrap());
+            encoder.encode_vectored(batch.iter().map(Vec::as_slice));
+            if seen.last() != Some(&encoder.vectored_impl) {
+                seen.push(encoder.vectored_impl);
+            }
+            vecs.extend(batch);
+        }
+        assert_eq!(seen, expected);
+
+        let bytes = encoder.collect();
+        let mut decoder = VecDecoder::<T>::default();
+        let mut input = bytes.as_slice();
+        decoder.populate(&mut input, vecs.len()).unwrap();
+        assert!(input.is_empty());
+        for v in vecs {
+            let decoded: Vec<T> = decoder.decode();
+            assert_eq!(decoded, v);
+        }
+    }
+
+    #[test]
+    fn vectored_escalation_u8() {
+        use VectoredImpl::*;
+        vectored_escalation::<u8>(&[MaxLen8, MaxLen16, MaxLen32, MaxLen64, Fallback]);
+    }
+
+    #[test]
+    fn vectored_escalation_u32() {
+        use VectoredImpl::*;
+        vectored_escalation::<u32>(&[MaxLen2, MaxLen4, MaxLen8, MaxLen16, Fallback]);
+    }
+
+    #[test]
+    fn vectored_escalation_u64() {
+        use VectoredImpl::*;
+        vectored_escalation::<u64>(&[MaxLen1, MaxLen2, MaxLen4, MaxLen8, Fallback]);
+    }
+
+    #[test]
+    fn vectored_chunk_len() {
+        let v: Vec<(u8, String, Option<u16>)> = (0..200u8)
+            .map(|i| (i, i.to_string(), (i % 2 == 0).then_some(i as u16 * 300)))
+            .collect();
+        let expected = crate::encode(&v);
+        for n in [0, 1, 2, 3, 63, 64, 65, usize::MAX] {
+            let options = crate::EncodeOptions::new().vectored_chunk_len(n);
+            assert_eq!(crate::encode_with_options(&v, &options), expected);
+        }
+    }
+
+    #[test]
+    fn allocation_limit() {
+        use crate::{decode_with_options, encode, Buffer, DecodeOptions};
+        let limit = |n| DecodeOptions::new().allocation_limit(n);
+        let size = std::mem::size_of::<Vec<()>>();
+
+        // Empty Vec<()>s take up a few bits each but size bytes once decoded.
+        let empty = vec![vec![(); 0]; 1000];
+        let encoded = encode(&empty);
+        assert!(encoded.len() < 200);
+        type T = Vec<Vec<()>>;
+        assert!(decode_with_options::<T>(&encoded, &limit(1000 * size - 1)).is_err());
+        assert_eq!(
+            decode_with_options::<T>(&encoded, &limit(1000 * size)).unwrap(),
+            empty
+        );
+
+        // The limit is shared by nested collections and reset by each call.
+        let nested = vec![vec![0u32; 100]; 10];
+        let encoded = encode(&nested);
+        let needed = 10 * size + 1000 * 4;
+        let mut buffer = Buffer::new();
+        for _ in 0..2 {
+            let options = limit(needed);
+            let decoded: Vec<Vec<u32>> = buffer.decode_with_options(&encoded, &options).unwrap();
+            assert_eq!(decoded, nested);
+            let options = limit(needed - 1);
+            assert!(buffer
+                .decode_with_options::<Vec<Vec<u32>>>(&encoded, &options)
+                .is_err());
+        }
+        let map: HashMap<u16, u64> = (0..100).map(|i| (i, i as u64)).collect();
+        let needed = 100 * std::mem::size_of::<(u16, u64)>();
+        let encoded = encode(&map);
+        assert!(decode_with_options::<HashMap<u16, u64>>(&encoded, &limit(needed)).is_ok());
+        assert!(decode_with_options::<HashMap<u16, u64>>(&encoded, &limit(needed - 1)).is_err());
+
+        // Decoding without a limit after one was exceeded.
+        assert_eq!(
+            crate::decode::<Vec<Vec<u32>>>(&encode(&nested)).unwrap(),
+            nested
+        );
+    }
+
+    #[test]
+    fn max_len() {
+        use crate::{decode_with_options, encode, DecodeOptions};
+        use std::borrow::Cow;
+        fn check<T: Encode + DecodeOwned + Debug + PartialEq>(
+            t: T,
+            options: DecodeOptions,
+            ok: bool,
+        ) {
+            let decoded = decode_with_options::<T>(&encode(&t), &options);
+            if ok {
+                assert_eq!(decoded.unwrap(), t);
+            } else {
+                assert!(decoded.is_err());
+            }
+        }
+        let vec = DecodeOptions::new().max_vec_len(300);
+        let map = DecodeOptions::new().max_map_len(300);
+        let str = DecodeOptions::new().max_str_len(300);
+
+        // Lengths that add up to more than the max but are each within it.
+        let lengths = [200, 0, 300, 255];
+        let vecs = lengths.map(|n| vec![0u16; n]);
+        check(vecs.clone(), vec, true);
+        check(vecs.map(VecDeque::from), vec, true);
+        let cows = lengths.map(|n| Cow::<[u8]>::Owned(vec![1u8; n]));
+        assert!(decode_with_options::<[Cow<[u8]>; 4]>(&encode(&cows), &vec).is_ok());
+        let maps = lengths.map(|n| (0..n as u16).map(|i| (i, ())).collect::<BTreeMap<_, _>>());
+        check(maps.clone(), map, true);
+        check(maps.clone(), vec, true);
+        check(lengths.map(|n| "a".repeat(n)), str, true);
+
+        let lengths = [200, 0, 301, 255];
+        let vecs = lengths.map(|n| vec![0u16; n]);
+        check(vecs.clone(), vec, false);
+        check(vecs.clone(), map, true);
+        check(vecs.map(VecDeque::from), vec, false);
+        let cows = lengths.map(|n| Cow::<[u8]>::Owned(vec![1u8; n]));
+        assert!(decode_with_options::<[Cow<[u8]>; 4]>(&encode(&cows), &vec).is_err());
+        let maps = lengths.map(|n| (0..n as u16).map(|i| (i, ())).collect::<HashMap<_, _>>());
+        check(maps.clone(), map, false);
+        check(maps, vec, true);
+        check(lengths.map(|n| "a".repeat(n)), str, false);
+        check(lengths.map(|n| "a".repeat(n)), vec, true);
+    }
+
+    #[test]
+    fn vectored_escalation_skip() {
+        // Escalates multiple times in one call.
+        let mut encoder = VecEncoder::<u8>::default();
+        let v = vec![0u8; 100];
+        encoder.reserve(NonZeroUsize::MIN);
+        encoder.encode_vectored([v.as_slice()].into_iter());
+        assert_eq!(encoder.vectored_impl, VectoredImpl::Fallback);
+    }
     fn bench_data<T: FromIterator<u8>>() -> T {
         (0..=255).collect()
     }
@@ -0,0 +1,301 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::consume_byte;
+use crate::derive::Decode;
+use std::mem::MaybeUninit;
+use std::num::NonZeroUsize;
+
+/// Used by `#[derive(Encode)]` with `#[bitcode(version = V)]`. Writes `V` as a single byte before
+/// the columns of `E`, so it's written once per type (not once per value).
+#[derive(Debug, Default)]
+pub struct VersionEncoder<E, const V: u8>(E);
+
+impl<T: ?Sized, E: Encoder<T>, const V: u8> Encoder<T> for VersionEncoder<E, V> {
+    #[inline(always)]
+    fn encode(&mut self, t: &T) {
+        self.0.encode(t);
+    }
+
+    #[inline(always)]
+    fn encode_vectored<'a>(&mut self, i: impl Iterator<Item = &'a T> + Clone)
+    where
+        T: 'a,
+    {
+        self.0.encode_vectored(i);
+    }
+}
+
+impl<E: EncoderBuffer, const V: u8> EncoderBuffer for VersionEncoder<E, V> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        out.push(V);
+        self.0.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        out.push(V);
+        self.0.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+/// Used by `#[derive(Decode)]` with `#[bitcode(version = V)]` (without `previous`). Errors if the
+/// version byte isn't `V`.
+#[derive(Debug, Default)]
+pub struct VersionDecoder<D, const V: u8>(D);
+
+impl<'a, D: View<'a>, const V: u8> View<'a> for VersionDecoder<D, V> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        if consume_byte(input)? != V {
+            return crate::error::err_kind(crate::ErrorKind::Unsupported, "unsupported version");
+        }
+        self.0.populate(input, length)
+    }
+}
+
+impl<'a, T, D: Decoder<'a, T>, const V: u8> Decoder<'a, T> for VersionDecoder<D, V> {
+    #[inline(always)]
+    fn decode_in_place(&mut self, out: &mut MaybeUninit<T>) {
+        self.0.decode_in_place(out);
+    }
+}
+
+/// Implemented by `#[derive(Decode)]` with `#[bitcode(version = V)]`.
+pub trait Versioned {
+    const VERSION: u8;
+}
+
+/// Used by `#[derive(Decode)]` with `#[bitcode(version = V, previous = "P")]`. Decodes with `D` if
+/// the version byte is `V`, otherwise leaves the version byte to `P`'s decoder and converts each
+/// `P` with `From`.
+///
+/// `P` must be [`Versioned`] with a version other than `V`. Otherwise the first byte of `P`'s
+/// columns could happen to be `V` and `P` would be decoded as the current version.
+pub struct UpgradeDecoder<'a, D, P: Decode<'a>, const V: u8> {
+    current: D,
+    previous: P::Decoder,
+    upgrade: bool,
+}
+
+// Can't derive since it would bound P: Default.
+impl<'a, D: Default, P: Decode<'a>, const V: u8> Default for UpgradeDecoder<'a, D, P, V> {
+    fn default() -> Self {
+        Self {
+            current: Default::default(),
+            previous: Default::default(),
+            upgrade: false,
+        }
+    }
+}
+
+impl<'a, D, P: Decode<'a> + Versioned, const V: u8> UpgradeDecoder<'a, D, P, V> {
+    const DISTINCT_VERSIONS: () = assert!(P::VERSION != V, "previous has the same version");
+}
+
+impl<'a, D: View<'a>, P: Decode<'a> + Versioned, const V: u8> View<'a>
+    for UpgradeDecoder<'a, D, P, V>
+{
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        let () = Self::DISTINCT_VERSIONS;
+        self.upgrade = input.first() != Some(&V);
+        if self.upgrade {
+            self.previous.populate(input, length)
+        } else {
+            *input = &input[1..];
+            self.current.populate(input, length)
+        }
+    }
+}
+
+impl<'a, T: From<P>, D: Decoder<'a, T>, P: Decode<'a> + Versioned, const V: u8> Decoder<'a, T>
+    for UpgradeDecoder<'a, D, P, V>
+{
+    #[inline(always)]
+    fn decode_in_place(&mut self, out: &mut MaybeUninit<T>) {
+        if self.upgrade {
+            out.write(T::from(self.previous.decode()));
+        } else {
+            self.current.decode_in_place(out);
+        }
+    }
+}
+
+/// Used by `#[derive(Decode)]` for fields with `#[bitcode(default)]`. If the input ends before
+/// the field, it was encoded by an older version of the struct that didn't have the field yet.
+///
+/// The format has no field count, so this relies on the struct's columns being the last ones in
+/// the input:
+/// - Input truncated exactly at the end of a column before the field is indistinguishable from
+///   input encoded by the older version, so it decodes with defaults instead of erroring with
+///   [`crate::ErrorKind::Eof`].
+/// - If anything is encoded after the struct, its columns aren't empty, so the field is populated
+///   from them and they're misread (or error) instead of the field being missing.
+#[derive(Debug, Default)]
+pub struct DefaultDecoder<D> {
+    inner: D,
+    missing: bool,
+}
+
+impl<D> DefaultDecoder<D> {
+    /// Returns the inner decoder or `None` if the field is missing.
+    #[inline(always)]
+    pub fn present(&mut self) -> Option<&mut D> {
+        (!self.missing).then_some(&mut self.inner)
+    }
+}
+
+impl<'a, D: View<'a>> View<'a> for DefaultDecoder<D> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.missing = input.is_empty();
+        if self.missing {
+            return Ok(());
+        }
+        self.inner.populate(input, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, Decode, Encode};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bitcode(version = 1)]
+    struct SaveV1 {
+        hp: u32,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bitcode(version = 2, previous = "SaveV1")]
+    struct SaveV2 {
+        hp: u32,
+        name: String,
+    }
+
+    impl From<SaveV1> for SaveV2 {
+        fn from(v: SaveV1) -> Self {
+            Self {
+                hp: v.hp,
+                name: "unnamed".into(),
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[bitcode(version = 3, previous = "SaveV2")]
+    enum Save {
+        Alive { hp: u32, name: String },
+        Dead,
+    }
+
+    impl From<SaveV2> for Save {
+        fn from(v: SaveV2) -> Self {
+            if v.hp == 0 {
+                Self::Dead
+            } else {
+                Self::Alive {
+                    hp: v.hp,
+                    name: v.name,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn version() {
+        // The version is written once per type, not once per value.
+        let v1 = vec![SaveV1 { hp: 0 }, SaveV1 { hp: 5 }];
+        let encoded = encode(&v1);
+        assert_eq!(
+            encoded,
+            [&[2u8, 1][..], &encode(&vec![0u32, 5])[1..]].concat()
+        );
+        assert_eq!(decode::<Vec<SaveV1>>(&encoded).unwrap(), v1);
+
+        // Older versions are upgraded through the chain of previous types.
+        let v2: Vec<SaveV2> = decode(&encoded).unwrap();
+        assert_eq!(v2[1].name, "unnamed");
+        let saves: Vec<Save> = decode(&encoded).unwrap();
+        assert_eq!(
+            saves,
+            [
+                Save::Dead,
+                Save::Alive {
+                    hp: 5,
+                    name: "unnamed".into()
+                }
+            ]
+        );
+        assert_eq!(decode::<Vec<Save>>(&encode(&v2)).unwrap(), saves);
+        assert_eq!(decode::<Vec<Save>>(&encode(&saves)).unwrap(), saves);
+
+        // Newer versions and types without a previous version can't be decoded.
+        assert!(decode::<Vec<SaveV1>>(&encode(&v2)).is_err());
+        assert!(decode::<Vec<SaveV2>>(&encode(&saves)).is_err());
+        assert!(decode::<SaveV1>(&[]).is_err());
+        assert!(decode::<Save>(&[]).is_err());
+    }
+
+    #[test]
+    fn default() {
+        #[derive(Clone, Encode)]
+        struct Old {
+            a: u32,
+            b: String,
+        }
+
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct New {
+            a: u32,
+            b: String,
+            #[bitcode(default)]
+            c: Vec<u8>,
+            #[bitcode(skip)]
+            d: bool,
+            #[bitcode(default = "5")]
+            e: u16,
+        }
+
+        let old = vec![
+            Old {
+                a: 1,
+                b: "b".into(),
+            };
+            3
+        ];
+        let new: Vec<New> = decode(&encode(&old)).unwrap();
+        assert!(new.iter().all(|n| n.a == 1 && n.c.is_empty() && n.e == 5));
+
+        let new = New {
+            a: 1,
+            b: "b".into(),
+            c: vec![1, 2],
+            d: false,
+            e: 7,
+        };
+        let encoded = encode(&new);
+        assert_eq!(encoded, encode(&(1u32, "b", vec![1u8, 2], 7u16)));
+        assert_eq!(decode::<New>(&encoded).unwrap(), new);
+
+        // Only fields at the end of the input can be missing.
+        assert!(decode::<New>(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    mod shadowed_default {
+        use crate::{Decode, Encode};
+
+        #[allow(dead_code)]
+        struct Default;
+
+        // Compiles because the generated code doesn't refer to Default unqualified.
+        #[allow(dead_code)]
+        #[derive(Encode, Decode)]
+        pub(super) struct Shadowed {
+            a: u32,
+            #[bitcode(skip)]
+            b: u8,
+            #[bitcode(default)]
+            c: u8,
+        }
+    }
+}
@@ -0,0 +1,769 @@
+use crate::consume::{consume_byte, consume_byte_arrays, consume_bytes};
+use crate::error::{err_kind, error, error_kind, ErrorKind};
+use crate::{visit, Encode, Error, Schema, Value, Visitor};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque};
+use std::marker::PhantomData;
+
+/// Header nesting limit to avoid overflowing the stack on malicious input.
+const MAX_DEPTH: usize = 1024;
+
+/// A type that can describe its encoding as a [`Schema`], e.g. for tools or other languages that
+/// read the format or for [`encode_self_describing`].
+///
+/// Use `#[derive(Describe)]` to implement. Structs and enums are [`Schema::Named`] after the type,
+/// and so are named fields and enum variants. Fields with `#[bitcode(skip)]` are left out. Fields
+/// with `#[bitcode(with = "module")]` are described as `module::Repr`.
+///
+/// ```rust
+/// use bitcode::{Describe, Encode, Schema};
+///
+/// #[derive(Encode, Describe)]
+/// struct Player {
+///     name: String,
+///     hp: Option<u8>,
+/// }
+///
+/// let named = |name: &str, schema| Schema::Named(name.into(), Box::new(schema));
+/// let fields = vec![
+///     named("name", Schema::Str),
+///     named("hp", Schema::Option(Box::new(Schema::U8))),
+/// ];
+/// assert_eq!(Player::describe(), named("Player", Schema::Tuple(fields)));
+/// ```
+pub trait Describe {
+    /// Returns the [`Schema`] of the type's encoding.
+    fn describe() -> Schema;
+}
+
+macro_rules! impl_describe {
+    ($($t:ty => $schema:ident),+) => {
+        $(
+            impl Describe for $t {
+                fn describe() -> Schema {
+                    Schema::$schema
+                }
+            }
+        )+
+    };
+}
+impl_describe!(bool => Bool, f32 => F32, f64 => F64, str => Str, String => Str);
+impl_describe!(u8 => U8, u16 => U16, u32 => U32, u64 => U64, u128 => U128, usize => U64);
+impl_describe!(i8 => I8, i16 => I16, i32 => I32, i64 => I64, i128 => I128, isize => I64);
+
+macro_rules! impl_describe_deref {
+    ($($t:ty),+) => {
+        $(
+            impl<T: Describe + ?Sized> Describe for $t {
+                fn describe() -> Schema {
+                    T::describe()
+                }
+            }
+        )+
+    };
+}
+impl_describe_deref!(&T, &mut T, Box<T>, std::rc::Rc<T>, std::sync::Arc<T>);
+
+impl Describe for std::borrow::Cow<'_, str> {
+    fn describe() -> Schema {
+        Schema::Str
+    }
+}
+
+macro_rules! impl_describe_seq {
+    ($($t:ty),+) => {
+        $(
+            impl<T: Describe> Describe for $t {
+                fn describe() -> Schema {
+                    Schema::Vec(Box::new(T::describe()))
+                }
+            }
+        )+
+    };
+}
+impl_describe_seq!(
+    [T],
+    Vec<T>,
+    VecDeque<T>,
+    LinkedList<T>,
+    BinaryHeap<T>,
+    BTreeSet<T>
+);
+impl<T: Describe, S> Describe for HashSet<T, S> {
+    fn describe() -> Schema {
+        Schema::Vec(Box::new(T::describe()))
+    }
+}
+
+impl<K: Describe, V: Describe> Describe for BTreeMap<K, V> {
+    fn describe() -> Schema {
+        Schema::Map(Box::new(K::describe()), Box::new(V::describe()))
+    }
+}
+impl<K: Describe, V: Describe, S> Describe for HashMap<K, V, S> {
+    fn describe() -> Schema {
+        Schema::Map(Box::new(K::describe()), Box::new(V::describe()))
+    }
+}
+
+impl<T: Describe, const N: usize> Describe for [T; N] {
+    fn describe() -> Schema {
+        Schema::Array(Box::new(T::describe()), N)
+    }
+}
+
+impl<T: Describe> Describe for Option<T> {
+    fn describe() -> Schema {
+        Schema::Option(Box::new(T::describe()))
+    }
+}
+
+impl<T> Describe for PhantomData<T> {
+    fn describe() -> Schema {
+        Schema::Tuple(vec![])
+    }
+}
+
+macro_rules! impl_describe_tuples {
+    ($(($($name:ident)*))+) => {
+        $(
+            impl<$($name: Describe,)*> Describe for ($($name,)*) {
+                fn describe() -> Schema {
+                    Schema::Tuple(vec![$($name::describe(),)*])
+                }
+            }
+        )+
+    };
+}
+impl_describe_tuples! {
+    ()
+    (T0)
+    (T0 T1)
+    (T0 T1 T2)
+    (T0 T1 T2 T3)
+    (T0 T1 T2 T3 T4)
+    (T0 T1 T2 T3 T4 T5)
+    (T0 T1 T2 T3 T4 T5 T6)
+    (T0 T1 T2 T3 T4 T5 T6 T7)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15)
+}
+
+/// A value decoded by [`decode_self_describing`] without knowing its Rust type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynamicValue<'a> {
+    /// A primitive value such as an integer or a string.
+    Value(Value<'a>),
+    /// The elements of a [`Schema::Vec`] or [`Schema::Array`].
+    Seq(Vec<Self>),
+    /// The key/value pairs of a [`Schema::Map`].
+    Map(Vec<(Self, Self)>),
+    /// A [`Schema::Option`].
+    Option(Option<Box<Self>>),
+    /// The fields of a [`Schema::Tuple`].
+    Tuple(Vec<Self>),
+    /// The index of a [`Schema::Enum`] variant and its fields.
+    Enum(usize, Box<Self>),
+}
+
+/// Encodes a `T:` [`Encode`] prefixed with `schema`, so it can be decoded by
+/// [`decode_self_describing`] without knowing `T`, e.g. by a program that outlives the one that
+/// encoded it. `schema` must describe `T`, which is checked in debug mode.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// use bitcode::{DynamicValue, Schema, Value};
+///
+/// let schema = Schema::Vec(Box::new(Schema::Tuple(vec![Schema::Str, Schema::U32])));
+/// let encoded = bitcode::encode_self_describing(&vec![("a", 1u32)], &schema);
+///
+/// let (decoded_schema, value) = bitcode::decode_self_describing(&encoded).unwrap();
+/// assert_eq!(decoded_schema, schema);
+/// let row = DynamicValue::Tuple(vec![
+///     DynamicValue::Value(Value::Str("a")),
+///     DynamicValue::Value(Value::U32(1)),
+/// ]);
+/// assert_eq!(value, DynamicValue::Seq(vec![row]));
+/// ```
+pub fn encode_self_describing<T: Encode + ?Sized>(t: &T, schema: &Schema) -> Vec<u8> {
+    let mut out = vec![];
+    write_schema(schema, &mut out);
+    let header_len = out.len();
+    crate::Buffer::new().encode_append(t, &mut out);
+    debug_assert!(
+        visit(&out[header_len..], schema, &mut Ignore).is_ok(),
+        "schema doesn't describe T"
+    );
+    out
+}
+
+/// Decodes the output of [`encode_self_describing`] into its [`Schema`] and a [`DynamicValue`].
+/// Strings are borrowed from `bytes`.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn decode_self_describing(mut bytes: &[u8]) -> Result<(Schema, DynamicValue<'_>), Error> {
+    let schema = read_schema(&mut bytes, 0)?;
+    let mut events = Events::default();
+    visit(bytes, &schema, &mut events)?;
+    let mut events = events.0.into_iter();
+    let value = build(&schema, &mut events);
+    debug_assert!(events.next().is_none());
+    Ok((schema, value))
+}
+
+impl Schema {
+    /// Returns if values encoded as `self` can be decoded as `other`, i.e. if they describe the
+    /// same encoding. Names are ignored, as are other differences that don't change the encoding
+    /// such as nested tuples or enums with one variant.
+    ///
+    /// ```rust
+    /// use bitcode::{Describe, Encode};
+    ///
+    /// #[derive(Encode, Describe)]
+    /// struct Old {
+    ///     id: u64,
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(Encode, Describe)]
+    /// struct Renamed {
+    ///     key: u64,
+    ///     title: String,
+    /// }
+    ///
+    /// #[derive(Encode, Describe)]
+    /// struct Reordered {
+    ///     name: String,
+    ///     id: u64,
+    /// }
+    ///
+    /// assert!(Old::describe().compatible_with(&Renamed::describe()));
+    /// assert!(Old::describe().compatible_with(&<(u64, String)>::describe()));
+    /// assert!(!Old::describe().compatible_with(&Reordered::describe()));
+    /// ```
+    pub fn compatible_with(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+
+    /// Returns a hash of the encoding `self` describes, which is equal for schemas that are
+    /// [`Schema::compatible_with`] each other. It's stable across platforms and builds, so a
+    /// client and server can exchange fingerprints when they connect to detect mismatched types
+    /// before misinterpreting each other's messages.
+    ///
+    /// **Warning:** The fingerprint is subject to change between major versions.
+    ///
+    /// ```rust
+    /// use bitcode::{Describe, Encode};
+    ///
+    /// #[derive(Encode, Describe)]
+    /// enum Message {
+    ///     Chat(String),
+    ///     Move { x: f32, y: f32 },
+    /// }
+    ///
+    /// let client = Message::describe().fingerprint();
+    /// let server = Message::describe().fingerprint();
+    /// assert_eq!(client, server);
+    /// assert_ne!(client, <Vec<Message>>::describe().fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut bytes = vec![];
+        write_schema(&self.normalized(), &mut bytes);
+        // 64 bit FNV-1a.
+        bytes.iter().fold(0xCBF29CE484222325, |hash, &b| {
+            (hash ^ b as u64).wrapping_mul(0x100000001B3)
+        })
+    }
+
+    /// Returns the simplest schema with the same encoding as `self`.
+    fn normalized(&self) -> Self {
+        let boxed = |t: &Self| Box::new(t.normalized());
+        match self {
+            Self::Vec(t) => Self::Vec(boxed(t)),
+            Self::Array(t, n) => Self::Array(boxed(t), *n),
+            Self::Map(k, v) => Self::Map(boxed(k), boxed(v)),
+            // Encoded exactly like an enum with a unit variant and a T variant.
+            Self::Option(t) => Self::Enum(vec![Self::Tuple(vec![]), t.normalized()]),
+            Self::Tuple(fields) => {
+                // A field that's a tuple is encoded exactly like its fields in place of it.
+                let mut flattened = vec![];
+                for field in fields {
+                    match field.normalized() {
+                        Self::Tuple(fields) => flattened.extend(fields),
+                        field => flattened.push(field),
+                    }
+                }
+                if flattened.len() == 1 {
+                    flattened.pop().unwrap()
+                } else {
+                    Self::Tuple(flattened)
+                }
+            }
+            // The variant of an enum with one variant isn't encoded.
+            Self::Enum(variants) if variants.len() == 1 => variants[0].normalized(),
+            Self::Enum(variants) => Self::Enum(variants.iter().map(Self::normalized).collect()),
+            Self::Named(_, t) => t.normalized(),
+            _ => self.clone(),
+        }
+    }
+}
+
+// A Schema is written in pre-order as a byte per node. Schema::Array is followed by its length,
+// Schema::Tuple and Schema::Enum by their number of children and Schema::Named by the length of
+// its name and the name.
+const BOOL: u8 = 0;
+const U8: u8 = 1;
+const U16: u8 = 2;
+const U32: u8 = 3;
+const U64: u8 = 4;
+const U128: u8 = 5;
+const I8: u8 = 6;
+const I16: u8 = 7;
+const I32: u8 = 8;
+const I64: u8 = 9;
+const I128: u8 = 10;
+const F32: u8 = 11;
+const F64: u8 = 12;
+const STR: u8 = 13;
+const VEC: u8 = 14;
+const ARRAY: u8 = 15;
+const MAP: u8 = 16;
+const OPTION: u8 = 17;
+const TUPLE: u8 = 18;
+const ENUM: u8 = 19;
+const NAMED: u8 = 20;
+
+fn write_schema(schema: &Schema, out: &mut Vec<u8>) {
+    let write_len = |len: usize, out: &mut Vec<u8>| {
+        out.extend_from_slice(&u32::try_from(len).unwrap().to_le_bytes());
+    };
+    let (tag, children): (_, &[Schema]) = match schema {
+        Schema::Bool => (BOOL, &[]),
+        Schema::U8 => (U8, &[]),
+        Schema::U16 => (U16, &[]),
+        Schema::U32 => (U32, &[]),
+        Schema::U64 => (U64, &[]),
+        Schema::U128 => (U128, &[]),
+        Schema::I8 => (I8, &[]),
+        Schema::I16 => (I16, &[]),
+        Schema::I32 => (I32, &[]),
+        Schema::I64 => (I64, &[]),
+        Schema::I128 => (I128, &[]),
+        Schema::F32 => (F32, &[]),
+        Schema::F64 => (F64, &[]),
+        Schema::Str => (STR, &[]),
+        Schema::Vec(t) => (VEC, std::slice::from_ref(t.as_ref())),
+        Schema::Array(t, n) => {
+            out.push(ARRAY);
+            write_len(*n, out);
+            write_schema(t, out);
+            return;
+        }
+        Schema::Map(k, v) => {
+            out.push(MAP);
+            write_schema(k, out);
+            write_schema(v, out);
+            return;
+        }
+        Schema::Option(t) => (OPTION, std::slice::from_ref(t.as_ref())),
+        Schema::Tuple(fields) => (TUPLE, fields),
+        Schema::Enum(variants) => (ENUM, variants),
+        Schema::Named(name, t) => {
+            out.push(NAMED);
+            write_len(name.len(), out);
+            out.extend_from_slice(name.as_bytes());
+            write_schema(t, out);
+            return;
+        }
+    };
+    out.push(tag);
+    if matches!(tag, TUPLE | ENUM) {
+        write_len(children.len(), out);
+    }
+    children.iter().for_each(|c| write_schema(c, out));
+}
+
+fn read_schema(input: &mut &[u8], depth: usize) -> Result<Schema, Error> {
+    if depth > MAX_DEPTH {
+        return err_kind(ErrorKind::LimitExceeded, "schema is too deep");
+    }
+    let depth = depth + 1;
+    let read_len = |input: &mut &[u8]| {
+        let [bytes] = consume_byte_arrays::<4>(input, 1)? else {
+            unreachable!();
+        };
+        Ok::<_, Error>(u32::from_le_bytes(*bytes) as usize)
+    };
+    let read_box = |input: &mut &[u8]| read_schema(input, depth).map(Box::new);
+    let read_children = |input: &mut &[u8]| {
+        let len = read_len(input)?;
+        // Each child takes at least 1 byte so we can reject len before allocating.
+        if len > input.len() {
+            return err_kind(ErrorKind::Eof, "EOF");
+        }
+        (0..len).map(|_| read_schema(input, depth)).collect()
+    };
+    Ok(match consume_byte(input)? {
+        BOOL => Schema::Bool,
+        U8 => Schema::U8,
+        U16 => Schema::U16,
+        U32 => Schema::U32,
+        U64 => Schema::U64,
+        U128 => Schema::U128,
+        I8 => Schema::I8,
+        I16 => Schema::I16,
+        I32 => Schema::I32,
+        I64 => Schema::I64,
+        I128 => Schema::I128,
+        F32 => Schema::F32,
+        F64 => Schema::F64,
+        STR => Schema::Str,
+        VEC => Schema::Vec(read_box(input)?),
+        ARRAY => {
+            let n = read_len(input)?;
+            Schema::Array(read_box(input)?, n)
+        }
+        MAP => Schema::Map(read_box(input)?, read_box(input)?),
+        OPTION => Schema::Option(read_box(input)?),
+        TUPLE => Schema::Tuple(read_children(input)?),
+        ENUM => Schema::Enum(read_children(input)?),
+        NAMED => {
+            let len = read_len(input)?;
+            let name = std::str::from_utf8(consume_bytes(input, len)?)
+                .map_err(|_| error_kind(ErrorKind::InvalidUtf8, "invalid utf8"))?;
+            Schema::Named(name.to_owned(), read_box(input)?)
+        }
+        _ => return Err(error("invalid schema")),
+    })
+}
+
+enum Event<'a> {
+    Value(Value<'a>),
+    BeginSeq(usize),
+    EndSeq,
+    Variant(usize),
+}
+
+/// Records the callbacks of [`visit`] so [`build`] can turn them into a [`DynamicValue`].
+#[derive(Default)]
+struct Events<'a>(Vec<Event<'a>>);
+
+impl<'a> Visitor<'a> for Events<'a> {
+    fn value(&mut self, value: Value<'a>) {
+        self.0.push(Event::Value(value));
+    }
+    fn begin_seq(&mut self, len: usize) {
+        self.0.push(Event::BeginSeq(len));
+    }
+    fn end_seq(&mut self) {
+        self.0.push(Event::EndSeq);
+    }
+    fn variant(&mut self, index: usize) {
+        self.0.push(Event::Variant(index));
+    }
+}
+
+/// Used to check that a [`Schema`] matches without recording anything.
+struct Ignore;
+
+impl Visitor<'_> for Ignore {
+    fn value(&mut self, _: Value) {}
+}
+
+type EventIter<'a> = std::vec::IntoIter<Event<'a>>;
+
+/// Builds a [`DynamicValue`] out of the `events` of visiting a `schema`. Since `visit` validated
+/// the input against `schema`, the events always match it.
+fn build<'a>(schema: &Schema, events: &mut EventIter<'a>) -> DynamicValue<'a> {
+    let mut next = || events.next().expect("missing event");
+    match schema {
+        Schema::Vec(t) | Schema::Array(t, _) => {
+            let Event::BeginSeq(len) = next() else {
+                unreachable!()
+            };
+            let elements = (0..len).map(|_| build(t, events)).collect();
+            end_seq(events);
+            DynamicValue::Seq(elements)
+        }
+        Schema::Map(k, v) => {
+            let Event::BeginSeq(len) = next() else {
+                unreachable!()
+            };
+            let pairs = (0..len)
+                .map(|_| (build(k, events), build(v, events)))
+                .collect();
+            end_seq(events);
+            DynamicValue::Map(pairs)
+        }
+        Schema::Option(t) => {
+            let Event::Variant(variant) = next() else {
+                unreachable!()
+            };
+            DynamicValue::Option((variant != 0).then(|| Box::new(build(t, events))))
+        }
+        Schema::Tuple(fields) => {
+            DynamicValue::Tuple(fields.iter().map(|f| build(f, events)).collect())
+        }
+        Schema::Enum(variants) => {
+            let Event::Variant(variant) = next() else {
+                unreachable!()
+            };
+            DynamicValue::Enum(variant, Box::new(build(&variants[variant], events)))
+        }
+        Schema::Named(_, t) => build(t, events),
+        _ => {
+            let Event::Value(value) = next() else {
+                unreachable!()
+            };
+            DynamicValue::Value(value)
+        }
+    }
+}
+
+fn end_seq(events: &mut EventIter) {
+    let Some(Event::EndSeq) = events.next() else {
+        unreachable!()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, Describe};
+    use DynamicValue as D;
+
+    #[derive(Encode)]
+    enum Shape {
+        Point,
+        Circle(f32),
+    }
+
+    #[test]
+    fn self_describing() {
+        let value = (
+            "abc",
+            vec![Shape::Circle(1.5), Shape::Point],
+            std::collections::BTreeMap::from([(7u8, [true, false])]),
+            Some(1000u64),
+            None::<i8>,
+        );
+        let schema = Schema::Tuple(vec![
+            Schema::Str,
+            Schema::Vec(Box::new(Schema::Enum(vec![
+                Schema::Tuple(vec![]),
+                Schema::F32,
+            ]))),
+            Schema::Map(
+                Box::new(Schema::U8),
+                Box::new(Schema::Array(Box::new(Schema::Bool), 2)),
+            ),
+            Schema::Option(Box::new(Schema::U64)),
+            Schema::Option(Box::new(Schema::I8)),
+        ]);
+        let encoded = encode_self_describing(&value, &schema);
+        assert!(encoded.ends_with(&encode(&value)));
+
+        let v = |v| D::Value(v);
+        let (decoded_schema, decoded) = decode_self_describing(&encoded).unwrap();
+        assert_eq!(decoded_schema, schema);
+        assert_eq!(
+            decoded,
+            D::Tuple(vec![
+                v(Value::Str("abc")),
+                D::Seq(vec![
+                    D::Enum(1, Box::new(v(Value::F32(1.5)))),
+                    D::Enum(0, Box::new(D::Tuple(vec![]))),
+                ]),
+                D::Map(vec![(
+                    v(Value::U8(7)),
+                    D::Seq(vec![v(Value::Bool(true)), v(Value::Bool(false))])
+                )]),
+                D::Option(Some(Box::new(v(Value::U64(1000))))),
+                D::Option(None),
+            ])
+        );
+
+        for i in 0..encoded.len() {
+            assert!(decode_self_describing(&encoded[..i]).is_err());
+        }
+        assert!(decode_self_describing(&[&encoded[..], &[0]].concat()).is_err());
+        assert!(decode_self_describing(&[20]).is_err());
+    }
+
+    mod rgb {
+        pub type Repr = u32;
+        pub fn encode(rgb: &[u8; 3]) -> Repr {
+            u32::from_le_bytes([rgb[0], rgb[1], rgb[2], 0])
+        }
+    }
+
+    #[derive(Encode, Describe)]
+    enum Item<'a, T> {
+        Empty,
+        Tuple(T, &'a str),
+        Struct {
+            kind: u8,
+            #[bitcode(skip)]
+            skipped: (),
+            #[bitcode(with = "rgb")]
+            color: [u8; 3],
+        },
+    }
+
+    #[derive(Encode, Describe)]
+    #[bitcode(repr = "u8")]
+    enum Fieldless {
+        A,
+    }
+
+    #[derive(Encode, Describe)]
+    struct Inventory<'a> {
+        items: Vec<Item<'a, i64>>,
+        fieldless: Fieldless,
+        r#type: bool,
+    }
+
+    #[test]
+    fn describe() {
+        let named = |name: &str, schema| Schema::Named(name.into(), Box::new(schema));
+        let item = Schema::Enum(vec![
+            named("Empty", Schema::Tuple(vec![])),
+            named("Tuple", Schema::Tuple(vec![Schema::I64, Schema::Str])),
+            named(
+                "Struct",
+                Schema::Tuple(vec![named("kind", Schema::U8), named("color", Schema::U32)]),
+            ),
+        ]);
+        let schema = named(
+            "Inventory",
+            Schema::Tuple(vec![
+                named("items", Schema::Vec(Box::new(named("Item", item)))),
+                named("fieldless", named("Fieldless", Schema::U8)),
+                named("type", Schema::Bool),
+            ]),
+        );
+        assert_eq!(Inventory::describe(), schema);
+        assert_eq!(
+            <(Option<[u16; 2]>, Box<str>)>::describe(),
+            Schema::Tuple(vec![
+                Schema::Option(Box::new(Schema::Array(Box::new(Schema::U16), 2))),
+                Schema::Str
+            ])
+        );
+
+        let inventory = Inventory {
+            items: vec![
+                Item::Struct {
+                    kind: 1,
+                    skipped: (),
+                    color: [1, 2, 3],
+                },
+                Item::Empty,
+                Item::Tuple(-5, "a"),
+            ],
+            fieldless: Fieldless::A,
+            r#type: true,
+        };
+        let encoded = encode_self_describing(&inventory, &Inventory::describe());
+        let (decoded_schema, value) = decode_self_describing(&encoded).unwrap();
+        assert_eq!(decoded_schema, schema);
+        let D::Tuple(fields) = value else {
+            panic!("{value:?}");
+        };
+        let D::Seq(items) = &fields[0] else {
+            panic!("{fields:?}");
+        };
+        assert_eq!(
+            items[2],
+            D::Enum(
+                1,
+                Box::new(D::Tuple(vec![v(Value::I64(-5)), v(Value::Str("a"))]))
+            )
+        );
+    }
+
+    fn v(v: Value) -> DynamicValue {
+        D::Value(v)
+    }
+
+    #[test]
+    fn compatible_with() {
+        #[derive(Encode, Describe)]
+        enum UnitOrU8 {
+            Unit,
+            U8(u8),
+        }
+        #[derive(Encode, Describe)]
+        enum One {
+            Only { a: u16, b: (bool, ()) },
+        }
+        #[derive(Encode, Describe)]
+        struct Nested {
+            one: One,
+            c: Option<u8>,
+        }
+
+        let compatible = |a: Schema, b: Schema| {
+            assert_eq!(a.compatible_with(&b), a.fingerprint() == b.fingerprint());
+            a.compatible_with(&b)
+        };
+        assert!(compatible(Nested::describe(), Nested::describe()));
+        assert!(compatible(
+            Nested::describe(),
+            <(u16, bool, UnitOrU8)>::describe()
+        ));
+        assert!(compatible(<(u8,)>::describe(), u8::describe()));
+        assert!(!compatible(u8::describe(), i8::describe()));
+        assert!(!compatible(<[u8; 2]>::describe(), <(u8, u8)>::describe()));
+        assert!(!compatible(<Vec<u8>>::describe(), <[u8; 2]>::describe()));
+        assert!(!compatible(
+            Nested::describe(),
+            <(u16, u8, bool)>::describe()
+        ));
+
+        // The schemas that are compatible are encoded the same way.
+        assert_eq!(encode(&Some(5u8)), encode(&UnitOrU8::U8(5)));
+        let nested = Nested {
+            one: One::Only {
+                a: 1,
+                b: (true, ()),
+            },
+            c: None,
+        };
+        assert_eq!(encode(&nested), encode(&(1u16, true, UnitOrU8::Unit)));
+        assert_eq!(encode(&(7u8,)), encode(&7u8));
+    }
+
+    #[test]
+    fn fingerprint() {
+        // Fingerprints don't change between builds.
+        assert_eq!(u8::describe().fingerprint(), 0xAF63BC4C8601B62C);
+        assert_ne!(u8::describe().fingerprint(), u16::describe().fingerprint());
+    }
+
+    #[test]
+    fn too_deep() {
+        let mut deep = vec![VEC; MAX_DEPTH + 1];
+        deep.push(U8);
+        assert_eq!(
+            decode_self_describing(&deep).unwrap_err().kind(),
+            ErrorKind::LimitExceeded
+        );
+        // Claims more children than there are bytes.
+        let huge = [&[TUPLE], u32::MAX.to_le_bytes().as_slice()].concat();
+        assert!(decode_self_describing(&huge).is_err());
+    }
+
+    #[test]
+    #[should_panic = "schema doesn't describe T"]
+    #[cfg(debug_assertions)]
+    fn wrong_schema() {
+        encode_self_describing(&vec![1u8], &Schema::U64);
+    }
+}
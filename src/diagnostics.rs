@@ -0,0 +1,117 @@
+//! Records which fast paths sequences were encoded/decoded with (requires the `diagnostics`
+//! feature). Recording is thread local and slows down encoding/decoding, so it's only meant for
+//! finding out why something got slower.
+//!
+//! ```rust
+//! use bitcode::diagnostics::{self, Path};
+//!
+//! diagnostics::clear();
+//! bitcode::encode(&vec![vec![1u8, 2, 3]; 10]);
+//! let records = diagnostics::take();
+//! assert!(records.iter().any(|r| r.element == "u8" && r.path == Path::WildCopy));
+//! ```
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+/// How a sequence's elements were encoded/decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Path {
+    /// Copied all of a sequence's primitive elements at once.
+    Memcpy,
+    /// Copied many short sequences of primitive elements with fixed size copies.
+    WildCopy,
+    /// Copied many sequences of primitive elements one sequence at a time since some were too long
+    /// for [`Path::WildCopy`].
+    Fallback,
+    /// Encoded/decoded each element separately since they aren't primitives.
+    PerElement,
+}
+
+/// How many times sequences of `element` were encoded/decoded with `path`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The type name of the sequence's elements.
+    pub element: &'static str,
+    /// True if decoding, false if encoding.
+    pub decode: bool,
+    /// The path that was taken.
+    pub path: Path,
+    /// The number of sequences (or chunks of sequences for [`Path::WildCopy`] and
+    /// [`Path::Fallback`]).
+    pub count: usize,
+}
+
+type Key = (&'static str, bool, Path);
+
+thread_local! {
+    static RECORDS: RefCell<BTreeMap<Key, usize>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+#[inline(always)]
+pub(crate) fn record<T: ?Sized>(decode: bool, path: Path) {
+    let key = (std::any::type_name::<T>(), decode, path);
+    RECORDS.with(|r| *r.borrow_mut().entry(key).or_default() += 1);
+}
+
+/// Returns the records of the current thread and clears them.
+pub fn take() -> Vec<Record> {
+    RECORDS
+        .with(|r| std::mem::take(&mut *r.borrow_mut()))
+        .into_iter()
+        .map(|((element, decode, path), count)| Record {
+            element,
+            decode,
+            path,
+            count,
+        })
+        .collect()
+}
+
+/// Clears the records of the current thread.
+pub fn clear() {
+    RECORDS.with(|r| r.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, Encode};
+
+    fn encode_paths<T: Encode + ?Sized>(t: &T) -> Vec<(&'static str, Path)> {
+        clear();
+        encode(t);
+        take().into_iter().map(|r| (r.element, r.path)).collect()
+    }
+
+    #[test]
+    fn diagnostics() {
+        assert_eq!(encode_paths(&vec![1u32; 10]), [("u32", Path::Memcpy)]);
+        assert_eq!(
+            encode_paths(&vec![vec![1u32]; 10]),
+            [
+                ("alloc::vec::Vec<u32>", Path::PerElement),
+                ("u32", Path::WildCopy)
+            ]
+        );
+        assert_eq!(
+            encode_paths(&vec![vec![1u8; 100]; 2]),
+            [
+                ("alloc::vec::Vec<u8>", Path::PerElement),
+                ("u8", Path::Fallback)
+            ]
+        );
+
+        let encoded = encode(&vec![(1u8, "a")]);
+        clear();
+        decode::<Vec<(u8, &str)>>(&encoded).unwrap();
+        assert_eq!(
+            take(),
+            [Record {
+                element: "(u8, &str)",
+                decode: true,
+                path: Path::PerElement,
+                count: 1,
+            }]
+        );
+    }
+}
@@ -7,35 +7,195 @@ pub fn err<T>(msg: &'static str) -> Result<T, Error> {
     Err(error(msg))
 }
 
-/// Creates an error with a message that might be displayed.
-pub fn error(_msg: &'static str) -> Error {
-    #[cfg(debug_assertions)]
-    return Error(Cow::Borrowed(_msg));
-    #[cfg(not(debug_assertions))]
-    Error(())
+/// Creates an [`ErrorKind::Invalid`] error with a message that might be displayed.
+pub fn error(msg: &'static str) -> Error {
+    error_kind(ErrorKind::Invalid, msg)
+}
+
+/// Short version of `Err(error_kind(kind, "..."))`.
+pub fn err_kind<T>(kind: ErrorKind, msg: &'static str) -> Result<T, Error> {
+    Err(error_kind(kind, msg))
+}
+
+/// Creates an error of `kind` with a message that might be displayed.
+pub fn error_kind(kind: ErrorKind, _msg: &'static str) -> Error {
+    Error {
+        kind,
+        #[cfg(debug_assertions)]
+        details: Box::new(Details::new(Cow::Borrowed(_msg))),
+    }
 }
 
-/// Creates an error from a `T:` [`Display`].
-#[cfg(feature = "serde")]
+/// Creates an [`ErrorKind::Custom`] error from a `T:` [`Display`].
 pub fn error_from_display(_t: impl Display) -> Error {
-    #[cfg(debug_assertions)]
-    return Error(Cow::Owned(_t.to_string()));
-    #[cfg(not(debug_assertions))]
-    Error(())
+    Error {
+        kind: ErrorKind::Custom,
+        #[cfg(debug_assertions)]
+        details: Box::new(Details::new(Cow::Owned(_t.to_string()))),
+    }
 }
 
-#[cfg(debug_assertions)]
-type ErrorImpl = Cow<'static, str>;
-#[cfg(not(debug_assertions))]
-type ErrorImpl = ();
+/// What kind of [`Error`] occurred. More kinds may be added in minor versions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ended before everything was decoded, e.g. a truncated frame.
+    Eof,
+    /// The input has bytes left over after everything was decoded.
+    TrailingBytes,
+    /// A string isn't valid UTF-8.
+    InvalidUtf8,
+    /// An enum (or `Option`) variant is out of range.
+    InvalidVariant,
+    /// The lengths in the input add up to more than can be decoded.
+    LengthOverflow,
+    /// A limit was exceeded, such as the maximum recursion depth or one set with
+    /// [`DecodeOptions`][`crate::DecodeOptions`].
+    LimitExceeded,
+    /// The input or type uses something that isn't supported, e.g. a newer
+    /// `#[bitcode(version = ..)]` than the one being decoded.
+    Unsupported,
+    /// An [`EncodeTarget`][`crate::EncodeTarget`] was too small to fit the encoding.
+    TargetTooSmall,
+    /// The checksum appended by [`encode_checked`][`crate::encode_checked`] doesn't match.
+    Checksum,
+    /// A user provided function such as `#[bitcode(validate = "path")]`, a `TryFrom` impl, a
+    /// serde impl or a [`Transform`][`crate::Transform`] returned an error.
+    Custom,
+    /// The input is invalid in some other way, e.g. a value is outside of its range.
+    Invalid,
+}
 
 /// Decoding / (De)serialization errors.
 /// # Debug mode
-/// In debug mode, the error contains a reason.
+/// In debug mode, the error contains a reason and, when decoding, where in the input it occurred
+/// (see [`Error::offset`] and [`Error::path`]).
 /// # Release mode
-/// In release mode, the error is a zero-sized type for efficiency.
+/// In release mode, the error is just an [`ErrorKind`] for efficiency.
 #[cfg_attr(test, derive(PartialEq))]
-pub struct Error(ErrorImpl);
+pub struct Error {
+    kind: ErrorKind,
+    // Boxed to keep Result<(), Error> small since decoders return it recursively.
+    #[cfg(debug_assertions)]
+    details: Box<Details>,
+}
+
+#[cfg(debug_assertions)]
+#[cfg_attr(test, derive(PartialEq))]
+struct Details {
+    msg: Cow<'static, str>,
+    offset: Option<usize>,
+    /// The length of the input that remained when the innermost field started being populated.
+    remaining: Option<usize>,
+    /// Field names from innermost to outermost.
+    path: Vec<&'static str>,
+}
+
+#[cfg(debug_assertions)]
+impl Details {
+    fn new(msg: Cow<'static, str>) -> Self {
+        Self {
+            msg,
+            offset: None,
+            remaining: None,
+            path: vec![],
+        }
+    }
+}
+
+impl Error {
+    /// Returns the kind of error, e.g. to tell a truncated frame ([`ErrorKind::Eof`]) apart from
+    /// corrupt data.
+    ///
+    /// ```rust
+    /// use bitcode::ErrorKind;
+    ///
+    /// let encoded = bitcode::encode(&vec![1u32, 2, 3]);
+    /// let truncated = &encoded[..encoded.len() - 1];
+    /// let error = bitcode::decode::<Vec<u32>>(truncated).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::Eof);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns the offset in the input where the innermost field in [`Error::path`] starts (or
+    /// where decoding stopped if there isn't one, e.g. the start of trailing bytes). Since values
+    /// are decoded a column at a time, this isn't where the invalid value is. Always `None` in
+    /// release mode.
+    pub fn offset(&self) -> Option<usize> {
+        #[cfg(debug_assertions)]
+        return self.details.offset;
+        #[cfg(not(debug_assertions))]
+        None
+    }
+
+    /// Returns the path of derived fields leading to the value that couldn't be decoded, e.g.
+    /// `players.inventory.items` (or `Variant.field` for enums). Since a column holds the values
+    /// of every element of a collection, it doesn't contain indices. Always `None` in release mode
+    /// or if the error didn't occur within a field.
+    ///
+    /// ```rust
+    /// use bitcode::{Decode, Encode};
+    ///
+    /// #[derive(Debug, Encode, Decode)]
+    /// struct Player {
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(Debug, Encode, Decode)]
+    /// struct Game {
+    ///     players: Vec<Player>,
+    /// }
+    ///
+    /// let mut encoded = bitcode::encode(&Game { players: vec![Player { name: "a".into() }] });
+    /// *encoded.last_mut().unwrap() = 0xFF; // Invalid UTF-8.
+    /// let error = bitcode::decode::<Game>(&encoded).unwrap_err();
+    /// # if cfg!(debug_assertions) {
+    /// assert_eq!(error.path().as_deref(), Some("players.name"));
+    /// # }
+    /// ```
+    pub fn path(&self) -> Option<String> {
+        #[cfg(debug_assertions)]
+        return (!self.details.path.is_empty()).then(|| {
+            let mut path = self.details.path.clone();
+            path.reverse();
+            path.join(".")
+        });
+        #[cfg(not(debug_assertions))]
+        None
+    }
+
+    /// Sets the offset in `input` of the innermost field, or of `remaining` if the error didn't
+    /// occur in a field.
+    pub(crate) fn at_offset(self, _input: &[u8], _remaining: &[u8]) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            let mut e = self;
+            let remaining = e.details.remaining.unwrap_or(_remaining.len());
+            e.details.offset = Some(_input.len() - remaining);
+            e
+        }
+        #[cfg(not(debug_assertions))]
+        self
+    }
+
+    /// Adds a field to the start of the path. `remaining` is the length of the input when the field
+    /// started being populated. Used by `#[derive(Decode)]`.
+    #[cfg(feature = "derive")]
+    pub(crate) fn in_field(self, _name: &'static str, _remaining: usize) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            let mut e = self;
+            e.details.path.push(_name);
+            e.details.remaining.get_or_insert(_remaining);
+            e
+        }
+        #[cfg(not(debug_assertions))]
+        self
+    }
+}
+
 impl Debug for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "Error({:?})", self.to_string())
@@ -44,9 +204,65 @@ impl Debug for Error {
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         #[cfg(debug_assertions)]
-        return f.write_str(&self.0);
+        {
+            f.write_str(&self.details.msg)?;
+            if let Some(path) = self.path() {
+                write!(f, " at {path}")?;
+            }
+            if let Some(offset) = self.details.offset {
+                write!(f, " (byte {offset})")?;
+            }
+            Ok(())
+        }
         #[cfg(not(debug_assertions))]
         f.write_str("bitcode error")
     }
 }
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorKind;
+    use crate::{decode, decode_with_options, encode, Buffer, DecodeOptions};
+
+    #[test]
+    fn kind() {
+        fn kind<T>(r: Result<T, crate::Error>) -> ErrorKind {
+            r.map(drop).unwrap_err().kind()
+        }
+        let encoded = encode(&vec![1u32, 2, 3]);
+        assert_eq!(kind(decode::<Vec<u32>>(&encoded[..1])), ErrorKind::Eof);
+        let trailing = [encoded.as_slice(), &[0]].concat();
+        assert_eq!(
+            kind(decode::<Vec<u32>>(&trailing)),
+            ErrorKind::TrailingBytes
+        );
+        assert_eq!(kind(decode::<u8>(&[])), ErrorKind::Eof);
+
+        let invalid_utf8 = encode(&vec![0xFFu8]);
+        assert_eq!(
+            kind(decode::<String>(&invalid_utf8)),
+            ErrorKind::InvalidUtf8
+        );
+        #[derive(Debug, crate::Decode)]
+        enum Five {
+            A,
+            B,
+            C,
+            D,
+            E,
+        }
+        let _ = [Five::A, Five::B, Five::C, Five::D, Five::E];
+        assert_eq!(kind(decode::<Five>(&[5])), ErrorKind::InvalidVariant);
+        let invalid_char = encode(&0x110000u32);
+        assert_eq!(kind(decode::<char>(&invalid_char)), ErrorKind::Invalid);
+
+        let options = DecodeOptions::new().max_vec_len(2);
+        let limited = decode_with_options::<Vec<u32>>(&encoded, &options);
+        assert_eq!(kind(limited), ErrorKind::LimitExceeded);
+
+        let mut small = [0u8; 2];
+        let too_small = Buffer::new().encode_into(&encoded, small.as_mut_slice());
+        assert_eq!(kind(too_small), ErrorKind::TargetTooSmall);
+    }
+}
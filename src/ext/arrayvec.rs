@@ -20,7 +20,7 @@ impl<const N: usize> Encoder<ArrayString<N>> for StrEncoder {
         let s = t.as_str();
         self.0.lengths.encode_less_than_255(s.len());
         let primitives = self.0.elements.as_primitive().unwrap();
-        primitives.reserve(N); // TODO Buffer::reserve impl additional * N so we can remove encode_vectored impl.
+        primitives.reserve(N); // TODO EncoderBuffer::reserve impl additional * N so we can remove encode_vectored impl.
         let dst = primitives.end_ptr();
 
         // Safety: `s.as_ptr()` points to `N` valid bytes since it's referencing an ArrayString<N>.
@@ -186,6 +186,13 @@ mod tests {
         assert_eq!(decode::<ArrayString<2>>(&b).unwrap(), v);
         assert_eq!(decode::<ArrayString<3>>(&b).unwrap().as_str(), v.as_str());
         assert!(decode::<ArrayString<0>>(&encode(&ArrayString::<0>::default())).is_ok());
+
+        // N > 64 takes a different path in encode and decode.
+        let v = ArrayString::<100>::from(&"ä".repeat(50)).unwrap();
+        let b = encode(&vec![v; 3]);
+        assert!(decode::<Vec<ArrayString<99>>>(&b).is_err());
+        assert_eq!(decode::<Vec<ArrayString<100>>>(&b).unwrap(), vec![v; 3]);
+        assert!(decode::<Vec<ArrayString<100>>>(&b[..b.len() - 1]).is_err());
     }
 
     #[test]
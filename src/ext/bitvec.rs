@@ -0,0 +1,78 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer};
+use crate::derive::vec::{VecDecoder, VecEncoder};
+use crate::derive::{Decode, Encode};
+use bitvec::order::BitOrder;
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+use std::num::NonZeroUsize;
+
+// Encoded like a Vec<bool> so the bits are packed by pack_bools.
+impl<T: BitStore, O: BitOrder> Encoder<BitSlice<T, O>> for VecEncoder<bool> {
+    #[inline(always)]
+    fn encode(&mut self, bits: &BitSlice<T, O>) {
+        let n = bits.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.elements.reserve(n);
+            for bit in bits.iter().by_vals() {
+                self.elements.encode(&bit);
+            }
+        }
+    }
+}
+impl<T: BitStore, O: BitOrder> Encode for BitSlice<T, O> {
+    type Encoder = VecEncoder<bool>;
+}
+
+impl<T: BitStore, O: BitOrder> Encoder<BitVec<T, O>> for VecEncoder<bool> {
+    #[inline(always)]
+    fn encode(&mut self, bits: &BitVec<T, O>) {
+        self.encode(bits.as_bitslice());
+    }
+}
+impl<T: BitStore, O: BitOrder> Encode for BitVec<T, O> {
+    type Encoder = VecEncoder<bool>;
+}
+
+impl<'a, T: BitStore, O: BitOrder> Decoder<'a, BitVec<T, O>> for VecDecoder<'a, bool> {
+    #[inline(always)]
+    fn decode(&mut self) -> BitVec<T, O> {
+        let n = self.lengths.decode();
+        let mut bits = BitVec::with_capacity(n);
+        for _ in 0..n {
+            bits.push(self.elements.decode());
+        }
+        bits
+    }
+}
+impl<'a, T: BitStore, O: BitOrder> Decode<'a> for BitVec<T, O> {
+    type Decoder = VecDecoder<'a, bool>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use bitvec::prelude::*;
+
+    #[test]
+    fn bit_vec() {
+        let bools: Vec<bool> = crate::random_data(1000);
+        let bits: BitVec = bools.iter().collect();
+        let encoded = encode(&bits);
+        assert_eq!(encoded, encode(&bools));
+        assert_eq!(encode(bits.as_bitslice()), encoded);
+        assert_eq!(decode::<BitVec>(&encoded).unwrap(), bits);
+        assert_eq!(decode::<BitVec<u8, Msb0>>(&encoded).unwrap(), bits);
+
+        // 8 bits per byte.
+        let grid = bitvec![1; 1 << 20];
+        assert!(encode(&grid).len() <= (1 << 20) / 8 + 16);
+        assert_eq!(decode::<BitVec>(&encode(&grid)).unwrap(), grid);
+    }
+
+    fn bench_data() -> BitVec {
+        crate::random_data::<bool>(1000).into_iter().collect()
+    }
+    crate::bench_encode_decode!(bit_vec: BitVec);
+}
@@ -0,0 +1,159 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::consume_bytes;
+use crate::derive::vec::VecEncoder;
+use crate::derive::{Decode, Encode};
+use crate::fast::{NextUnchecked, SliceImpl};
+use crate::length::LengthDecoder;
+use crate::options::decode_options;
+use crate::u8_char::U8Char;
+use crate::Error;
+use bytes::{Bytes, BytesMut};
+use std::cell::Cell;
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+/// Like [`decode`][`crate::decode`], but [`Bytes`] are decoded as slices of `bytes` (with
+/// [`Bytes::slice_ref`]) instead of being copied.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// use bytes::Bytes;
+///
+/// let bytes = Bytes::from(bitcode::encode(&Bytes::from_static(b"payload")));
+/// let decoded: Bytes = bitcode::decode_bytes(&bytes).unwrap();
+/// assert_eq!(decoded, b"payload"[..]);
+/// assert!(bytes.as_ptr_range().contains(&decoded.as_ptr()));
+/// ```
+pub fn decode_bytes<'a, T: Decode<'a>>(bytes: &'a Bytes) -> std::result::Result<T, Error> {
+    struct Restore(Option<NonNull<Bytes>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SHARED.with(|s| s.set(self.0));
+        }
+    }
+    let _restore = Restore(SHARED.with(|s| s.replace(Some(NonNull::from(bytes)))));
+    crate::decode(bytes)
+}
+
+// Like the context in context.rs, only BytesDecoder needs it so it isn't passed through decoders.
+thread_local! {
+    static SHARED: Cell<Option<NonNull<Bytes>>> = const { Cell::new(None) };
+}
+
+/// Returns a slice of the [`Bytes`] passed to the innermost [`decode_bytes`] call if it contains
+/// `s`, or a copy of `s` otherwise.
+fn slice_or_copy(s: &[u8]) -> Bytes {
+    if let Some(shared) = SHARED.with(Cell::get) {
+        // Safety: `decode_bytes` holds a `&Bytes` until decoding is done.
+        let shared = unsafe { shared.as_ref() };
+        let range = shared.as_ptr_range();
+        let s_range = s.as_ptr_range();
+        if range.start <= s_range.start && s_range.end <= range.end {
+            return shared.slice_ref(s);
+        }
+    }
+    Bytes::copy_from_slice(s)
+}
+
+/// Encodes bytes like a `&str` (as raw bytes instead of as `u8` integers which could be packed), so
+/// they can be decoded by slicing the input.
+#[derive(Default)]
+pub struct BytesEncoder(VecEncoder<U8Char>);
+
+impl EncoderBuffer for BytesEncoder {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional);
+    }
+}
+
+macro_rules! impl_encode {
+    ($($t:ty),+) => {
+        $(
+            impl Encoder<$t> for BytesEncoder {
+                #[inline(always)]
+                fn encode(&mut self, t: &$t) {
+                    self.0.encode(bytemuck::must_cast_slice(t.as_ref()));
+                }
+            }
+            impl Encode for $t {
+                type Encoder = BytesEncoder;
+            }
+        )+
+    };
+}
+impl_encode!(Bytes, BytesMut);
+
+#[derive(Default)]
+pub struct BytesDecoder<'a> {
+    lengths: LengthDecoder<'a>,
+    bytes: SliceImpl<'a, u8>,
+}
+
+impl<'a> View<'a> for BytesDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.lengths.populate(input, length)?;
+        // Safety: `length` was just passed to populate.
+        let max = decode_options().max_vec_len;
+        unsafe { self.lengths.check_max(length, max, "vec too long")? };
+        self.bytes = consume_bytes(input, self.lengths.length())?.into();
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, Bytes> for BytesDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> Bytes {
+        // Safety: populate consumed the sum of the lengths.
+        slice_or_copy(unsafe { self.bytes.chunk_unchecked(self.lengths.decode()) })
+    }
+}
+impl<'a> Decode<'a> for Bytes {
+    type Decoder = BytesDecoder<'a>;
+}
+
+impl<'a> Decoder<'a, BytesMut> for BytesDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> BytesMut {
+        // Safety: populate consumed the sum of the lengths.
+        BytesMut::from(unsafe { self.bytes.chunk_unchecked(self.lengths.decode()) })
+    }
+}
+impl<'a> Decode<'a> for BytesMut {
+    type Decoder = BytesDecoder<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_bytes;
+    use crate::{decode, encode};
+    use bytes::{Bytes, BytesMut};
+
+    #[test]
+    fn bytes() {
+        let v: Vec<Bytes> = (0..100u8)
+            .map(|i| Bytes::from(vec![i; i as usize]))
+            .collect();
+        let encoded = encode(&v);
+        assert_eq!(decode::<Vec<Bytes>>(&encoded).unwrap(), v);
+        assert_eq!(decode::<Vec<BytesMut>>(&encoded).unwrap(), v);
+        assert!(decode::<Vec<Bytes>>(&encoded[..encoded.len() - 1]).is_err());
+
+        // Encoded like a &str of the bytes.
+        assert_eq!(encode(&Bytes::from_static(b"abc")), encode("abc"));
+        assert_eq!(encode(&BytesMut::from("abc")), encode("abc"));
+
+        // Decoded as slices of the input.
+        let shared = Bytes::from(encoded);
+        let decoded: Vec<Bytes> = decode_bytes(&shared).unwrap();
+        assert_eq!(decoded, v);
+        let range = shared.as_ptr_range();
+        assert!(decoded[1..].iter().all(|b| range.contains(&b.as_ptr())));
+        drop(shared);
+        assert_eq!(decoded, v);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::derive::convert::impl_convert;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+
+// Each component is its own column (like a struct's fields) so similar dates/times pack well.
+impl_convert!(checked NaiveDate => i32, |s| chrono::Datelike::num_days_from_ce(s), |r| {
+    NaiveDate::from_num_days_from_ce_opt(r)
+});
+// Nanoseconds are >= 1_000_000_000 during a leap second.
+impl_convert!(checked NaiveTime => (u32, u32), |s| {
+    (s.num_seconds_from_midnight(), s.nanosecond())
+}, |r| NaiveTime::from_num_seconds_from_midnight_opt(r.0, r.1));
+impl_convert!(NaiveDateTime => (NaiveDate, NaiveTime), |s| (s.date(), s.time()), |r| {
+    NaiveDateTime::new(r.0, r.1)
+});
+impl_convert!(DateTime<Utc> => NaiveDateTime, |s| s.naive_utc(), |r| r.and_utc());
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+    #[test]
+    fn chrono() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let time = NaiveTime::from_hms_nano_opt(23, 59, 59, 1_500_000_000).unwrap(); // Leap second.
+        let date_time = NaiveDateTime::new(date, time);
+        let utc = date_time.and_utc();
+        let min = NaiveDateTime::MIN.and_utc();
+        let max = NaiveDateTime::MAX.and_utc();
+
+        assert_eq!(decode::<NaiveDate>(&encode(&date)).unwrap(), date);
+        assert_eq!(decode::<NaiveTime>(&encode(&time)).unwrap(), time);
+        assert_eq!(
+            decode::<NaiveDateTime>(&encode(&date_time)).unwrap(),
+            date_time
+        );
+        let v = vec![utc, min, max];
+        assert_eq!(decode::<Vec<DateTime<Utc>>>(&encode(&v)).unwrap(), v);
+
+        assert!(decode::<NaiveDate>(&encode(&i32::MAX)).is_err());
+        assert!(decode::<NaiveTime>(&encode(&(86400u32, 0u32))).is_err());
+        assert!(decode::<NaiveTime>(&encode(&(0u32, 1_000_000_000u32))).is_err()); // Not :59.
+        assert!(
+            decode::<[NaiveDateTime; 2]>(&encode(&[(0i32, 0u32, 0u32), (i32::MIN, 0, 0)])).is_err()
+        );
+    }
+}
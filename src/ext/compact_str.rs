@@ -0,0 +1,55 @@
+use crate::coder::{Decoder, Encoder};
+use crate::derive::{Decode, Encode};
+use crate::str::{StrDecoder, StrEncoder};
+use compact_str::CompactString;
+
+impl Encoder<CompactString> for StrEncoder {
+    #[inline(always)]
+    fn encode(&mut self, t: &CompactString) {
+        self.encode(t.as_str());
+    }
+
+    #[inline(always)]
+    fn encode_vectored<'a>(&mut self, i: impl Iterator<Item = &'a CompactString> + Clone) {
+        self.encode_vectored(i.map(CompactString::as_str));
+    }
+}
+impl Encode for CompactString {
+    type Encoder = StrEncoder;
+}
+
+impl<'a> Decoder<'a, CompactString> for StrDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> CompactString {
+        // Short strings are stored inline without allocating a String first.
+        let s: &str = self.decode();
+        CompactString::from(s)
+    }
+}
+impl<'a> Decode<'a> for CompactString {
+    type Decoder = StrDecoder<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use compact_str::CompactString;
+
+    #[test]
+    fn compact_str() {
+        let v: Vec<CompactString> = (0..100)
+            .map(|i| CompactString::from("a".repeat(i)))
+            .collect();
+        let encoded = encode(&v);
+        assert_eq!(
+            encoded,
+            encode(&v.iter().map(CompactString::as_str).collect::<Vec<_>>())
+        );
+        assert_eq!(decode::<Vec<CompactString>>(&encoded).unwrap(), v);
+
+        // Invalid UTF-8 is rejected.
+        let mut encoded = encode("a");
+        *encoded.last_mut().unwrap() = 0xFF;
+        assert!(decode::<CompactString>(&encoded).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::bool::{BoolDecoder, BoolEncoder};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::mul_length;
+use crate::derive::{Decode, Encode};
+use enumset::{EnumSet, EnumSetType};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+fn variant_count<T: EnumSetType>() -> usize {
+    EnumSet::<T>::variant_count() as usize
+}
+
+/// Encodes an [`EnumSet<T>`] as one packed bool per variant of `T` (in the order of
+/// [`EnumSet::all`]), so sets of enums with sparse discriminants don't waste any bits.
+pub struct EnumSetEncoder<T>(BoolEncoder, PhantomData<fn(&T)>);
+
+// Can't derive since it would bound T: Default.
+impl<T> Default for EnumSetEncoder<T> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<T: EnumSetType> Encoder<EnumSet<T>> for EnumSetEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, set: &EnumSet<T>) {
+        for v in EnumSet::<T>::all() {
+            self.0.encode(&set.contains(v));
+        }
+    }
+}
+
+impl<T: EnumSetType> EncoderBuffer for EnumSetEncoder<T> {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.0.collect_into(out);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        let Some(n) = NonZeroUsize::new(variant_count::<T>()) else {
+            return; // self.0.reserve takes NonZeroUsize and `additional * n == 0`.
+        };
+        self.0.reserve(additional.checked_mul(n).unwrap());
+    }
+}
+
+impl<T: EnumSetType> Encode for EnumSet<T> {
+    type Encoder = EnumSetEncoder<T>;
+}
+
+pub struct EnumSetDecoder<'a, T>(BoolDecoder<'a>, PhantomData<fn() -> T>);
+
+// Can't derive since it would bound T: Default.
+impl<T> Default for EnumSetDecoder<'_, T> {
+    fn default() -> Self {
+        Self(Default::default(), PhantomData)
+    }
+}
+
+impl<'a, T: EnumSetType> View<'a> for EnumSetDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        let length = mul_length(length, variant_count::<T>())?;
+        self.0.populate(input, length)
+    }
+}
+
+impl<'a, T: EnumSetType> Decoder<'a, EnumSet<T>> for EnumSetDecoder<'a, T> {
+    #[inline(always)]
+    fn decode(&mut self) -> EnumSet<T> {
+        let mut set = EnumSet::new();
+        for v in EnumSet::<T>::all() {
+            if self.0.decode() {
+                set.insert(v);
+            }
+        }
+        set
+    }
+}
+
+impl<'a, T: EnumSetType> Decode<'a> for EnumSet<T> {
+    type Decoder = EnumSetDecoder<'a, T>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use enumset::{EnumSet, EnumSetType};
+
+    #[derive(Debug, EnumSetType)]
+    enum Sparse {
+        A = 1,
+        B = 5,
+        C = 100,
+    }
+
+    #[test]
+    fn enum_set() {
+        let sets: Vec<EnumSet<Sparse>> = crate::random_data::<u8>(100)
+            .into_iter()
+            .map(|i| {
+                let mut set = EnumSet::new();
+                for (bit, v) in [Sparse::A, Sparse::B, Sparse::C].into_iter().enumerate() {
+                    if i & (1 << bit) != 0 {
+                        set.insert(v);
+                    }
+                }
+                set
+            })
+            .collect();
+        let encoded = encode(&sets);
+        assert_eq!(decode::<Vec<EnumSet<Sparse>>>(&encoded).unwrap(), sets);
+
+        // Encoded like one bool per variant.
+        let bools: Vec<[bool; 3]> = sets
+            .iter()
+            .map(|s| {
+                [
+                    s.contains(Sparse::A),
+                    s.contains(Sparse::B),
+                    s.contains(Sparse::C),
+                ]
+            })
+            .collect();
+        assert_eq!(encoded, encode(&bools));
+    }
+
+    fn bench_data() -> Vec<EnumSet<Sparse>> {
+        crate::random_data::<bool>(1000)
+            .into_iter()
+            .map(|b| if b { EnumSet::all() } else { Sparse::B.into() })
+            .collect()
+    }
+    crate::bench_encode_decode!(enum_set_vec: Vec<EnumSet<Sparse>>);
+}
@@ -40,3 +40,29 @@ impl_glam!(f64, DVec2, DVec3, DVec4, DQuat, DMat2, DMat3, DMat4);
 impl_glam!(u32, UVec2, UVec3, UVec4);
 impl_glam!(i32, IVec2, IVec3, IVec4);
 impl_glam!(bool, BVec2, BVec3, BVec4);
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use glam::*;
+
+    #[test]
+    fn glam() {
+        type Transform = (Vec3, Quat, Mat4, IVec2);
+        let transforms: Vec<Transform> =
+            crate::random_data::<([f32; 3], [f32; 4], [f32; 16], [i32; 2])>(100)
+                .into_iter()
+                .map(|(v, q, m, i)| {
+                    (Vec3::from(v), Quat::from_array(q), Mat4::from_cols_array(&m), IVec2::from(i))
+                })
+                .collect();
+        let encoded = encode(&transforms);
+        assert_eq!(decode::<Vec<Transform>>(&encoded).unwrap(), transforms);
+
+        // Encoded like a struct of its components.
+        let v = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(encode(&v), encode(&(1.0f32, 2.0f32, 3.0f32, 4.0f32)));
+        let affine = Affine2::from_mat2_translation(Mat2::IDENTITY, Vec2::X);
+        assert_eq!(decode::<Affine2>(&encode(&affine)).unwrap(), affine);
+    }
+}
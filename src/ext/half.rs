@@ -0,0 +1,111 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::consume_byte_arrays;
+use crate::derive::{Decode, Encode};
+use crate::f32::normalize_floats;
+use crate::fast::{NextUnchecked, PushUnchecked, SliceImpl, VecImpl};
+use crate::options::{encode_options, EncodeOptions};
+use half::{bf16, f16};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+// Half floats are copied as their 2 little endian bytes, so Vec<f16> is encoded/decoded with a
+// single memcpy (on little endian targets).
+#[derive(Debug, Default)]
+pub struct HalfEncoder<T>(VecImpl<T>);
+
+#[derive(Debug, Default)]
+pub struct HalfDecoder<'a, T>(SliceImpl<'a, [u8; 2]>, PhantomData<T>);
+
+impl<'a, T> View<'a> for HalfDecoder<'a, T> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0 = consume_byte_arrays(input, length)?.into();
+        Ok(())
+    }
+}
+
+macro_rules! impl_half {
+    ($($t:ident),+) => {
+        $(
+            impl Encode for $t {
+                type Encoder = HalfEncoder<$t>;
+            }
+            impl<'a> Decode<'a> for $t {
+                type Decoder = HalfDecoder<'a, $t>;
+            }
+
+            impl Encoder<$t> for HalfEncoder<$t> {
+                #[inline(always)]
+                fn as_primitive(&mut self) -> Option<&mut VecImpl<$t>> {
+                    Some(&mut self.0)
+                }
+
+                #[inline(always)]
+                fn encode(&mut self, t: &$t) {
+                    unsafe { self.0.push_unchecked(*t) };
+                }
+            }
+
+            impl EncoderBuffer for HalfEncoder<$t> {
+                fn collect_into(&mut self, out: &mut Vec<u8>) {
+                    normalize_floats!(self.0.as_mut_slice(), f32, $t::to_f32, $t::from_f32);
+                    if cfg!(target_endian = "little") {
+                        out.extend_from_slice(bytemuck::must_cast_slice(self.0.as_slice()));
+                    } else {
+                        out.extend(self.0.as_slice().iter().flat_map(|v| v.to_le_bytes()));
+                    }
+                    self.0.clear();
+                }
+
+                fn reserve(&mut self, additional: NonZeroUsize) {
+                    self.0.reserve(additional.get());
+                }
+            }
+
+            impl<'a> Decoder<'a, $t> for HalfDecoder<'a, $t> {
+                #[inline(always)]
+                fn as_primitive_ptr(&self) -> Option<*const u8> {
+                    cfg!(target_endian = "little").then(|| self.0.as_ptr() as *const u8)
+                }
+
+                #[inline(always)]
+                unsafe fn as_primitive_advance(&mut self, n: usize) {
+                    self.0.advance(n);
+                }
+
+                #[inline(always)]
+                fn decode(&mut self) -> $t {
+                    $t::from_le_bytes(unsafe { self.0.next_unchecked() })
+                }
+            }
+        )+
+    };
+}
+impl_half!(f16, bf16);
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, encode_with_options, EncodeOptions};
+    use half::{bf16, f16};
+
+    #[test]
+    fn half() {
+        let v: Vec<f16> = (0..100)
+            .map(|i| f16::from_f32(i as f32 * 0.25 - 10.0))
+            .collect();
+        let encoded = encode(&v);
+        let bytes: Vec<u8> = v.iter().flat_map(|h| h.to_le_bytes()).collect();
+        assert!(encoded.ends_with(&bytes));
+        assert_eq!(decode::<Vec<f16>>(&encoded).unwrap(), v);
+
+        let v = [bf16::MIN, bf16::ONE, bf16::NEG_INFINITY];
+        assert_eq!(decode::<[bf16; 3]>(&encode(&v)).unwrap(), v);
+        assert!(decode::<f16>(&[0]).is_err());
+
+        let options = EncodeOptions::new()
+            .canonicalize_nan(true)
+            .normalize_negative_zero(true);
+        let weird_nan = f16::from_bits(f16::NAN.to_bits() | 1);
+        let encoded = encode_with_options(&[weird_nan, f16::NEG_ZERO], &options);
+        assert_eq!(encoded, encode(&[f16::NAN, f16::ZERO]));
+    }
+}
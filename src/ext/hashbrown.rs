@@ -0,0 +1,104 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer};
+use crate::derive::map::{MapDecoder, MapEncoder};
+use crate::derive::vec::{VecDecoder, VecEncoder};
+use crate::derive::{Decode, Encode};
+use hashbrown::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+use std::num::NonZeroUsize;
+
+// Same as the std::collections::{HashMap, HashSet} impls in map.rs and vec.rs.
+impl<K: Encode, V: Encode, S> Encoder<HashMap<K, V, S>> for MapEncoder<K, V> {
+    #[inline(always)]
+    fn encode(&mut self, map: &HashMap<K, V, S>) {
+        let n = map.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.keys.reserve(n);
+            self.values.reserve(n);
+            for (k, v) in map {
+                self.keys.encode(k);
+                self.values.encode(v);
+            }
+        }
+    }
+}
+impl<'a, K: Decode<'a> + Eq + Hash, V: Decode<'a>, S: BuildHasher + Default>
+    Decoder<'a, HashMap<K, V, S>> for MapDecoder<'a, K, V>
+{
+    #[inline(always)]
+    fn decode(&mut self) -> HashMap<K, V, S> {
+        (0..self.lengths.decode())
+            .map(|_| (self.keys.decode(), self.values.decode()))
+            .collect()
+    }
+}
+impl<K: Encode, V: Encode, S> Encode for HashMap<K, V, S> {
+    type Encoder = MapEncoder<K, V>;
+}
+impl<'a, K: Decode<'a> + Eq + Hash, V: Decode<'a>, S: BuildHasher + Default> Decode<'a>
+    for HashMap<K, V, S>
+{
+    type Decoder = MapDecoder<'a, K, V>;
+}
+
+impl<T: Encode, S> Encoder<HashSet<T, S>> for VecEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, set: &HashSet<T, S>) {
+        let n = set.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.elements.reserve(n);
+            // Internal iteration is faster (see the std::collections::HashSet impl).
+            set.iter().for_each(|v| self.elements.encode(v));
+        }
+    }
+}
+impl<'a, T: Decode<'a> + Eq + Hash, S: BuildHasher + Default> Decoder<'a, HashSet<T, S>>
+    for VecDecoder<'a, T>
+{
+    #[inline(always)]
+    fn decode(&mut self) -> HashSet<T, S> {
+        (0..self.lengths.decode())
+            .map(|_| self.elements.decode())
+            .collect()
+    }
+}
+impl<T: Encode, S> Encode for HashSet<T, S> {
+    type Encoder = VecEncoder<T>;
+}
+impl<'a, T: Decode<'a> + Eq + Hash, S: BuildHasher + Default> Decode<'a> for HashSet<T, S> {
+    type Decoder = VecDecoder<'a, T>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use std::collections::hash_map::RandomState;
+
+    type HashMap<K, V> = hashbrown::HashMap<K, V, RandomState>;
+    type HashSet<T> = hashbrown::HashSet<T, RandomState>;
+
+    #[test]
+    fn hashbrown() {
+        let map: HashMap<u32, String> = crate::random_data::<u32>(100)
+            .into_iter()
+            .map(|k| (k, k.to_string()))
+            .collect();
+        let encoded = encode(&map);
+        assert_eq!(decode::<HashMap<u32, String>>(&encoded).unwrap(), map);
+        let std_map: std::collections::HashMap<u32, String> = decode(&encoded).unwrap();
+        assert_eq!(std_map.len(), map.len());
+        assert!(map.iter().all(|(k, v)| std_map.get(k) == Some(v)));
+
+        let set: HashSet<u16> = crate::random_data::<u16>(100).into_iter().collect();
+        let encoded = encode(&set);
+        assert_eq!(decode::<HashSet<u16>>(&encoded).unwrap(), set);
+        let v: Vec<u16> = decode(&encoded).unwrap();
+        assert!(v.iter().eq(&set));
+    }
+
+    fn bench_data<T: FromIterator<(u8, u8)>>() -> T {
+        (0..=255).map(|k| (k, 0)).collect()
+    }
+    crate::bench_encode_decode!(hash_map: HashMap<_, _>);
+}
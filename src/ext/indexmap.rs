@@ -0,0 +1,112 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer};
+use crate::derive::map::{MapDecoder, MapEncoder};
+use crate::derive::vec::{VecDecoder, VecEncoder};
+use crate::derive::{Decode, Encode};
+use indexmap::{IndexMap, IndexSet};
+use std::hash::{BuildHasher, Hash};
+use std::num::NonZeroUsize;
+
+// Encoded like HashMap/HashSet but in insertion order, so decoding preserves the order.
+impl<K: Encode, V: Encode, S> Encoder<IndexMap<K, V, S>> for MapEncoder<K, V> {
+    #[inline(always)]
+    fn encode(&mut self, map: &IndexMap<K, V, S>) {
+        let n = map.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.keys.reserve(n);
+            self.values.reserve(n);
+            for (k, v) in map {
+                self.keys.encode(k);
+                self.values.encode(v);
+            }
+        }
+    }
+}
+impl<'a, K: Decode<'a> + Eq + Hash, V: Decode<'a>, S: BuildHasher + Default>
+    Decoder<'a, IndexMap<K, V, S>> for MapDecoder<'a, K, V>
+{
+    #[inline(always)]
+    fn decode(&mut self) -> IndexMap<K, V, S> {
+        (0..self.lengths.decode())
+            .map(|_| (self.keys.decode(), self.values.decode()))
+            .collect()
+    }
+}
+impl<K: Encode, V: Encode, S> Encode for IndexMap<K, V, S> {
+    type Encoder = MapEncoder<K, V>;
+}
+impl<'a, K: Decode<'a> + Eq + Hash, V: Decode<'a>, S: BuildHasher + Default> Decode<'a>
+    for IndexMap<K, V, S>
+{
+    type Decoder = MapDecoder<'a, K, V>;
+}
+
+impl<T: Encode, S> Encoder<IndexSet<T, S>> for VecEncoder<T> {
+    #[inline(always)]
+    fn encode(&mut self, set: &IndexSet<T, S>) {
+        let n = set.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.elements.reserve(n);
+            for v in set {
+                self.elements.encode(v);
+            }
+        }
+    }
+}
+impl<'a, T: Decode<'a> + Eq + Hash, S: BuildHasher + Default> Decoder<'a, IndexSet<T, S>>
+    for VecDecoder<'a, T>
+{
+    #[inline(always)]
+    fn decode(&mut self) -> IndexSet<T, S> {
+        (0..self.lengths.decode())
+            .map(|_| self.elements.decode())
+            .collect()
+    }
+}
+impl<T: Encode, S> Encode for IndexSet<T, S> {
+    type Encoder = VecEncoder<T>;
+}
+impl<'a, T: Decode<'a> + Eq + Hash, S: BuildHasher + Default> Decode<'a> for IndexSet<T, S> {
+    type Decoder = VecDecoder<'a, T>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use indexmap::{IndexMap, IndexSet};
+
+    #[test]
+    fn index_map() {
+        let map: IndexMap<u32, String> = crate::random_data::<u32>(100)
+            .into_iter()
+            .map(|k| (k, k.to_string()))
+            .collect();
+        let encoded = encode(&map);
+        let decoded: IndexMap<u32, String> = decode(&encoded).unwrap();
+        assert!(decoded.iter().eq(map.iter()));
+
+        // Maps are encoded like a Vec<(K, V)> of their entries.
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+        assert_eq!(encoded, encode(&entries));
+        assert_eq!(decode::<Vec<(u32, String)>>(&encoded).unwrap(), entries);
+    }
+
+    #[test]
+    fn index_set() {
+        let set: IndexSet<u16> = crate::random_data::<u16>(100).into_iter().collect();
+        let encoded = encode(&set);
+        assert_eq!(encoded, encode(&set.iter().copied().collect::<Vec<_>>()));
+        let decoded: IndexSet<u16> = decode(&encoded).unwrap();
+        assert!(decoded.iter().eq(set.iter()));
+        assert_eq!(
+            decode::<Vec<u16>>(&encoded).unwrap(),
+            set.iter().copied().collect::<Vec<_>>()
+        );
+    }
+
+    fn bench_data<T: FromIterator<(u8, u8)>>() -> T {
+        (0..=255).map(|k| (k, 0)).collect()
+    }
+    crate::bench_encode_decode!(index_map: IndexMap<_, _>);
+}
@@ -1,8 +1,36 @@
 #[cfg(feature = "arrayvec")]
 mod arrayvec;
+#[cfg(feature = "bitvec")]
+mod bitvec;
+#[cfg(feature = "bytes")]
+pub(crate) mod bytes;
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "compact_str")]
+mod compact_str;
+#[cfg(feature = "enumset")]
+mod enumset;
 #[cfg(feature = "glam")]
 #[rustfmt::skip] // Makes impl_struct! calls way longer.
 mod glam;
+#[cfg(feature = "half")]
+mod half;
+#[cfg(feature = "hashbrown")]
+mod hashbrown;
+#[cfg(feature = "indexmap")]
+mod indexmap;
+#[cfg(feature = "roaring")]
+mod roaring;
+#[cfg(feature = "rust_decimal")]
+mod rust_decimal;
+#[cfg(feature = "slotmap")]
+mod slotmap;
+#[cfg(feature = "smol_str")]
+mod smol_str;
+#[cfg(feature = "time")]
+mod time;
+#[cfg(feature = "uuid")]
+mod uuid;
 
 #[allow(unused)]
 macro_rules! impl_struct {
@@ -22,13 +50,23 @@ macro_rules! impl_struct {
                     )+
                 }
             }
-            impl crate::coder::Buffer for StructEncoder {
+            impl crate::coder::EncoderBuffer for StructEncoder {
                 fn collect_into(&mut self, out: &mut Vec<u8>) {
                     $(
                         self.$f.collect_into(out);
                     )+
                 }
 
+                fn collect_into_flushing(
+                    &mut self,
+                    out: &mut Vec<u8>,
+                    flush: &mut dyn FnMut(&mut Vec<u8>),
+                ) {
+                    $(
+                        self.$f.collect_into_flushing(out, flush);
+                    )+
+                }
+
                 fn reserve(&mut self, additional: std::num::NonZeroUsize) {
                     $(
                         self.$f.reserve(additional);
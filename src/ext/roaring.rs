@@ -0,0 +1,136 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::consume_bytes;
+use crate::derive::vec::VecEncoder;
+use crate::derive::{Decode, Encode};
+use crate::error::err;
+use crate::length::LengthDecoder;
+use crate::u8_char::U8Char;
+use roaring::{RoaringBitmap, RoaringTreemap};
+use std::num::NonZeroUsize;
+
+/// Encodes bitmaps as the bytes of their portable serialization (like a `&[u8]`), so large sets
+/// aren't expanded into their integers.
+#[derive(Default)]
+pub struct RoaringEncoder {
+    bytes: VecEncoder<U8Char>,
+    serialized: Vec<u8>,
+}
+
+impl EncoderBuffer for RoaringEncoder {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        self.bytes.collect_into(out);
+    }
+
+    fn collect_into_flushing(&mut self, out: &mut Vec<u8>, flush: &mut dyn FnMut(&mut Vec<u8>)) {
+        self.bytes.collect_into_flushing(out, flush);
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.bytes.reserve(additional);
+    }
+}
+
+/// Bitmaps are validated by deserializing them in populate, so they're stored until decoded.
+pub struct RoaringDecoder<T> {
+    // Reversed so decode can pop them.
+    decoded: Vec<T>,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T> Default for RoaringDecoder<T> {
+    fn default() -> Self {
+        Self { decoded: vec![] }
+    }
+}
+
+macro_rules! impl_roaring {
+    ($($t:ty),+) => {
+        $(
+            impl Encoder<$t> for RoaringEncoder {
+                #[inline(always)]
+                fn encode(&mut self, t: &$t) {
+                    self.serialized.clear();
+                    t.serialize_into(&mut self.serialized).unwrap();
+                    self.bytes.encode(bytemuck::must_cast_slice(&self.serialized));
+                }
+            }
+
+            impl Encode for $t {
+                type Encoder = RoaringEncoder;
+            }
+
+            impl<'a> View<'a> for RoaringDecoder<$t> {
+                fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+                    let mut lengths = LengthDecoder::default();
+                    lengths.populate(input, length)?;
+                    let mut bytes = consume_bytes(input, lengths.length())?;
+
+                    self.decoded.clear();
+                    self.decoded.reserve(length);
+                    for _ in 0..length {
+                        let mut serialized = consume_bytes(&mut bytes, lengths.decode())?;
+                        let Ok(bitmap) = <$t>::deserialize_from(&mut serialized) else {
+                            return err("invalid bitmap");
+                        };
+                        if !serialized.is_empty() {
+                            return err("invalid bitmap");
+                        }
+                        self.decoded.push(bitmap);
+                    }
+                    self.decoded.reverse();
+                    Ok(())
+                }
+            }
+
+            impl<'a> Decoder<'a, $t> for RoaringDecoder<$t> {
+                #[inline(always)]
+                fn decode(&mut self) -> $t {
+                    // Safety: populate decoded `length` bitmaps.
+                    unsafe { self.decoded.pop().unwrap_unchecked() }
+                }
+            }
+
+            impl<'a> Decode<'a> for $t {
+                type Decoder = RoaringDecoder<$t>;
+            }
+        )+
+    };
+}
+impl_roaring!(RoaringBitmap, RoaringTreemap);
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use roaring::{RoaringBitmap, RoaringTreemap};
+
+    #[test]
+    fn roaring_bitmap() {
+        let bitmaps: Vec<RoaringBitmap> = vec![
+            RoaringBitmap::new(),
+            (0..1_000_000).step_by(3).collect(),
+            [1, 5, u32::MAX].into_iter().collect(),
+        ];
+        let encoded = encode(&bitmaps);
+        assert!(encoded.len() < 300_000);
+        assert_eq!(decode::<Vec<RoaringBitmap>>(&encoded).unwrap(), bitmaps);
+
+        let treemap: RoaringTreemap = [0, u32::MAX as u64 + 1, u64::MAX].into_iter().collect();
+        assert_eq!(
+            decode::<RoaringTreemap>(&encode(&treemap)).unwrap(),
+            treemap
+        );
+
+        // Encoded like a string of the serialization (which is ASCII for an empty bitmap).
+        let mut serialized = vec![];
+        RoaringBitmap::new()
+            .serialize_into(&mut serialized)
+            .unwrap();
+        let mut serialized = String::from_utf8(serialized).unwrap();
+        assert_eq!(encode(&RoaringBitmap::new()), encode(&serialized));
+
+        // Invalid serializations are rejected.
+        assert!(decode::<RoaringBitmap>(&encode("abc")).is_err());
+        serialized.push('\0');
+        assert!(decode::<RoaringBitmap>(&encode(&serialized)).is_err());
+    }
+}
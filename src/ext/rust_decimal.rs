@@ -0,0 +1,36 @@
+use crate::derive::convert::impl_convert;
+use rust_decimal::Decimal;
+
+// The mantissa and scale are separate columns, so the scale (usually the same for every value)
+// packs to almost nothing and the mantissa is packed like any other integer. -0 is decoded as 0
+// (which it's equal to).
+impl_convert!(checked Decimal => (i128, u32), |s| (s.mantissa(), s.scale()), |r| {
+    Decimal::try_from_i128_with_scale(r.0, r.1).ok()
+});
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn decimal() {
+        let v: Vec<Decimal> = [
+            Decimal::ZERO,
+            Decimal::new(-12345, 2),
+            Decimal::new(1, 28),
+            Decimal::MAX,
+            Decimal::MIN,
+        ]
+        .into();
+        let encoded = encode(&v);
+        assert_eq!(decode::<Vec<Decimal>>(&encoded).unwrap(), v);
+
+        // Much smaller than the 16 bytes of Decimal::serialize.
+        let prices: Vec<Decimal> = (0..1000).map(|i| Decimal::new(i * 25, 2)).collect();
+        assert!(encode(&prices).len() < prices.len() * 4);
+
+        assert!(decode::<Decimal>(&encode(&(1i128, 29u32))).is_err());
+        assert!(decode::<Decimal>(&encode(&(i128::MAX, 0u32))).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer};
+use crate::derive::convert::impl_convert;
+use crate::derive::map::{MapDecoder, MapEncoder};
+use crate::derive::{Decode, Encode};
+use slotmap::{DefaultKey, Key, KeyData, SecondaryMap, SparseSecondaryMap};
+use std::hash::BuildHasher;
+use std::num::NonZeroUsize;
+
+// Encoded as (index, version) instead of a u64 so both halves can be packed.
+impl_convert!(KeyData => (u32, u32), |s| {
+    let ffi = s.as_ffi();
+    (ffi as u32, (ffi >> 32) as u32)
+}, |r| KeyData::from_ffi(r.0 as u64 | (r.1 as u64) << 32));
+impl_convert!(DefaultKey => KeyData, |s| s.data(), |r| r.into());
+
+// SlotMap isn't implemented since its public API can't recreate keys with specific versions.
+
+impl<K: Key + Encode, V: Encode> Encoder<SecondaryMap<K, V>> for MapEncoder<K, V> {
+    #[inline(always)]
+    fn encode(&mut self, map: &SecondaryMap<K, V>) {
+        let n = map.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.keys.reserve(n);
+            self.values.reserve(n);
+            for (k, v) in map {
+                self.keys.encode(&k);
+                self.values.encode(v);
+            }
+        }
+    }
+}
+impl<'a, K: Key + Decode<'a>, V: Decode<'a>> Decoder<'a, SecondaryMap<K, V>>
+    for MapDecoder<'a, K, V>
+{
+    #[inline(always)]
+    fn decode(&mut self) -> SecondaryMap<K, V> {
+        (0..self.lengths.decode())
+            .map(|_| (self.keys.decode(), self.values.decode()))
+            .collect()
+    }
+}
+impl<K: Key + Encode, V: Encode> Encode for SecondaryMap<K, V> {
+    type Encoder = MapEncoder<K, V>;
+}
+impl<'a, K: Key + Decode<'a>, V: Decode<'a>> Decode<'a> for SecondaryMap<K, V> {
+    type Decoder = MapDecoder<'a, K, V>;
+}
+
+impl<K: Key + Encode, V: Encode, S: BuildHasher> Encoder<SparseSecondaryMap<K, V, S>>
+    for MapEncoder<K, V>
+{
+    #[inline(always)]
+    fn encode(&mut self, map: &SparseSecondaryMap<K, V, S>) {
+        let n = map.len();
+        self.lengths.encode(&n);
+        if let Some(n) = NonZeroUsize::new(n) {
+            self.keys.reserve(n);
+            self.values.reserve(n);
+            for (k, v) in map {
+                self.keys.encode(&k);
+                self.values.encode(v);
+            }
+        }
+    }
+}
+impl<'a, K: Key + Decode<'a>, V: Decode<'a>, S: BuildHasher + Default>
+    Decoder<'a, SparseSecondaryMap<K, V, S>> for MapDecoder<'a, K, V>
+{
+    #[inline(always)]
+    fn decode(&mut self) -> SparseSecondaryMap<K, V, S> {
+        (0..self.lengths.decode())
+            .map(|_| (self.keys.decode(), self.values.decode()))
+            .collect()
+    }
+}
+impl<K: Key + Encode, V: Encode, S: BuildHasher> Encode for SparseSecondaryMap<K, V, S> {
+    type Encoder = MapEncoder<K, V>;
+}
+impl<'a, K: Key + Decode<'a>, V: Decode<'a>, S: BuildHasher + Default> Decode<'a>
+    for SparseSecondaryMap<K, V, S>
+{
+    type Decoder = MapDecoder<'a, K, V>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use slotmap::{DefaultKey, Key, SecondaryMap, SlotMap, SparseSecondaryMap};
+
+    slotmap::new_key_type! {
+        #[derive(crate::Encode, crate::Decode)]
+        struct EntityKey;
+    }
+
+    #[test]
+    fn slotmap() {
+        let mut entities = SlotMap::<EntityKey, u32>::with_key();
+        let keys: Vec<EntityKey> = (0..100).map(|i| entities.insert(i)).collect();
+        for &k in keys.iter().step_by(3) {
+            entities.remove(k);
+        }
+        let keys: Vec<EntityKey> = (0..50).map(|i| entities.insert(i)).collect();
+        let encoded = encode(&keys);
+        assert_eq!(decode::<Vec<EntityKey>>(&encoded).unwrap(), keys);
+        assert_eq!(decode::<Vec<DefaultKey>>(&encoded).unwrap(), {
+            keys.iter()
+                .map(|k| k.data().into())
+                .collect::<Vec<DefaultKey>>()
+        });
+
+        let names: SecondaryMap<EntityKey, String> =
+            entities.iter().map(|(k, v)| (k, v.to_string())).collect();
+        let decoded: SecondaryMap<EntityKey, String> = decode(&encode(&names)).unwrap();
+        assert_eq!(decoded, names);
+        assert!(decoded.iter().all(|(k, v)| entities[k].to_string() == *v));
+
+        let sparse: SparseSecondaryMap<EntityKey, u32> =
+            keys.iter().map(|&k| (k, entities[k])).collect();
+        let decoded: SparseSecondaryMap<EntityKey, u32> = decode(&encode(&sparse)).unwrap();
+        assert_eq!(decoded, sparse);
+    }
+}
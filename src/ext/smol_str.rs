@@ -0,0 +1,53 @@
+use crate::coder::{Decoder, Encoder};
+use crate::derive::{Decode, Encode};
+use crate::str::{StrDecoder, StrEncoder};
+use smol_str::SmolStr;
+
+impl Encoder<SmolStr> for StrEncoder {
+    #[inline(always)]
+    fn encode(&mut self, t: &SmolStr) {
+        self.encode(t.as_str());
+    }
+
+    #[inline(always)]
+    fn encode_vectored<'a>(&mut self, i: impl Iterator<Item = &'a SmolStr> + Clone) {
+        self.encode_vectored(i.map(SmolStr::as_str));
+    }
+}
+impl Encode for SmolStr {
+    type Encoder = StrEncoder;
+}
+
+impl<'a> Decoder<'a, SmolStr> for StrDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> SmolStr {
+        // Short strings are stored inline without allocating a String first.
+        let s: &str = self.decode();
+        SmolStr::new(s)
+    }
+}
+impl<'a> Decode<'a> for SmolStr {
+    type Decoder = StrDecoder<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use smol_str::SmolStr;
+
+    #[test]
+    fn smol_str() {
+        let v: Vec<SmolStr> = (0..100).map(|i| SmolStr::new("a".repeat(i))).collect();
+        let encoded = encode(&v);
+        assert_eq!(
+            encoded,
+            encode(&v.iter().map(SmolStr::as_str).collect::<Vec<_>>())
+        );
+        assert_eq!(decode::<Vec<SmolStr>>(&encoded).unwrap(), v);
+
+        // Invalid UTF-8 is rejected.
+        let mut encoded = encode("a");
+        *encoded.last_mut().unwrap() = 0xFF;
+        assert!(decode::<SmolStr>(&encoded).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::derive::convert::impl_convert;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+// Each component is its own column (like a struct's fields) so similar dates/times pack well.
+impl_convert!(checked Date => i32, |s| s.to_julian_day(), |r| Date::from_julian_day(r).ok());
+impl_convert!(checked Time => (u8, u8, u8, u32), |s| s.as_hms_nano(), |r| {
+    Time::from_hms_nano(r.0, r.1, r.2, r.3).ok()
+});
+impl_convert!(PrimitiveDateTime => (Date, Time), |s| (s.date(), s.time()), |r| {
+    PrimitiveDateTime::new(r.0, r.1)
+});
+impl_convert!(checked UtcOffset => i32, |s| s.whole_seconds(), |r| {
+    UtcOffset::from_whole_seconds(r).ok()
+});
+// The date and time are in the offset (not UTC) so they're preserved exactly.
+impl_convert!(OffsetDateTime => (PrimitiveDateTime, UtcOffset), |s| {
+    (PrimitiveDateTime::new(s.date(), s.time()), s.offset())
+}, |r| r.0.assume_offset(r.1));
+// Seconds and nanoseconds have the same sign and nanoseconds are < 1 second.
+impl_convert!(checked Duration => (i64, i32), |s| (s.whole_seconds(), s.subsec_nanoseconds()), |r| {
+    let (secs, nanos) = r;
+    let same_sign = secs == 0 || nanos == 0 || (secs < 0) == (nanos < 0);
+    let valid = same_sign && nanos.unsigned_abs() < 1_000_000_000;
+    valid.then(|| Duration::new(secs, nanos))
+});
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+    #[test]
+    fn time() {
+        let date = Date::from_calendar_date(2024, Month::February, 29).unwrap();
+        let time = Time::from_hms_nano(23, 59, 59, 999_999_999).unwrap();
+        let date_time = PrimitiveDateTime::new(date, time);
+        let offset = UtcOffset::from_hms(-5, -30, 0).unwrap();
+        let v = vec![
+            date_time.assume_offset(offset),
+            date_time.assume_utc(),
+            PrimitiveDateTime::MIN.assume_offset(UtcOffset::from_hms(25, 59, 59).unwrap()),
+            PrimitiveDateTime::MAX.assume_offset(UtcOffset::from_hms(-25, -59, -59).unwrap()),
+        ];
+        let decoded = decode::<Vec<OffsetDateTime>>(&encode(&v)).unwrap();
+        assert_eq!(decoded, v);
+        assert_eq!(decoded[0].offset(), offset); // OffsetDateTime's PartialEq ignores the offset.
+        assert_eq!(decode::<Date>(&encode(&Date::MIN)).unwrap(), Date::MIN);
+
+        assert!(decode::<Date>(&encode(&i32::MAX)).is_err());
+        assert!(decode::<Time>(&encode(&(24u8, 0u8, 0u8, 0u32))).is_err());
+        assert!(decode::<UtcOffset>(&encode(&(26 * 3600i32))).is_err());
+    }
+
+    #[test]
+    fn duration() {
+        let v = [
+            Duration::ZERO,
+            Duration::new(-5, -1),
+            Duration::MIN,
+            Duration::MAX,
+        ];
+        assert_eq!(decode::<[Duration; 4]>(&encode(&v)).unwrap(), v);
+        assert_eq!(encode(&Duration::new(1, 5)), encode(&(1i64, 5i32)));
+
+        assert!(decode::<Duration>(&encode(&(1i64, -1i32))).is_err());
+        assert!(decode::<Duration>(&encode(&(0i64, 1_000_000_000i32))).is_err());
+        assert!(decode::<Duration>(&encode(&(0i64, -1i32))).is_ok());
+    }
+}
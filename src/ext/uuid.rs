@@ -0,0 +1,88 @@
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::consume::consume_byte_arrays;
+use crate::derive::{Decode, Encode};
+use crate::fast::{NextUnchecked, PushUnchecked, SliceImpl, VecImpl};
+use std::num::NonZeroUsize;
+use uuid::Uuid;
+
+// Uuids are random so packing them like [u8; 16] would only waste time. Instead they're copied as
+// is, which lets Vec<Uuid> be encoded/decoded with a single memcpy.
+impl Encode for Uuid {
+    type Encoder = UuidEncoder;
+}
+impl<'a> Decode<'a> for Uuid {
+    type Decoder = UuidDecoder<'a>;
+}
+
+#[derive(Debug, Default)]
+pub struct UuidEncoder(VecImpl<Uuid>);
+
+impl Encoder<Uuid> for UuidEncoder {
+    #[inline(always)]
+    fn as_primitive(&mut self) -> Option<&mut VecImpl<Uuid>> {
+        Some(&mut self.0)
+    }
+
+    #[inline(always)]
+    fn encode(&mut self, &v: &Uuid) {
+        unsafe { self.0.push_unchecked(v) }
+    }
+}
+
+impl EncoderBuffer for UuidEncoder {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        out.extend_from_slice(bytemuck::must_cast_slice(self.0.as_slice()));
+        self.0.clear();
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional.get());
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UuidDecoder<'a>(SliceImpl<'a, [u8; 16]>);
+
+impl<'a> View<'a> for UuidDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.0 = consume_byte_arrays(input, length)?.into();
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, Uuid> for UuidDecoder<'a> {
+    #[inline(always)]
+    fn as_primitive_ptr(&self) -> Option<*const u8> {
+        Some(self.0.as_ptr() as *const u8)
+    }
+
+    #[inline(always)]
+    unsafe fn as_primitive_advance(&mut self, n: usize) {
+        self.0.advance(n);
+    }
+
+    #[inline(always)]
+    fn decode(&mut self) -> Uuid {
+        Uuid::from_bytes(unsafe { self.0.next_unchecked() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use uuid::Uuid;
+
+    #[test]
+    fn uuid() {
+        let v: Vec<Uuid> = crate::random_data::<u128>(100)
+            .into_iter()
+            .map(Uuid::from_u128)
+            .collect();
+        let encoded = encode(&v);
+        let bytes: Vec<u8> = v.iter().flat_map(|u| *u.as_bytes()).collect();
+        assert!(encoded.ends_with(&bytes));
+        assert_eq!(decode::<Vec<Uuid>>(&encoded).unwrap(), v);
+        assert_eq!(decode::<Uuid>(&encode(&v[0])).unwrap(), v[0]);
+        assert!(decode::<Uuid>(&encode(&v[0])[..15]).is_err());
+    }
+}
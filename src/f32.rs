@@ -1,6 +1,8 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::consume::consume_byte_arrays;
 use crate::fast::{FastSlice, NextUnchecked, PushUnchecked, VecImpl};
+use crate::options::{encode_options, EncodeOptions};
+use crate::pack_ints::pack_ints;
 use std::mem::MaybeUninit;
 use std::num::NonZeroUsize;
 
@@ -27,8 +29,33 @@ fn chunks_uninit<A, B>(m: &mut [MaybeUninit<A>]) -> &mut [MaybeUninit<B>] {
     }
 }
 
-impl Buffer for F32Encoder {
+/// Applies the float options of [`EncodeOptions`] to `floats` in place.
+macro_rules! normalize_floats {
+    ($floats:expr, $t:ty, $from_bits:expr, $into:expr) => {{
+        let options = encode_options();
+        if !options.floats_unchanged() {
+            let EncodeOptions {
+                canonicalize_nan,
+                normalize_negative_zero,
+                ..
+            } = options;
+            for v in $floats {
+                let f: $t = $from_bits(*v);
+                if canonicalize_nan && f.is_nan() {
+                    *v = $into(<$t>::NAN);
+                } else if normalize_negative_zero && f == 0.0 {
+                    *v = $into(0.0); // -0.0 == 0.0
+                }
+            }
+        }
+    }};
+}
+#[cfg(feature = "half")]
+pub(crate) use normalize_floats; // For half.rs.
+
+impl EncoderBuffer for F32Encoder {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
+        normalize_floats!(self.0.as_mut_slice(), f32, |f| f, |f| f);
         let floats = self.0.as_slice();
         let byte_len = std::mem::size_of_val(floats);
         out.reserve(byte_len);
@@ -75,6 +102,41 @@ impl Buffer for F32Encoder {
     }
 }
 
+/// Encodes `f64`s exactly like `IntEncoder<u64>` (which is also its decoder's encoder). It only
+/// exists so [`EncodeOptions`] can tell `f64`s apart from `u64`s.
+/// TODO F64Encoder that splits mantissa like [`F32Encoder`] (once it's sufficiently optimized).
+#[derive(Debug, Default)]
+pub struct F64Encoder(VecImpl<u64>);
+
+impl Encoder<f64> for F64Encoder {
+    #[inline(always)]
+    fn as_primitive(&mut self) -> Option<&mut VecImpl<f64>> {
+        // Safety: u64 and f64 have the same size/align and all bit patterns are valid for both.
+        unsafe {
+            Some(std::mem::transmute::<&mut VecImpl<u64>, &mut VecImpl<f64>>(
+                &mut self.0,
+            ))
+        }
+    }
+
+    #[inline(always)]
+    fn encode(&mut self, t: &f64) {
+        unsafe { self.0.push_unchecked(t.to_bits()) };
+    }
+}
+
+impl EncoderBuffer for F64Encoder {
+    fn collect_into(&mut self, out: &mut Vec<u8>) {
+        normalize_floats!(self.0.as_mut_slice(), f64, f64::from_bits, f64::to_bits);
+        pack_ints(self.0.as_mut_slice(), out);
+        self.0.clear();
+    }
+
+    fn reserve(&mut self, additional: NonZeroUsize) {
+        self.0.reserve(additional.get());
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct F32Decoder<'a> {
     // While it is true that this contains 1 bit of the exp we still call it mantissa.
@@ -139,6 +201,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encode_options() {
+        use crate::{decode, encode, encode_with_options, EncodeOptions};
+        let weird_nan = f32::from_bits(f32::NAN.to_bits() | 1);
+        let weird_nan_f64 = f64::from_bits(f64::NAN.to_bits() | 1);
+        let v = (vec![-weird_nan, -0.0, 1.5], vec![-weird_nan_f64, -0.0, 1.5]);
+
+        // Default options preserve bits (and f64 still encodes like u64).
+        let o = EncodeOptions::new();
+        assert_eq!(encode_with_options(&v, &o), encode(&v));
+        let bits: Vec<u64> = v.1.iter().map(|f| f.to_bits()).collect();
+        assert_eq!(encode(&v.1), encode(&bits));
+
+        let nan = EncodeOptions::new().canonicalize_nan(true);
+        let (a, b): (Vec<f32>, Vec<f64>) = decode(&encode_with_options(&v, &nan)).unwrap();
+        assert_eq!(a[0].to_bits(), f32::NAN.to_bits());
+        assert_eq!(b[0].to_bits(), f64::NAN.to_bits());
+        assert!(a[1].is_sign_negative() && b[1].is_sign_negative());
+
+        let zero = nan.normalize_negative_zero(true);
+        let (a, b): (Vec<f32>, Vec<f64>) = decode(&encode_with_options(&v, &zero)).unwrap();
+        assert_eq!(a[1].to_bits(), 0);
+        assert_eq!(b[1].to_bits(), 0);
+        assert_eq!((a[2], b[2]), (1.5, 1.5));
+
+        // Options don't leak into later calls.
+        assert_eq!(encode(&v), encode_with_options(&v, &o));
+    }
+
     fn bench_data() -> Vec<f32> {
         let mut rng = ChaCha20Rng::from_seed(Default::default());
         (0..crate::limit_bench_miri(1500001))
@@ -363,6 +363,19 @@ impl<'borrowed, T> CowSlice<'borrowed, T> {
         std::slice::from_raw_parts(self.slice.ptr, len)
     }
 
+    /// Like [`Self::as_slice`], but returns `None` if `self` is owned so the slice can have the
+    /// `'borrowed` lifetime.
+    /// Safety: `len` must be equal to the slices original len.
+    #[must_use]
+    pub unsafe fn as_borrowed_slice(&self, len: usize) -> Option<&'borrowed [T]> {
+        if std::ptr::eq(self.slice.ptr, self.vec.as_ptr()) {
+            return None;
+        }
+        #[cfg(debug_assertions)]
+        assert_eq!(self.slice.len, len);
+        Some(std::slice::from_raw_parts(self.slice.ptr, len))
+    }
+
     /// References the inner [`SliceImpl`].
     #[must_use]
     #[inline(always)]
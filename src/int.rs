@@ -1,6 +1,7 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::error::err;
 use crate::fast::{CowSlice, NextUnchecked, PushUnchecked, VecImpl};
+use crate::options::decode_options;
 use crate::pack_ints::{pack_ints, unpack_ints, Int};
 use bytemuck::{CheckedBitPattern, NoUninit, Pod};
 use std::marker::PhantomData;
@@ -27,7 +28,7 @@ impl<T: Int, P: NoUninit> Encoder<P> for IntEncoder<T> {
     }
 }
 
-impl<T: Int> Buffer for IntEncoder<T> {
+impl<T: Int> EncoderBuffer for IntEncoder<T> {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         pack_ints(self.0.as_mut_slice(), out);
         self.0.clear();
@@ -57,6 +58,24 @@ impl<'a, T: Int> View<'a> for IntDecoder<'a, T> {
     }
 }
 
+impl<'a> IntDecoder<'a, u8> {
+    /// Decodes `length` bytes at once. Used by serde's `deserialize_bytes` and `Cow<[u8]>`.
+    /// # Safety
+    /// Can only decode `self.populate(_, length)` items.
+    pub(crate) unsafe fn decode_bytes(&mut self, length: usize) -> &[u8] {
+        bytemuck::must_cast_slice(self.0.mut_slice().chunk_unchecked(length))
+    }
+
+    /// Returns all the bytes if they're borrowed from the input (they weren't packed).
+    /// # Safety
+    /// `length` must be the `length` passed to populate and nothing can have been decoded yet.
+    pub(crate) unsafe fn borrowed_bytes(&self, length: usize) -> Option<&'a [u8]> {
+        self.0
+            .as_borrowed_slice(length)
+            .map(bytemuck::must_cast_slice)
+    }
+}
+
 // Makes IntDecoder<u32> able to decode i32/f32 (but not char since it can fail).
 impl<'a, T: Int, P: Pod> Decoder<'a, P> for IntDecoder<'a, T> {
     #[inline(always)]
@@ -66,10 +85,10 @@ impl<'a, T: Int, P: Pod> Decoder<'a, P> for IntDecoder<'a, T> {
     }
 }
 
-/// For NonZeroU32, char, etc.
+/// For NonZeroU32, NonZeroI8, etc.
 pub struct CheckedIntDecoder<'a, C, I: Int>(IntDecoder<'a, I>, PhantomData<C>);
 
-// Can't bound C: Default since NonZeroU32/char don't implement it.
+// Can't bound C: Default since NonZeroU32 doesn't implement it.
 impl<C, I: Int> Default for CheckedIntDecoder<'_, C, I> {
     fn default() -> Self {
         Self(Default::default(), Default::default())
@@ -107,6 +126,38 @@ where
     }
 }
 
+#[derive(Debug, Default)]
+pub struct CharDecoder<'a> {
+    ints: IntDecoder<'a, u32>,
+    lossy: bool,
+}
+
+impl<'a> View<'a> for CharDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.ints.populate(input, length)?;
+
+        let mut decoder = self.ints.borrowed_clone();
+        self.lossy = (0..length).any(|_| char::from_u32(decoder.decode()).is_none());
+        if self.lossy && !decode_options().lossy_text {
+            return err("invalid bit pattern");
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, char> for CharDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> char {
+        let v: u32 = self.ints.decode();
+        if self.lossy {
+            char::from_u32(v).unwrap_or(char::REPLACEMENT_CHARACTER)
+        } else {
+            // Safety: populate ensures v is a valid char if !self.lossy.
+            unsafe { char::from_u32_unchecked(v) }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{decode, encode};
@@ -118,10 +169,87 @@ mod tests {
         assert!(decode::<NonZeroU32>(&encode(&1u32)).is_ok());
     }
 
+    #[test]
+    fn non_zero() {
+        macro_rules! test {
+            ($($t:ident => $i:ty),+) => {
+                $(
+                    let mut v: Vec<$i> = (1..=100).collect();
+                    let encoded = encode(&v);
+                    let non_zero: Vec<std::num::$t> = decode(&encoded).unwrap();
+                    assert_eq!(encode(&non_zero), encoded);
+
+                    v[50] = 0;
+                    assert!(decode::<Vec<std::num::$t>>(&encode(&v)).is_err());
+                )+
+            };
+        }
+        test!(NonZeroU8 => u8, NonZeroU16 => u16, NonZeroU64 => u64, NonZeroU128 => u128);
+        test!(NonZeroUsize => usize, NonZeroI8 => i8, NonZeroI16 => i16, NonZeroI32 => i32);
+        test!(NonZeroI64 => i64, NonZeroI128 => i128, NonZeroIsize => isize);
+    }
+
+    #[test]
+    fn usize_isize() {
+        // Encoded like u64/i64 regardless of the target's pointer width.
+        let v: Vec<u32> = crate::random_data(100);
+        let usizes: Vec<usize> = v.iter().map(|&v| v as usize).collect();
+        let u64s: Vec<u64> = v.iter().map(|&v| v as u64).collect();
+        assert_eq!(encode(&usizes), encode(&u64s));
+        assert_eq!(decode::<Vec<usize>>(&encode(&u64s)).unwrap(), usizes);
+
+        let isizes: Vec<isize> = v.iter().map(|&v| v as i32 as isize).collect();
+        let i64s: Vec<i64> = v.iter().map(|&v| v as i32 as i64).collect();
+        assert_eq!(encode(&isizes), encode(&i64s));
+        assert_eq!(decode::<Vec<isize>>(&encode(&i64s)).unwrap(), isizes);
+
+        let too_big = encode(&(u32::MAX as u64 + 1));
+        assert_eq!(
+            decode::<usize>(&too_big).is_ok(),
+            cfg!(target_pointer_width = "64")
+        );
+    }
+
+    #[test]
+    fn wrapping() {
+        use std::num::Wrapping;
+        let v: Vec<i16> = crate::random_data(100);
+        let encoded = encode(&v);
+        let wrapping: Vec<_> = v.iter().copied().map(Wrapping).collect();
+        assert_eq!(encode(&wrapping), encoded);
+        assert_eq!(decode::<Vec<Wrapping<i16>>>(&encoded).unwrap(), wrapping);
+    }
+
+    #[test]
+    #[cfg(feature = "saturating")]
+    fn saturating() {
+        use std::num::Saturating;
+        let v: Vec<i16> = crate::random_data(100);
+        let encoded = encode(&v);
+        let saturating: Vec<_> = v.iter().copied().map(Saturating).collect();
+        assert_eq!(encode(&saturating), encoded);
+        assert_eq!(
+            decode::<Vec<Saturating<i16>>>(&encoded).unwrap(),
+            saturating
+        );
+    }
+
     #[test]
     fn char_() {
         assert!(decode::<char>(&encode(&u32::MAX)).is_err());
         assert!(decode::<char>(&encode(&0u32)).is_ok());
+
+        let lossy = crate::DecodeOptions::new().lossy_text(true);
+        let encoded = encode(&[0x61u32, 0xD800, u32::MAX]);
+        assert!(decode::<[char; 3]>(&encoded).is_err());
+        assert_eq!(
+            crate::decode_with_options::<[char; 3]>(&encoded, &lossy).unwrap(),
+            [
+                'a',
+                char::REPLACEMENT_CHARACTER,
+                char::REPLACEMENT_CHARACTER
+            ]
+        );
     }
 
     fn bench_data() -> Vec<u16> {
@@ -0,0 +1,224 @@
+use crate::coder::{Encoder, EncoderBuffer};
+use crate::stream::{DEFAULT_MAX_FRAME_LEN, PREFIX_LEN};
+use crate::{DecodeOwned, Encode};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::num::NonZeroUsize;
+
+/// Bytes that are collected before they're written, so small columns aren't written one at a time.
+pub(crate) const WRITE_SIZE: usize = 1 << 16;
+
+/// Encodes a `T:` [`Encode`] into `writer`. Writes the same bytes as [`encode`][`crate::encode`]
+/// returns.
+///
+/// Columns are written as soon as they're packed instead of being collected into one allocation,
+/// so only the largest column has to fit in memory twice (once unpacked in its encoder and once
+/// packed). Wrap `writer` in a [`std::io::BufWriter`] if it makes a syscall per write.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// let mut file = vec![];
+/// bitcode::encode_to_writer(&vec![1u32, 2, 3], &mut file).unwrap();
+/// assert_eq!(file, bitcode::encode(&vec![1u32, 2, 3]));
+/// ```
+pub fn encode_to_writer<T: Encode + ?Sized, W: Write>(t: &T, mut writer: W) -> Result<(), Error> {
+    let mut encoder = T::Encoder::default();
+    encoder.reserve(NonZeroUsize::MIN);
+    encoder.encode(t);
+
+    let mut out = vec![];
+    let mut result = Ok(());
+    encoder.collect_into_flushing(&mut out, &mut |out| {
+        if out.len() >= WRITE_SIZE {
+            // Keep collecting after an error so the encoder is cleared.
+            if result.is_ok() {
+                result = writer.write_all(out);
+            }
+            out.clear();
+        }
+    });
+    result?;
+    writer.write_all(&out)
+}
+
+/// Reads `reader` until EOF and decodes its bytes into a `T:` [`DecodeOwned`]. Decode errors are
+/// returned as [`ErrorKind::InvalidData`].
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// let file = bitcode::encode(&vec![1u32, 2, 3]);
+/// let v: Vec<u32> = bitcode::decode_from_reader(file.as_slice()).unwrap();
+/// assert_eq!(v, [1, 2, 3]);
+/// ```
+pub fn decode_from_reader<T: DecodeOwned, R: Read>(mut reader: R) -> Result<T, Error> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    crate::decode(&bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Reads one frame from [`encode_frame`][`crate::encode_frame`] from `reader` and decodes it into
+/// a `T:` [`DecodeOwned`]. Only the frame's bytes are read, so it can be called again to read the
+/// next frame. Decode errors are returned as [`ErrorKind::InvalidData`].
+///
+/// Frames longer than 8 MiB (excluding their length prefix) are rejected, see
+/// [`decode_frame_from_reader_with_max_len`].
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// let mut file = bitcode::encode_frame("a");
+/// file.extend(bitcode::encode_frame("b"));
+/// let mut reader = file.as_slice();
+/// assert_eq!(bitcode::decode_frame_from_reader::<String, _>(&mut reader).unwrap(), "a");
+/// assert_eq!(bitcode::decode_frame_from_reader::<String, _>(&mut reader).unwrap(), "b");
+/// ```
+pub fn decode_frame_from_reader<T: DecodeOwned, R: Read>(reader: R) -> Result<T, Error> {
+    decode_frame_from_reader_with_max_len(reader, DEFAULT_MAX_FRAME_LEN)
+}
+
+/// Like [`decode_frame_from_reader`] but frames longer than `max_frame_len` (excluding their length
+/// prefix) are rejected instead of 8 MiB ones. A rejected frame returns [`ErrorKind::InvalidData`]
+/// wrapping a [`crate::Error`] of kind [`crate::ErrorKind::LimitExceeded`], and only its length
+/// prefix has been read from `reader`.
+pub fn decode_frame_from_reader_with_max_len<T: DecodeOwned, R: Read>(
+    mut reader: R,
+    max_frame_len: usize,
+) -> Result<T, Error> {
+    let mut prefix = [0; PREFIX_LEN];
+    reader.read_exact(&mut prefix)?;
+    let len = u32::from_le_bytes(prefix) as usize;
+    if len > max_frame_len {
+        let e = crate::error::error_kind(crate::ErrorKind::LimitExceeded, "frame too large");
+        return Err(Error::new(ErrorKind::InvalidData, e));
+    }
+
+    // Grows as bytes are read instead of trusting len with an allocation.
+    let mut bytes = vec![];
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    crate::decode(&bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode, encode_frame, Decode, Encode};
+    use std::io::{ErrorKind, Write};
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Row {
+        id: u64,
+        name: String,
+        tags: Vec<(u8, bool)>,
+    }
+
+    /// Records the length of each write.
+    #[derive(Default)]
+    struct Writes(Vec<u8>, Vec<usize>);
+
+    impl Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.extend_from_slice(buf);
+            self.1.push(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writer() {
+        let rows: Vec<Row> = (0..50000)
+            .map(|i| Row {
+                id: i * 1000,
+                name: i.to_string(),
+                tags: (0..i as u8 % 4).map(|t| (t, i % 2 == 0)).collect(),
+            })
+            .collect();
+        let mut writes = Writes::default();
+        super::encode_to_writer(&rows, &mut writes).unwrap();
+        assert_eq!(writes.0, encode(&rows));
+        assert_eq!(decode::<Vec<Row>>(&writes.0).unwrap(), rows);
+        // Columns were written separately instead of all at once.
+        assert!(writes.1.len() > 1, "{:?}", writes.1);
+        assert!(writes.1.iter().all(|&n| n < writes.0.len()));
+
+        let mut small = vec![];
+        super::encode_to_writer(&(1u8, "a"), &mut small).unwrap();
+        assert_eq!(small, encode(&(1u8, "a")));
+    }
+
+    #[test]
+    fn writer_error() {
+        let rows = vec![u64::MAX; 100000];
+        let mut full = [0u8; 1000];
+        assert!(super::encode_to_writer(&rows, full.as_mut_slice()).is_err());
+    }
+
+    #[test]
+    fn reader() {
+        let rows: Vec<Row> = (0..100)
+            .map(|i| Row {
+                id: i,
+                name: i.to_string(),
+                tags: vec![(i as u8, true)],
+            })
+            .collect();
+        let encoded = encode(&rows);
+        let decoded: Vec<Row> = super::decode_from_reader(encoded.as_slice()).unwrap();
+        assert_eq!(decoded, rows);
+
+        let invalid = super::decode_from_reader::<Vec<Row>, _>(&encoded[..encoded.len() - 1]);
+        assert_eq!(invalid.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn frame_reader() {
+        let frames: Vec<u8> = (0..10u32)
+            .flat_map(|i| encode_frame(&vec![i; i as usize]))
+            .collect();
+        let mut reader = frames.as_slice();
+        for i in 0..10u32 {
+            let v: Vec<u32> = super::decode_frame_from_reader(&mut reader).unwrap();
+            assert_eq!(v, vec![i; i as usize]);
+        }
+        let eof = super::decode_frame_from_reader::<Vec<u32>, _>(&mut reader);
+        assert_eq!(eof.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+        // The length prefix claims more bytes than there are.
+        let frame = encode_frame(&vec![1u32; 5]);
+        let err = super::decode_frame_from_reader::<Vec<u32>, _>(&frame[..frame.len() - 1]);
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        let huge = [u32::MAX.to_le_bytes().as_slice(), &[0; 10]].concat();
+        let err = super::decode_frame_from_reader::<Vec<u32>, _>(huge.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let err = super::decode_frame_from_reader_with_max_len::<Vec<u32>, _>(huge.as_slice(), !0);
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn frame_reader_max_len() {
+        let frame = encode_frame(&vec![1u32; 5]);
+        let max_len = frame.len() - super::PREFIX_LEN;
+        let v: Vec<u32> =
+            super::decode_frame_from_reader_with_max_len(frame.as_slice(), max_len).unwrap();
+        assert_eq!(v, vec![1; 5]);
+
+        let err = super::decode_frame_from_reader_with_max_len::<Vec<u32>, _>(
+            frame.as_slice(),
+            max_len - 1,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let inner = err
+            .into_inner()
+            .unwrap()
+            .downcast::<crate::Error>()
+            .unwrap();
+        assert_eq!(inner.kind(), crate::ErrorKind::LimitExceeded);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::coder::{Decoder, View};
+use crate::consume::expect_eof;
+use crate::length::LengthDecoder;
+use crate::{Decode, Error};
+
+/// Decodes the items of an encoded `Vec<T>` (or any other sequence that's encoded like one, such
+/// as a slice or `VecDeque<T>`) one at a time instead of collecting them into a `Vec<T>`.
+///
+/// Each column is validated as a whole, so the entire input is validated before the first item is
+/// returned and the iterator itself can't fail.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// let encoded = bitcode::encode(&vec!["a", "bb", "ccc"]);
+/// let mut iter = bitcode::decode_iter::<&str>(&encoded).unwrap();
+/// assert_eq!(iter.len(), 3);
+/// assert_eq!(iter.find(|s| s.len() == 2), Some("bb"));
+/// ```
+pub fn decode_iter<'a, T: Decode<'a>>(bytes: &'a [u8]) -> Result<DecodeIter<'a, T>, Error> {
+    let mut input = bytes;
+    let mut lengths = LengthDecoder::default();
+    let mut elements = T::Decoder::default();
+    let mut len = 0;
+    lengths
+        .populate(&mut input, 1)
+        .and_then(|()| {
+            len = lengths.length();
+            elements.populate(&mut input, len)
+        })
+        .and_then(|()| expect_eof(input))
+        .map_err(|e| e.at_offset(bytes, input))?;
+    Ok(DecodeIter {
+        elements,
+        remaining: len,
+    })
+}
+
+/// The iterator returned by [`decode_iter`].
+pub struct DecodeIter<'a, T: Decode<'a>> {
+    elements: T::Decoder,
+    remaining: usize,
+}
+
+impl<'a, T: Decode<'a>> Iterator for DecodeIter<'a, T> {
+    type Item = T;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<T> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(self.elements.decode())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T: Decode<'a>> ExactSizeIterator for DecodeIter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_iter;
+    use crate::{encode, Decode, Encode};
+    use std::collections::VecDeque;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Row<'a> {
+        id: u32,
+        name: &'a str,
+        tags: Vec<u8>,
+    }
+
+    #[test]
+    fn iter() {
+        let names: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+        let rows: Vec<Row> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Row {
+                id: i as u32,
+                name,
+                tags: vec![i as u8; i % 3],
+            })
+            .collect();
+        let encoded = encode(&rows);
+        let decoded: Vec<Row> = decode_iter(&encoded).unwrap().collect();
+        assert_eq!(decoded, rows);
+
+        // Stopping early.
+        let mut iter = decode_iter::<Row>(&encoded).unwrap();
+        assert_eq!(iter.nth(500).unwrap().name, "500");
+        assert_eq!(iter.len(), 499);
+
+        let deque: VecDeque<u16> = (0..100).collect();
+        let sum: u16 = decode_iter::<u16>(&encode(&deque)).unwrap().sum();
+        assert_eq!(sum, 4950);
+        assert_eq!(
+            decode_iter::<u8>(&encode(&Vec::<u8>::new())).unwrap().len(),
+            0
+        );
+
+        // Invalid inputs fail before iterating.
+        assert!(decode_iter::<Row>(&encoded[..encoded.len() - 1]).is_err());
+        assert!(decode_iter::<bool>(&encode(&vec![0u8, 2])).is_err());
+        assert!(decode_iter::<u8>(&[encode(&vec![1u8]), vec![0]].concat()).is_err());
+    }
+}
@@ -1,5 +1,5 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
-use crate::error::{err, error};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
+use crate::error::{err_kind, error_kind, ErrorKind};
 use crate::fast::{CowSlice, NextUnchecked, VecImpl};
 use crate::int::{IntDecoder, IntEncoder};
 use crate::pack::{pack_bytes, unpack_bytes};
@@ -90,21 +90,24 @@ impl LengthEncoder {
         false
     }
 
+    /// Encodes lengths of any size, calling `encode_large` with each `T` after its length. Unlike
+    /// [`Self::encode_vectored_max_len`], nothing is reserved for the elements, so the caller has
+    /// to reserve them before calling this or in `encode_large`.
     #[inline(always)]
     pub fn encode_vectored_fallback<T: Len>(
         &mut self,
         i: impl Iterator<Item = T>,
-        mut reserve_and_encode_large: impl FnMut(T),
+        mut encode_large: impl FnMut(T),
     ) {
         for v in i {
             let n = v.len();
             self.encode(&n);
-            reserve_and_encode_large(v);
+            encode_large(v);
         }
     }
 }
 
-impl Buffer for LengthEncoder {
+impl EncoderBuffer for LengthEncoder {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         pack_bytes(self.small.as_mut_slice(), out);
         self.small.clear();
@@ -160,6 +163,30 @@ impl<'a> LengthDecoder<'a> {
     }
 }
 
+impl LengthDecoder<'_> {
+    /// Returns an error with `msg` if any of the decoded lengths are > `max`.
+    /// Safety: `length` must be the `length` passed to populate.
+    pub unsafe fn check_max(
+        &self,
+        length: usize,
+        max: Option<usize>,
+        msg: &'static str,
+    ) -> Result<()> {
+        let Some(max) = max else {
+            return Ok(());
+        };
+        // Fast path: no length can be > max if their sum isn't.
+        if self.sum <= max {
+            return Ok(());
+        }
+        let mut decoder = self.borrowed_clone();
+        if (0..length).any(|_| decoder.decode() > max) {
+            return err_kind(ErrorKind::LimitExceeded, msg);
+        }
+        Ok(())
+    }
+}
+
 impl<'a> View<'a> for LengthDecoder<'a> {
     fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
         unpack_bytes(input, length, &mut self.small)?;
@@ -187,12 +214,15 @@ impl<'a> View<'a> for LengthDecoder<'a> {
             let v: usize = decoder.decode();
             sum = sum
                 .checked_add(v as u64)
-                .ok_or_else(|| error("length overflow"))?;
+                .ok_or_else(|| error_kind(ErrorKind::LengthOverflow, "length overflow"))?;
         }
         if sum >= HUGE_LEN {
-            return err("length overflow"); // Lets us optimize decode with unreachable_unchecked.
+            // Lets us optimize decode with unreachable_unchecked.
+            return err_kind(ErrorKind::LengthOverflow, "length overflow");
         }
-        self.sum = sum.try_into().map_err(|_| error("length > usize::MAX"))?;
+        self.sum = sum
+            .try_into()
+            .map_err(|_| error_kind(ErrorKind::LengthOverflow, "length > usize::MAX"))?;
         Ok(())
     }
 }
@@ -229,7 +259,7 @@ impl<'a> Decoder<'a, usize> for LengthDecoder<'a> {
 #[cfg(test)]
 mod tests {
     use super::{LengthDecoder, LengthEncoder};
-    use crate::coder::{Buffer, Decoder, Encoder, View};
+    use crate::coder::{Decoder, Encoder, EncoderBuffer, View};
     use std::num::NonZeroUsize;
 
     #[test]
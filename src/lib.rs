@@ -12,28 +12,68 @@ extern crate test;
 
 mod bool;
 mod buffer;
+mod checksum;
+#[cfg(feature = "tokio-util")]
+mod codec;
 mod coder;
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+mod compress;
 mod consume;
 mod derive;
+mod describe;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
 mod error;
 mod ext;
 mod f32;
 mod fast;
 mod histogram;
 mod int;
+mod io;
+mod iter;
 mod length;
 mod nightly;
+mod options;
 mod pack;
 mod pack_ints;
+mod seekable;
 mod str;
+mod stream;
+mod target;
+#[cfg(feature = "tokio")]
+mod tokio;
+mod transform;
 mod u8_char;
+mod visit;
+#[cfg(feature = "zstd")]
+mod zstd;
 
 pub use crate::buffer::Buffer;
+pub use crate::checksum::*;
+#[cfg(feature = "tokio-util")]
+pub use crate::codec::BitcodeCodec;
+#[cfg(any(feature = "zstd", feature = "lz4"))]
+pub use crate::compress::*;
 pub use crate::derive::*;
-pub use crate::error::Error;
+pub use crate::describe::*;
+pub use crate::error::{Error, ErrorKind};
+#[cfg(feature = "bytes")]
+pub use crate::ext::bytes::decode_bytes;
+pub use crate::io::*;
+pub use crate::iter::*;
+pub use crate::options::{DecodeOptions, EncodeOptions};
+pub use crate::seekable::*;
+pub use crate::stream::*;
+pub use crate::target::EncodeTarget;
+#[cfg(feature = "tokio")]
+pub use crate::tokio::{decode_async, decode_async_with_max_len, encode_async};
+pub use crate::transform::*;
+pub use crate::visit::{visit, Schema, Value, Visitor};
+#[cfg(feature = "zstd")]
+pub use crate::zstd::{train_zstd_dictionary, ZstdDictionary};
 
 #[cfg(feature = "derive")]
-pub use bitcode_derive::{Decode, Encode};
+pub use bitcode_derive::{Decode, Describe, Encode};
 
 #[cfg(feature = "serde")]
 mod serde;
@@ -0,0 +1,252 @@
+use crate::coder::{Result, MAX_VECTORED_CHUNK};
+use crate::error::{error_kind, ErrorKind};
+use std::cell::Cell;
+
+/// Options for [`encode_with_options`][`crate::encode_with_options`] and
+/// [`Buffer::encode_with_options`][`crate::Buffer::encode_with_options`].
+///
+/// The default options encode exactly like [`encode`][`crate::encode`].
+///
+/// They only apply to [`Encode`][`crate::Encode`] types. `serialize` doesn't take options, so
+/// floats encoded with `serde` always keep their NaN payloads and signs.
+///
+/// Those two functions are the only ones that take options. Every other way of encoding uses the
+/// default options, including [`encode_to_writer`][`crate::encode_to_writer`],
+/// [`encode_frame`][`crate::encode_frame`], [`encode_chunks`][`crate::encode_chunks`],
+/// [`SequenceEncoder`][`crate::SequenceEncoder`], [`encode_seekable`][`crate::encode_seekable`],
+/// [`Buffer::encode_into`][`crate::Buffer::encode_into`],
+/// [`Buffer::encode_append`][`crate::Buffer::encode_append`], `encode_compressed`, `encode_async`
+/// and `BitcodeCodec`.
+///
+/// ```rust
+/// use bitcode::EncodeOptions;
+///
+/// let options = EncodeOptions::new()
+///     .canonicalize_nan(true)
+///     .normalize_negative_zero(true);
+/// let a = bitcode::encode_with_options(&[f32::NAN, -0.0], &options);
+/// let b = bitcode::encode_with_options(&[-f32::NAN, 0.0], &options);
+/// assert_eq!(a, b);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub(crate) canonicalize_nan: bool,
+    pub(crate) normalize_negative_zero: bool,
+    pub(crate) vectored_chunk_len: usize,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EncodeOptions {
+    /// Constructs the default options.
+    pub const fn new() -> Self {
+        Self {
+            canonicalize_nan: false,
+            normalize_negative_zero: false,
+            vectored_chunk_len: MAX_VECTORED_CHUNK,
+        }
+    }
+
+    /// Encodes every NaN `f32`/`f64` as [`f32::NAN`]/[`f64::NAN`] instead of preserving its sign
+    /// and payload bits, which differ between hardware.
+    pub const fn canonicalize_nan(mut self, canonicalize_nan: bool) -> Self {
+        self.canonicalize_nan = canonicalize_nan;
+        self
+    }
+
+    /// Encodes `-0.0` as `0.0`.
+    pub const fn normalize_negative_zero(mut self, normalize_negative_zero: bool) -> Self {
+        self.normalize_negative_zero = normalize_negative_zero;
+        self
+    }
+
+    /// Sets how many elements of a sequence are encoded at a time (one field at a time) before
+    /// moving on to the next elements. Smaller chunks can be faster for large elements since more
+    /// of a chunk stays in the CPU cache. Doesn't change the output. Defaults to 64 and is clamped
+    /// to `1..=64`.
+    pub const fn vectored_chunk_len(mut self, vectored_chunk_len: usize) -> Self {
+        self.vectored_chunk_len = if vectored_chunk_len == 0 {
+            1
+        } else if vectored_chunk_len > MAX_VECTORED_CHUNK {
+            MAX_VECTORED_CHUNK
+        } else {
+            vectored_chunk_len
+        };
+        self
+    }
+
+    /// Returns true if the options don't change how floats are encoded.
+    pub(crate) fn floats_unchanged(&self) -> bool {
+        !self.canonicalize_nan && !self.normalize_negative_zero
+    }
+}
+
+// Options are only read by the few encoders that need them (and outside of their hot loops) so
+// passing them through every encoder isn't worth the extra code.
+thread_local! {
+    static ENCODE_OPTIONS: Cell<EncodeOptions> = const { Cell::new(EncodeOptions::new()) };
+}
+
+/// Returns the options of the current [`with_encode_options`] call (or the default options).
+pub(crate) fn encode_options() -> EncodeOptions {
+    ENCODE_OPTIONS.with(Cell::get)
+}
+
+/// Calls `f` with `options` being returned by [`encode_options`].
+pub(crate) fn with_encode_options<R>(options: &EncodeOptions, f: impl FnOnce() -> R) -> R {
+    struct Restore(EncodeOptions);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ENCODE_OPTIONS.with(|o| o.set(self.0));
+        }
+    }
+    let _restore = Restore(ENCODE_OPTIONS.with(|o| o.replace(*options)));
+    f()
+}
+
+/// Options for [`decode_with_options`][`crate::decode_with_options`] and
+/// [`Buffer::decode_with_options`][`crate::Buffer::decode_with_options`].
+///
+/// The default options decode exactly like [`decode`][`crate::decode`].
+///
+/// ```rust
+/// use bitcode::DecodeOptions;
+///
+/// let mut encoded = bitcode::encode(&(0x110000u32, "ab")); // The u32 isn't a valid char.
+/// *encoded.last_mut().unwrap() = 0xFF; // Neither is the b.
+/// assert!(bitcode::decode::<(char, String)>(&encoded).is_err());
+///
+/// let options = DecodeOptions::new().lossy_text(true);
+/// let decoded: (char, String) = bitcode::decode_with_options(&encoded, &options).unwrap();
+/// assert_eq!(decoded, ('\u{FFFD}', "a\u{FFFD}".to_owned()));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    pub(crate) lossy_text: bool,
+    pub(crate) allocation_limit: Option<usize>,
+    pub(crate) max_vec_len: Option<usize>,
+    pub(crate) max_map_len: Option<usize>,
+    pub(crate) max_str_len: Option<usize>,
+}
+
+impl DecodeOptions {
+    /// Constructs the default options.
+    pub const fn new() -> Self {
+        Self {
+            lossy_text: false,
+            allocation_limit: None,
+            max_vec_len: None,
+            max_map_len: None,
+            max_str_len: None,
+        }
+    }
+
+    /// Decodes invalid UTF-8 in `String`s (and types decoded like them such as `Box<str>`) with
+    /// [`char::REPLACEMENT_CHARACTER`]s like [`String::from_utf8_lossy`], and invalid `char`s as
+    /// [`char::REPLACEMENT_CHARACTER`], instead of returning an error. `&str`s can't be decoded
+    /// lossily since they borrow the input, so they still return an error.
+    pub const fn lossy_text(mut self, lossy_text: bool) -> Self {
+        self.lossy_text = lossy_text;
+        self
+    }
+
+    /// Returns an error instead of decoding if the collections (`Vec`s, `HashMap`s, etc.) in the
+    /// input would need more than `allocation_limit` bytes in total. Their lengths are checked
+    /// before anything is allocated, so a small malicious input claiming a huge collection of
+    /// values that take up no space in the input (e.g. `Vec<()>` in a `Vec<Vec<()>>`) can't exhaust
+    /// memory. Strings and bytes aren't counted since they can't be longer than the input.
+    ///
+    /// ```rust
+    /// use bitcode::DecodeOptions;
+    ///
+    /// let options = DecodeOptions::new().allocation_limit(1000);
+    /// let encoded = bitcode::encode(&vec![0u32; 1000]);
+    /// assert!(bitcode::decode_with_options::<Vec<u32>>(&encoded, &options).is_err());
+    /// let encoded = bitcode::encode(&vec![0u32; 250]);
+    /// assert!(bitcode::decode_with_options::<Vec<u32>>(&encoded, &options).is_ok());
+    /// ```
+    pub const fn allocation_limit(mut self, allocation_limit: usize) -> Self {
+        self.allocation_limit = Some(allocation_limit);
+        self
+    }
+
+    /// Returns an error if a `Vec` (or any other sequence, such as a `VecDeque`, `HashSet`,
+    /// `Box<[T]>` or `Cow<[u8]>`) has more than `max_vec_len` elements.
+    ///
+    /// ```rust
+    /// use bitcode::DecodeOptions;
+    ///
+    /// let options = DecodeOptions::new().max_vec_len(2).max_str_len(3);
+    /// let encoded = bitcode::encode(&vec![vec!["abc"; 2]; 2]);
+    /// assert!(bitcode::decode_with_options::<Vec<Vec<&str>>>(&encoded, &options).is_ok());
+    /// let encoded = bitcode::encode(&vec![vec!["abc"; 3]]);
+    /// assert!(bitcode::decode_with_options::<Vec<Vec<&str>>>(&encoded, &options).is_err());
+    /// let encoded = bitcode::encode(&vec![vec!["abcd"]]);
+    /// assert!(bitcode::decode_with_options::<Vec<Vec<&str>>>(&encoded, &options).is_err());
+    /// ```
+    pub const fn max_vec_len(mut self, max_vec_len: usize) -> Self {
+        self.max_vec_len = Some(max_vec_len);
+        self
+    }
+
+    /// Returns an error if a `HashMap` (or any other map, such as a `BTreeMap`) has more than
+    /// `max_map_len` entries.
+    pub const fn max_map_len(mut self, max_map_len: usize) -> Self {
+        self.max_map_len = Some(max_map_len);
+        self
+    }
+
+    /// Returns an error if a `String` (or any other string, such as a `&str` or `Box<str>`) is
+    /// longer than `max_str_len` bytes.
+    pub const fn max_str_len(mut self, max_str_len: usize) -> Self {
+        self.max_str_len = Some(max_str_len);
+        self
+    }
+}
+
+thread_local! {
+    static DECODE_OPTIONS: Cell<DecodeOptions> = const { Cell::new(DecodeOptions::new()) };
+    /// What's left of [`DecodeOptions::allocation_limit`] in the current [`with_decode_options`].
+    static ALLOCATION_BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Returns the options of the current [`with_decode_options`] call (or the default options).
+pub(crate) fn decode_options() -> DecodeOptions {
+    DECODE_OPTIONS.with(Cell::get)
+}
+
+/// Calls `f` with `options` being returned by [`decode_options`].
+pub(crate) fn with_decode_options<R>(options: &DecodeOptions, f: impl FnOnce() -> R) -> R {
+    struct Restore(DecodeOptions, Option<usize>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DECODE_OPTIONS.with(|o| o.set(self.0));
+            ALLOCATION_BUDGET.with(|b| b.set(self.1));
+        }
+    }
+    let _restore = Restore(
+        DECODE_OPTIONS.with(|o| o.replace(*options)),
+        ALLOCATION_BUDGET.with(|b| b.replace(options.allocation_limit)),
+    );
+    f()
+}
+
+/// Subtracts the size of `length` `T`s from the current [`DecodeOptions::allocation_limit`],
+/// returning an error if it's exceeded. Called in populate by decoders of collections.
+pub(crate) fn reserve_allocation<T>(length: usize) -> Result<()> {
+    ALLOCATION_BUDGET.with(|b| {
+        let Some(budget) = b.get() else {
+            return Ok(());
+        };
+        let bytes = length.saturating_mul(std::mem::size_of::<T>());
+        let remaining = budget
+            .checked_sub(bytes)
+            .ok_or_else(|| error_kind(ErrorKind::LimitExceeded, "allocation limit exceeded"))?;
+        b.set(Some(remaining));
+        Ok(())
+    })
+}
@@ -1,6 +1,6 @@
 use crate::coder::Result;
 use crate::consume::{consume_byte, consume_byte_arrays, consume_bytes};
-use crate::error::err;
+use crate::error::{err, err_kind, ErrorKind};
 use crate::fast::CowSlice;
 use crate::pack_ints::SizedInt;
 
@@ -222,7 +222,7 @@ pub fn unpack_bytes_less_than<'a, const N: usize, const HISTOGRAM: usize>(
         assert!(FACTOR >= N);
         debug_assert!(unpacked.iter().all(|&v| (v as usize) < FACTOR));
         if FACTOR > N && unpacked.iter().copied().max().unwrap_or(0) as usize >= N {
-            return invalid_packing();
+            return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
         }
         Ok(std::array::from_fn(|_| unreachable!("HISTOGRAM not 0")))
     }
@@ -233,7 +233,7 @@ pub fn unpack_bytes_less_than<'a, const N: usize, const HISTOGRAM: usize>(
     ) -> Result<[usize; OUT]> {
         let (histogram, remaining) = histogram.split_at(OUT);
         if remaining.iter().copied().sum::<usize>() != 0 {
-            return invalid_packing();
+            return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
         }
         Ok(*<&[usize; OUT]>::try_from(histogram).unwrap())
     }
@@ -0,0 +1,201 @@
+use crate::consume::consume_byte_arrays;
+use crate::error::{err, error_kind, ErrorKind};
+use crate::nightly::div_ceil_usize;
+use crate::{Buffer, Decode, Encode, Error};
+
+/// Encodes `items` into independently decodable chunks of up to `chunk_len` items followed by a
+/// table of contents, so [`SeekableReader`] can decode any item by only decoding its chunk.
+///
+/// The format is each chunk encoded as a `Vec<T>`, followed by the end offset of each chunk, the
+/// number of items, and `chunk_len` (all as 8 byte little endian integers).
+///
+/// Only the items of `items` can be seeked to, not the fields or collections nested inside them.
+/// Columns of nested values are shared by all the items of a chunk, so they have no offsets of
+/// their own. To seek into a collection inside a larger value (e.g. `world.chunks[1234]`), encode
+/// the collection with `encode_seekable` and the rest of the value separately.
+///
+/// **Panics** if `chunk_len` is 0.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// use bitcode::{encode_seekable, SeekableReader};
+///
+/// let items: Vec<String> = (0..10000).map(|i| i.to_string()).collect();
+/// let encoded = encode_seekable(&items, 256);
+///
+/// let reader = SeekableReader::new(&encoded).unwrap();
+/// assert_eq!(reader.len(), items.len());
+/// assert_eq!(reader.get::<&str>(1234).unwrap(), "1234");
+/// ```
+pub fn encode_seekable<T: Encode>(items: &[T], chunk_len: usize) -> Vec<u8> {
+    assert_ne!(chunk_len, 0, "chunk_len must be > 0");
+    let mut buffer = Buffer::new();
+    let mut out = vec![];
+    let mut offsets = vec![];
+    for chunk in items.chunks(chunk_len) {
+        out.extend_from_slice(buffer.encode(chunk));
+        offsets.push(out.len() as u64);
+    }
+    for v in offsets
+        .into_iter()
+        .chain([items.len() as u64, chunk_len as u64])
+    {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out
+}
+
+/// Decodes individual chunks/items of the output of [`encode_seekable`] without decoding the
+/// chunks before them.
+#[derive(Copy, Clone, Debug)]
+pub struct SeekableReader<'a> {
+    chunks: &'a [u8],
+    offsets: &'a [[u8; 8]],
+    len: usize,
+    chunk_len: usize,
+}
+
+impl<'a> SeekableReader<'a> {
+    /// Reads the table of contents of `bytes`. Only the table of contents is validated, chunks are
+    /// validated when they're decoded.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        let trailer_start = bytes
+            .len()
+            .checked_sub(16)
+            .ok_or_else(|| error_kind(ErrorKind::Eof, "EOF"))?;
+        let (rest, trailer) = bytes.split_at(trailer_start);
+        let read_usize = |b: &[u8]| {
+            let v = u64::from_le_bytes(b.try_into().unwrap());
+            usize::try_from(v).map_err(|_| error_kind(ErrorKind::LengthOverflow, "length overflow"))
+        };
+        let len = read_usize(&trailer[..8])?;
+        let chunk_len = read_usize(&trailer[8..])?;
+        if chunk_len == 0 {
+            return err("invalid chunk length");
+        }
+        let chunk_count = div_ceil_usize(len, chunk_len);
+
+        let chunks_len = rest
+            .len()
+            .checked_sub(chunk_count.saturating_mul(8))
+            .ok_or_else(|| error_kind(ErrorKind::Eof, "EOF"))?;
+        let (chunks, mut toc) = rest.split_at(chunks_len);
+        let offsets = consume_byte_arrays::<8>(&mut toc, chunk_count)?;
+
+        let mut start = 0;
+        for offset in offsets {
+            let end = u64::from_le_bytes(*offset);
+            if end < start {
+                return err("invalid chunk offset");
+            }
+            start = end;
+        }
+        if start != chunks.len() as u64 {
+            return err("invalid chunk offset");
+        }
+
+        Ok(Self {
+            chunks,
+            offsets,
+            len,
+            chunk_len,
+        })
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of chunks.
+    pub fn chunk_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns the maximum number of items per chunk. Chunk `i` contains items
+    /// `i * chunk_len..(i + 1) * chunk_len`.
+    pub fn chunk_len(&self) -> usize {
+        self.chunk_len
+    }
+
+    /// Decodes chunk `i`.
+    pub fn decode_chunk<T: Decode<'a>>(&self, i: usize) -> Result<Vec<T>, Error> {
+        let end = |i: usize| u64::from_le_bytes(self.offsets[i]) as usize;
+        if i >= self.chunk_count() {
+            return err("chunk index out of bounds");
+        }
+        let start = if i == 0 { 0 } else { end(i - 1) };
+        let chunk: Vec<T> = crate::decode(&self.chunks[start..end(i)])?;
+
+        let expected = self.chunk_len.min(self.len - i * self.chunk_len);
+        if chunk.len() != expected {
+            return err("invalid chunk length");
+        }
+        Ok(chunk)
+    }
+
+    /// Decodes item `index` by decoding the chunk that contains it.
+    pub fn get<T: Decode<'a>>(&self, index: usize) -> Result<T, Error> {
+        if index >= self.len {
+            return err("index out of bounds");
+        }
+        let mut chunk = self.decode_chunk(index / self.chunk_len)?;
+        Ok(chunk.swap_remove(index % self.chunk_len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seekable() {
+        for len in [0, 1, 99, 100, 101, 1000] {
+            let items: Vec<(u32, String)> = (0..len).map(|i| (i, i.to_string())).collect();
+            for chunk_len in [1, 7, 100, 5000] {
+                let encoded = encode_seekable(&items, chunk_len);
+                let reader = SeekableReader::new(&encoded).unwrap();
+                assert_eq!(reader.len(), items.len());
+                assert_eq!(reader.chunk_count(), div_ceil_usize(items.len(), chunk_len));
+
+                let mut decoded = vec![];
+                for i in 0..reader.chunk_count() {
+                    decoded.extend(reader.decode_chunk::<(u32, &str)>(i).unwrap());
+                }
+                let expected: Vec<_> = items.iter().map(|(i, s)| (*i, s.as_str())).collect();
+                assert_eq!(decoded, expected);
+
+                for (i, item) in items.iter().enumerate().step_by(13) {
+                    assert_eq!(&reader.get::<(u32, String)>(i).unwrap(), item);
+                }
+                assert!(reader.get::<(u32, String)>(items.len()).is_err());
+                assert!(reader
+                    .decode_chunk::<(u32, String)>(reader.chunk_count())
+                    .is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let encoded = encode_seekable(&[1u8, 2, 3], 2);
+        for i in 0..encoded.len() {
+            assert!(SeekableReader::new(&encoded[..i]).is_err());
+            assert!(SeekableReader::new(&encoded[i + 1..]).is_err());
+        }
+
+        // Chunk with the wrong number of items.
+        let mut encoded = crate::encode(&[1u8][..]);
+        for v in [encoded.len() as u64, 2, 2] {
+            encoded.extend_from_slice(&v.to_le_bytes());
+        }
+        let reader = SeekableReader::new(&encoded).unwrap();
+        assert!(reader.decode_chunk::<u8>(0).is_err());
+    }
+}
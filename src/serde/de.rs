@@ -1,11 +1,12 @@
 use crate::bool::BoolDecoder;
 use crate::coder::{Decoder, Result, View};
 use crate::consume::expect_eof;
-use crate::error::{err, error, Error};
+use crate::error::{err, err_kind, error, Error, ErrorKind};
 use crate::f32::F32Decoder;
 use crate::int::IntDecoder;
 use crate::length::LengthDecoder;
 use crate::serde::guard::guard_zst;
+use crate::serde::shape::{read_len, Shape, MAX_DEPTH};
 use crate::serde::variant::VariantDecoder;
 use crate::serde::{default_box_slice, get_mut_or_resize, type_changed};
 use crate::str::StrDecoder;
@@ -13,6 +14,7 @@ use serde::de::{
     DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
 };
 use serde::{Deserialize, Deserializer};
+use std::marker::PhantomData;
 
 // Redefine Result from crate::coder::Result to std::result::Result since the former isn't public.
 mod inner {
@@ -28,20 +30,50 @@ mod inner {
         let t = T::deserialize(DecoderWrapper {
             decoder: &mut decoder,
             input: &mut bytes,
+            self_describing: false,
+        })?;
+        expect_eof(bytes)?;
+        Ok(t)
+    }
+
+    /// Deserializes the output of [`serialize_self_describing`][`crate::serialize_self_describing`]
+    /// into an instance of `T:` [`Deserialize`]. Unlike [`deserialize`], this supports
+    /// [`Deserializer::deserialize_any`].
+    ///
+    /// **Warning:** The format is incompatible with [`deserialize`] and subject to change between
+    /// major versions.
+    pub fn deserialize_self_describing<'de, T: Deserialize<'de>>(
+        mut bytes: &'de [u8],
+    ) -> Result<T, Error> {
+        let mut decoder = SerdeDecoder::from_shape(&mut bytes, 0)?;
+        decoder.populate(&mut bytes, 1)?;
+        let t = T::deserialize(DecoderWrapper {
+            decoder: &mut decoder,
+            input: &mut bytes,
+            self_describing: true,
         })?;
         expect_eof(bytes)?;
         Ok(t)
     }
 }
-pub use inner::deserialize;
+pub use inner::{deserialize, deserialize_self_describing};
 
 #[derive(Debug)]
 enum SerdeDecoder<'a> {
     Bool(BoolDecoder<'a>),
+    Char(IntDecoder<'a, u32>),
     Enum((VariantDecoder<'a>, Vec<SerdeDecoder<'a>>)), // (variants, values)
     F32(F32Decoder<'a>),
-    // We don't need signed integer decoders here because unsigned ones work the same.
+    F64(IntDecoder<'a, u64>),
+    // We don't need signed integer decoders because unsigned ones work the same. They (and
+    // Char/F64/Option) are only specified by self describing headers for deserialize_any.
+    I8(IntDecoder<'a, u8>),
+    I16(IntDecoder<'a, u16>),
+    I32(IntDecoder<'a, u32>),
+    I64(IntDecoder<'a, u64>),
+    I128(IntDecoder<'a, u128>),
     Map((LengthDecoder<'a>, Box<(SerdeDecoder<'a>, SerdeDecoder<'a>)>)), // (lengths, (keys, values))
+    Option((VariantDecoder<'a>, Vec<SerdeDecoder<'a>>)),                 // Same as Enum.
     Seq((LengthDecoder<'a>, Box<SerdeDecoder<'a>>)),                     // (lengths, values)
     Str(StrDecoder<'a>),
     Tuple(Box<[SerdeDecoder<'a>]>), // [field0, field1, ..]
@@ -50,6 +82,7 @@ enum SerdeDecoder<'a> {
     U32(IntDecoder<'a, u32>),
     U64(IntDecoder<'a, u64>),
     U128(IntDecoder<'a, u128>),
+    Union(Box<UnionDecoder<'a>>),
     Unpopulated,
     Unspecified { length: usize },
 }
@@ -64,8 +97,13 @@ impl<'a> View<'a> for SerdeDecoder<'a> {
     fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
         match self {
             Self::Bool(d) => d.populate(input, length),
-            Self::Enum(d) => {
+            Self::Enum(d) | Self::Option(d) => {
                 d.0.populate(input, length)?;
+                let variants = d.0.max_variant_index().map_or(0, |i| i as usize + 1);
+                if d.1.len() > variants {
+                    // Only possible with an invalid self describing header.
+                    return err_kind(ErrorKind::InvalidVariant, "invalid enum");
+                }
                 if let Some(max_variant_index) = d.0.max_variant_index() {
                     get_mut_or_resize(&mut d.1, max_variant_index as usize);
                     d.1.iter_mut()
@@ -89,11 +127,12 @@ impl<'a> View<'a> for SerdeDecoder<'a> {
             }
             Self::Str(d) => d.populate(input, length),
             Self::Tuple(d) => d.iter_mut().try_for_each(|d| d.populate(input, length)),
-            Self::U8(d) => d.populate(input, length),
-            Self::U16(d) => d.populate(input, length),
-            Self::U32(d) => d.populate(input, length),
-            Self::U64(d) => d.populate(input, length),
-            Self::U128(d) => d.populate(input, length),
+            Self::U8(d) | Self::I8(d) => d.populate(input, length),
+            Self::U16(d) | Self::I16(d) => d.populate(input, length),
+            Self::U32(d) | Self::I32(d) | Self::Char(d) => d.populate(input, length),
+            Self::U64(d) | Self::I64(d) | Self::F64(d) => d.populate(input, length),
+            Self::U128(d) | Self::I128(d) => d.populate(input, length),
+            Self::Union(d) => d.populate(input, length),
             Self::Unpopulated => {
                 *self = Self::Unspecified { length };
                 Ok(())
@@ -103,48 +142,244 @@ impl<'a> View<'a> for SerdeDecoder<'a> {
     }
 }
 
+/// Only created by self describing headers: values of different types in the same place.
+#[derive(Debug, Default)]
+struct UnionDecoder<'a> {
+    indices: VariantDecoder<'a>,
+    explicit: usize, // Number of values with an explicit alternative index.
+    implicit: usize, // Number of values left that are implicitly the first alternative.
+    alternatives: Vec<SerdeDecoder<'a>>,
+}
+
+impl<'a> View<'a> for UnionDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        self.implicit = length
+            .checked_sub(self.explicit)
+            .ok_or_else(|| error("invalid union"))?;
+        self.indices.populate(input, self.explicit)?;
+        let used = self
+            .indices
+            .max_variant_index()
+            .map_or(0, |i| i as usize + 1);
+        if used > self.alternatives.len() || (self.implicit != 0 && self.alternatives.is_empty()) {
+            return err("invalid union");
+        }
+        self.alternatives
+            .iter_mut()
+            .enumerate()
+            .try_for_each(|(i, alternative)| {
+                let mut length = if i < used {
+                    self.indices.length(i as u8)
+                } else {
+                    0
+                };
+                if i == 0 {
+                    length += self.implicit;
+                }
+                alternative.populate(input, length)
+            })
+    }
+}
+
+impl<'a> UnionDecoder<'a> {
+    /// Returns the decoder of the next value's alternative.
+    fn decode(&mut self) -> &mut SerdeDecoder<'a> {
+        let i = if self.implicit != 0 {
+            self.implicit -= 1;
+            0
+        } else {
+            self.indices.decode()
+        };
+        // Safety: populate checked that all indices are in bounds.
+        unsafe { self.alternatives.get_unchecked_mut(i as usize) }
+    }
+}
+
+impl SerdeDecoder<'_> {
+    /// Creates an unpopulated decoder out of a self describing header (see [`Shape`]).
+    fn from_shape(input: &mut &[u8], depth: usize) -> Result<Self> {
+        if depth > MAX_DEPTH {
+            return err_kind(
+                ErrorKind::LimitExceeded,
+                "self describing header is too deep",
+            );
+        }
+        let depth = depth + 1;
+        let children = |input: &mut &[u8], len: usize| {
+            // Each child takes at least 1 byte so we can reject len before allocating.
+            if len > input.len() {
+                return err_kind(ErrorKind::Eof, "EOF");
+            }
+            (0..len)
+                .map(|_| Self::from_shape(input, depth))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(match Shape::read(input)? {
+            Shape::Unit => Self::Unpopulated,
+            Shape::Bool => Self::Bool(Default::default()),
+            Shape::Char => Self::Char(Default::default()),
+            shape @ (Shape::Enum | Shape::Option) => {
+                let len = read_len(input)?;
+                if len > 256 {
+                    return err_kind(
+                        ErrorKind::Unsupported,
+                        "enums with more than 256 variants are unsupported",
+                    );
+                }
+                let values = (Default::default(), children(input, len)?);
+                if shape == Shape::Enum {
+                    Self::Enum(values)
+                } else {
+                    Self::Option(values)
+                }
+            }
+            Shape::F32 => Self::F32(Default::default()),
+            Shape::F64 => Self::F64(Default::default()),
+            Shape::I8 => Self::I8(Default::default()),
+            Shape::I16 => Self::I16(Default::default()),
+            Shape::I32 => Self::I32(Default::default()),
+            Shape::I64 => Self::I64(Default::default()),
+            Shape::I128 => Self::I128(Default::default()),
+            Shape::Map => {
+                let keys = Self::from_shape(input, depth)?;
+                let values = Self::from_shape(input, depth)?;
+                Self::Map((Default::default(), Box::new((keys, values))))
+            }
+            Shape::Seq => Self::Seq((
+                Default::default(),
+                Box::new(Self::from_shape(input, depth)?),
+            )),
+            Shape::Str => Self::Str(Default::default()),
+            Shape::Tuple => {
+                let len = read_len(input)?;
+                Self::Tuple(children(input, len)?.into())
+            }
+            Shape::U8 => Self::U8(Default::default()),
+            Shape::U16 => Self::U16(Default::default()),
+            Shape::U32 => Self::U32(Default::default()),
+            Shape::U64 => Self::U64(Default::default()),
+            Shape::U128 => Self::U128(Default::default()),
+            Shape::Union => {
+                let len = read_len(input)?;
+                if len > 256 {
+                    return err_kind(
+                        ErrorKind::Unsupported,
+                        "unions with more than 256 alternatives are unsupported",
+                    );
+                }
+                let explicit = read_len(input)?;
+                Self::Union(Box::new(UnionDecoder {
+                    explicit,
+                    alternatives: children(input, len)?,
+                    ..Default::default()
+                }))
+            }
+        })
+    }
+}
+
 struct DecoderWrapper<'a, 'de> {
     decoder: &'a mut SerdeDecoder<'de>,
     input: &'a mut &'de [u8],
+    self_describing: bool,
 }
 
+/// Takes the variant to create followed by other variants with the same decoder that are also
+/// accepted, e.g. `specify!(self, U64 | I64 | F64)`.
 macro_rules! specify {
-    ($self:ident, $variant:ident) => {
+    ($self:ident, $variant:ident $(| $other:ident)*) => {
         {
             match &mut $self.decoder {
                 // Check if it's already the correct decoder. This results in 1 branch in the hot path.
-                SerdeDecoder::$variant(_) => (),
+                SerdeDecoder::$variant(_) $(| SerdeDecoder::$other(_))* => (),
                 _ => {
                     // Either create the correct decoder if unspecified or diverge via panic/error.
                     #[cold]
-                    fn cold<'de>(decoder: &mut SerdeDecoder<'de>, input: &mut &'de[u8]) -> Result<()> {
+                    fn cold<'a, 'de>(
+                        decoder: &'a mut SerdeDecoder<'de>,
+                        input: &mut &'de [u8],
+                        self_describing: bool,
+                    ) -> Result<&'a mut SerdeDecoder<'de>> {
+                        if self_describing {
+                            // The header already specified every decoder, so it's either a union or
+                            // the types don't match.
+                            if let SerdeDecoder::Union(union) = decoder {
+                                let alternative = union.decode();
+                                if matches!(
+                                    alternative,
+                                    SerdeDecoder::$variant(_) $(| SerdeDecoder::$other(_))*
+                                ) {
+                                    return Ok(alternative);
+                                }
+                            }
+                            return err("type doesn't match self describing header");
+                        }
                         let &mut SerdeDecoder::Unspecified { length } = decoder else {
                             type_changed!();
                         };
                         *decoder = SerdeDecoder::$variant(Default::default());
-                        decoder.populate(input, length)
+                        decoder.populate(input, length)?;
+                        Ok(decoder)
                     }
-                    cold(&mut *$self.decoder, &mut *$self.input)?;
+                    $self.decoder = cold($self.decoder, &mut *$self.input, $self.self_describing)?;
                 }
             }
-            let SerdeDecoder::$variant(d) = &mut *$self.decoder else {
+            match &mut *$self.decoder {
+                SerdeDecoder::$variant(d) $(| SerdeDecoder::$other(d))* => d,
                 // Safety: `cold` gets called when decoder isn't the correct decoder. `cold` either
-                // errors or sets lazy to the correct decoder.
-                unsafe { std::hint::unreachable_unchecked() };
-            };
-            d
+                // errors or returns the correct decoder.
+                _ => unsafe { std::hint::unreachable_unchecked() },
+            }
         }
     };
 }
 
+impl<'a> DecoderWrapper<'a, '_> {
+    /// Self describing only: units don't have a decoder, but they can be in a union.
+    fn decode_unit(self) -> Result<()> {
+        let decoder = match self.decoder {
+            SerdeDecoder::Union(union) => union.decode(),
+            decoder => decoder,
+        };
+        if matches!(
+            decoder,
+            SerdeDecoder::Unpopulated | SerdeDecoder::Unspecified { .. }
+        ) {
+            Ok(())
+        } else {
+            err("type doesn't match self describing header")
+        }
+    }
+
+    /// Decodes a seq of u8 all at once instead of 1 element at a time. Only `deserialize_bytes`
+    /// and `deserialize_byte_buf` (e.g. with `serde_bytes`) can use it. A plain `Vec<u8>` calls
+    /// `deserialize_seq`, whose visitor only accepts elements one at a time through `SeqAccess`.
+    #[inline(always)]
+    fn decode_bytes(mut self) -> Result<&'a [u8]> {
+        let (length_decoder, decoder) = specify!(self, Seq);
+        let len = length_decoder.decode();
+        if len == 0 {
+            return Ok(&[]); // Don't specify elements since deserialize_seq wouldn't either.
+        }
+        let mut elements = DecoderWrapper {
+            decoder,
+            input: &mut *self.input,
+            self_describing: self.self_describing,
+        };
+        let d = specify!(elements, U8 | I8);
+        // Safety: populate guarantees there are `len` elements.
+        Ok(unsafe { d.decode_bytes(len) })
+    }
+}
+
 macro_rules! impl_de {
-    ($deserialize:ident, $visit:ident, $t:ty, $variant:ident) => {
+    ($deserialize:ident, $visit:ident, $t:ty, $($variant:ident)|+) => {
         #[inline(always)]
         fn $deserialize<V>(mut self, v: V) -> Result<V::Value>
         where
             V: Visitor<'de>,
         {
-            v.$visit(specify!(self, $variant).decode())
+            v.$visit(specify!(self, $($variant)|+).decode())
         }
     };
 }
@@ -152,30 +387,77 @@ macro_rules! impl_de {
 impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _: V) -> Result<V::Value>
+    fn deserialize_any<V>(self, v: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        err("deserialize_any is not supported")
+        if !self.self_describing {
+            return err_kind(ErrorKind::Unsupported, "deserialize_any is not supported");
+        }
+        match &*self.decoder {
+            SerdeDecoder::Bool(_) => self.deserialize_bool(v),
+            SerdeDecoder::Char(_) => self.deserialize_char(v),
+            SerdeDecoder::Enum(_) => {
+                // Enums are visited as a map with 1 entry: {variant_index: value}.
+                let (variant_index, value) = self.variant_seed(PhantomData::<u32>)?;
+                v.visit_map(VariantMap {
+                    variant_index: Some(variant_index),
+                    value: Some(value),
+                })
+            }
+            SerdeDecoder::F32(_) => self.deserialize_f32(v),
+            SerdeDecoder::F64(_) => self.deserialize_f64(v),
+            SerdeDecoder::I8(_) => self.deserialize_i8(v),
+            SerdeDecoder::I16(_) => self.deserialize_i16(v),
+            SerdeDecoder::I32(_) => self.deserialize_i32(v),
+            SerdeDecoder::I64(_) => self.deserialize_i64(v),
+            SerdeDecoder::I128(_) => self.deserialize_i128(v),
+            SerdeDecoder::Map(_) => self.deserialize_map(v),
+            SerdeDecoder::Option(_) => self.deserialize_option(v),
+            SerdeDecoder::Seq(_) => self.deserialize_seq(v),
+            SerdeDecoder::Str(_) => self.deserialize_str(v),
+            SerdeDecoder::Tuple(d) => {
+                let len = d.len();
+                self.deserialize_tuple(len, v)
+            }
+            SerdeDecoder::U8(_) => self.deserialize_u8(v),
+            SerdeDecoder::U16(_) => self.deserialize_u16(v),
+            SerdeDecoder::U32(_) => self.deserialize_u32(v),
+            SerdeDecoder::U64(_) => self.deserialize_u64(v),
+            SerdeDecoder::U128(_) => self.deserialize_u128(v),
+            SerdeDecoder::Union(_) => {
+                let SerdeDecoder::Union(union) = self.decoder else {
+                    unreachable!();
+                };
+                DecoderWrapper {
+                    decoder: union.decode(),
+                    input: self.input,
+                    self_describing: true,
+                }
+                .deserialize_any(v)
+            }
+            // The header only leaves units unspecified.
+            SerdeDecoder::Unpopulated | SerdeDecoder::Unspecified { .. } => v.visit_unit(),
+        }
     }
 
     // Use native decoders.
     impl_de!(deserialize_bool, visit_bool, bool, Bool);
     impl_de!(deserialize_f32, visit_f32, f32, F32);
-    impl_de!(deserialize_u8, visit_u8, u8, U8);
-    impl_de!(deserialize_u16, visit_u16, u16, U16);
-    impl_de!(deserialize_u32, visit_u32, u32, U32);
-    impl_de!(deserialize_u64, visit_u64, u64, U64);
-    impl_de!(deserialize_u128, visit_u128, u128, U128);
+    impl_de!(deserialize_u8, visit_u8, u8, U8 | I8);
+    impl_de!(deserialize_u16, visit_u16, u16, U16 | I16);
+    impl_de!(deserialize_u32, visit_u32, u32, U32 | I32 | Char);
+    impl_de!(deserialize_u64, visit_u64, u64, U64 | I64 | F64);
+    impl_de!(deserialize_u128, visit_u128, u128, U128 | I128);
     impl_de!(deserialize_str, visit_borrowed_str, &str, Str);
 
     // IntDecoder<unsigned> works on signed integers/f64 (but not chars).
-    impl_de!(deserialize_i8, visit_i8, i8, U8);
-    impl_de!(deserialize_i16, visit_i16, i16, U16);
-    impl_de!(deserialize_i32, visit_i32, i32, U32);
-    impl_de!(deserialize_i64, visit_i64, i64, U64);
-    impl_de!(deserialize_i128, visit_i128, i128, U128);
-    impl_de!(deserialize_f64, visit_f64, f64, U64);
+    impl_de!(deserialize_i8, visit_i8, i8, I8 | U8);
+    impl_de!(deserialize_i16, visit_i16, i16, I16 | U16);
+    impl_de!(deserialize_i32, visit_i32, i32, I32 | U32 | Char);
+    impl_de!(deserialize_i64, visit_i64, i64, I64 | U64 | F64);
+    impl_de!(deserialize_i128, visit_i128, i128, I128 | U128);
+    impl_de!(deserialize_f64, visit_f64, f64, F64 | U64 | I64);
 
     #[inline(always)]
     fn deserialize_char<V>(self, v: V) -> Result<V::Value>
@@ -198,7 +480,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_byte_buf(v) // TODO avoid allocation.
+        v.visit_bytes(self.decode_bytes()?)
     }
 
     #[inline(always)]
@@ -206,7 +488,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     where
         V: Visitor<'de>,
     {
-        v.visit_byte_buf(<Vec<u8>>::deserialize(self)?)
+        v.visit_byte_buf(self.decode_bytes()?.to_vec())
     }
 
     #[inline(always)]
@@ -214,7 +496,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     where
         V: Visitor<'de>,
     {
-        let (variant_decoder, decoders) = specify!(self, Enum);
+        let (variant_decoder, decoders) = specify!(self, Option | Enum);
         let variant_index = variant_decoder.decode();
         // Safety: populate guarantees `variant_decoder.max_variant_index() < decoders.len()`.
         let decoder = unsafe { decoders.get_unchecked_mut(variant_index as usize) };
@@ -224,8 +506,9 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
             1 => v.visit_some(DecoderWrapper {
                 decoder,
                 input: &mut *self.input,
+                self_describing: self.self_describing,
             }),
-            _ => err("invalid option"),
+            _ => err_kind(ErrorKind::InvalidVariant, "invalid option"),
         }
     }
 
@@ -234,6 +517,9 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     where
         V: Visitor<'de>,
     {
+        if self.self_describing {
+            self.decode_unit()?;
+        }
         v.visit_unit()
     }
 
@@ -242,7 +528,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     where
         V: Visitor<'de>,
     {
-        v.visit_unit()
+        self.deserialize_unit(v)
     }
 
     #[inline(always)]
@@ -280,6 +566,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
                         DecoderWrapper {
                             decoder: &mut *self.wrapper.decoder,
                             input: &mut *self.wrapper.input,
+                            self_describing: self.wrapper.self_describing,
                         },
                     )?))
                 } else {
@@ -296,6 +583,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
             wrapper: DecoderWrapper {
                 decoder,
                 input: self.input,
+                self_describing: self.self_describing,
             },
             len,
         })
@@ -306,11 +594,12 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
     where
         V: Visitor<'de>,
     {
-        // Fast path: avoid overhead of tuple for 1 element.
-        if tuple_len == 1 {
+        // Fast path: avoid overhead of tuple for 1 element (see serialize_tuple).
+        if tuple_len == 1 && !self.self_describing {
             return v.visit_seq(Access {
                 decoders: std::slice::from_mut(self.decoder),
                 input: self.input,
+                self_describing: self.self_describing,
                 index: 0,
             });
         }
@@ -320,18 +609,34 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
             SerdeDecoder::Tuple(_) => (),
             _ => {
                 #[cold]
-                fn cold<'de>(
-                    decoder: &mut SerdeDecoder<'de>,
+                fn cold<'a, 'de>(
+                    decoder: &'a mut SerdeDecoder<'de>,
                     input: &mut &'de [u8],
+                    self_describing: bool,
                     tuple_len: usize,
-                ) -> Result<()> {
+                ) -> Result<&'a mut SerdeDecoder<'de>> {
+                    if self_describing {
+                        if let SerdeDecoder::Union(union) = decoder {
+                            let alternative = union.decode();
+                            if matches!(alternative, SerdeDecoder::Tuple(_)) {
+                                return Ok(alternative);
+                            }
+                        }
+                        return err("type doesn't match self describing header");
+                    }
                     let &mut SerdeDecoder::Unspecified { length } = decoder else {
                         type_changed!();
                     };
                     *decoder = SerdeDecoder::Tuple(default_box_slice(tuple_len));
-                    decoder.populate(input, length)
+                    decoder.populate(input, length)?;
+                    Ok(decoder)
                 }
-                cold(&mut *self.decoder, &mut *self.input, tuple_len)?;
+                self.decoder = cold(
+                    self.decoder,
+                    &mut *self.input,
+                    self.self_describing,
+                    tuple_len,
+                )?;
             }
         }
         let SerdeDecoder::Tuple(decoders) = &mut *self.decoder else {
@@ -339,12 +644,16 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
             unsafe { std::hint::unreachable_unchecked() };
         };
         if decoders.len() != tuple_len {
+            if self.self_describing {
+                return err("type doesn't match self describing header");
+            }
             type_changed!(); // Removes multiple bounds checks.
         }
 
         struct Access<'a, 'de> {
             decoders: &'a mut [SerdeDecoder<'de>],
             input: &'a mut &'de [u8],
+            self_describing: bool,
             index: usize,
         }
         impl<'de> SeqAccess<'de> for Access<'_, 'de> {
@@ -362,6 +671,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
                         DecoderWrapper {
                             decoder,
                             input: &mut *self.input,
+                            self_describing: self.self_describing,
                         },
                     )?))
                 } else {
@@ -378,6 +688,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
         v.visit_seq(Access {
             decoders,
             input: &mut *self.input,
+            self_describing: self.self_describing,
             index: 0,
         })
     }
@@ -400,6 +711,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
         struct Access<'a, 'de> {
             decoders: &'a mut (SerdeDecoder<'de>, SerdeDecoder<'de>),
             input: &'a mut &'de [u8],
+            self_describing: bool,
             len: usize,
             key_deserialized: bool,
         }
@@ -423,6 +735,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
                         DecoderWrapper {
                             decoder: &mut self.decoders.0,
                             input: &mut *self.input,
+                            self_describing: self.self_describing,
                         },
                     )?))
                 } else {
@@ -446,6 +759,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
                     DecoderWrapper {
                         decoder: &mut self.decoders.1,
                         input: &mut *self.input,
+                        self_describing: self.self_describing,
                     },
                 )
             }
@@ -460,6 +774,7 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
         v.visit_map(Access {
             decoders,
             input: self.input,
+            self_describing: self.self_describing,
             len,
             key_deserialized: false, // No keys have been deserialized yet, so next_value_seed can't be called.
         })
@@ -491,18 +806,30 @@ impl<'de> Deserializer<'de> for DecoderWrapper<'_, 'de> {
         v.visit_enum(self)
     }
 
-    fn deserialize_identifier<V>(self, _: V) -> Result<V::Value>
+    fn deserialize_identifier<V>(self, v: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        err("deserialize_identifier is not supported")
+        if !self.self_describing {
+            return err_kind(
+                ErrorKind::Unsupported,
+                "deserialize_identifier is not supported",
+            );
+        }
+        self.deserialize_any(v)
     }
 
-    fn deserialize_ignored_any<V>(self, _: V) -> Result<V::Value>
+    fn deserialize_ignored_any<V>(self, v: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        err("deserialize_ignored_any is not supported")
+        if !self.self_describing {
+            return err_kind(
+                ErrorKind::Unsupported,
+                "deserialize_ignored_any is not supported",
+            );
+        }
+        self.deserialize_any(v)
     }
 
     #[inline(always)]
@@ -520,7 +847,7 @@ impl<'a, 'de> EnumAccess<'de> for DecoderWrapper<'a, 'de> {
     where
         V: DeserializeSeed<'de>,
     {
-        let (variant_decoder, decoders) = specify!(self, Enum);
+        let (variant_decoder, decoders) = specify!(self, Enum | Option);
         let variant_index = variant_decoder.decode();
         // Safety: populate guarantees `variant_decoder.max_variant_index() < decoders.len()`.
         let decoder = unsafe { decoders.get_unchecked_mut(variant_index as usize) };
@@ -532,11 +859,47 @@ impl<'a, 'de> EnumAccess<'de> for DecoderWrapper<'a, 'de> {
             DecoderWrapper {
                 decoder,
                 input: &mut *self.input,
+                self_describing: self.self_describing,
             },
         ))
     }
 }
 
+/// Visits an enum as a map with 1 entry: {variant_index: value}. Used by deserialize_any.
+struct VariantMap<'a, 'de> {
+    variant_index: Option<u32>,
+    value: Option<DecoderWrapper<'a, 'de>>,
+}
+
+impl<'de> MapAccess<'de> for VariantMap<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        self.variant_index
+            .take()
+            .map(|variant_index| seed.deserialize(variant_index.into_deserializer()))
+            .transpose()
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        assert!(
+            self.variant_index.is_none(),
+            "next_value_seed before next_key_seed"
+        );
+        seed.deserialize(self.value.take().expect("next_value_seed called twice"))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.variant_index.is_some() as usize)
+    }
+}
+
 impl<'de> VariantAccess<'de> for DecoderWrapper<'_, 'de> {
     type Error = Error;
 
@@ -637,6 +1000,198 @@ mod tests {
         test!(vec![(None, 3), (Some(4), 5)], Vec<(Option<u8>, u8)>);
     }
 
+    #[test]
+    fn self_describing() {
+        use serde::{Deserialize, Serialize};
+
+        macro_rules! test {
+            ($v:expr, $t:ty) => {
+                let v = $v;
+                let ser = crate::serialize_self_describing::<$t>(&v).unwrap();
+                println!("{:<24} {ser:?}", stringify!($t));
+                assert_eq!(v, crate::deserialize_self_describing::<$t>(&ser).unwrap());
+            };
+        }
+        test!(5, u8);
+        test!(-5, i64);
+        test!(1.5, f64);
+        test!('a', char);
+        test!(vec![None, Some(1u8)], Vec<Option<u8>>);
+        test!((1u8,), (u8,));
+        test!(vec![(vec![], 0u8), (vec![true], 1u8)], Vec<(Vec<bool>, u8)>);
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(tag = "t")]
+        enum Internal {
+            A,
+            B { x: u8, y: String },
+            C(BTreeMap<String, i32>),
+        }
+        let mut map = BTreeMap::new();
+        map.insert("a".to_owned(), -1);
+        test!(
+            vec![
+                Internal::A,
+                Internal::B {
+                    x: 5,
+                    y: "abc".to_owned()
+                },
+                Internal::C(map)
+            ],
+            Vec<Internal>
+        );
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        #[serde(untagged)]
+        enum Untagged {
+            Nothing,
+            Number(i16),
+            Text(String),
+            Pair(char, f64),
+        }
+        test!(
+            vec![
+                Untagged::Number(-300),
+                Untagged::Text("abc".to_owned()),
+                Untagged::Nothing,
+                Untagged::Pair('a', -0.5),
+                Untagged::Number(5),
+            ],
+            Vec<Untagged>
+        );
+
+        // Types that don't match the header are errors instead of panics.
+        let ser = crate::serialize_self_describing(&vec![1u8, 2u8]).unwrap();
+        assert!(crate::deserialize_self_describing::<Vec<bool>>(&ser).is_err());
+        assert!(crate::deserialize_self_describing::<(u8, u8)>(&ser).is_err());
+        assert!(crate::deserialize::<Vec<u8>>(&ser).is_err());
+
+        // A tuple of `self.0` units, so each length is a different type.
+        struct Shape(usize);
+        impl Serialize for Shape {
+            fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeTuple;
+                let mut t = s.serialize_tuple(self.0)?;
+                for _ in 0..self.0 {
+                    t.serialize_element(&())?;
+                }
+                t.end()
+            }
+        }
+        let shapes = |n| (0..n).map(Shape).collect::<Vec<_>>();
+        assert!(crate::serialize_self_describing(&shapes(256)).is_ok());
+        assert!(crate::serialize_self_describing(&shapes(257)).is_err());
+    }
+
+    #[test]
+    fn borrowed_str() {
+        use serde::{Deserialize, Serialize};
+        use std::borrow::Cow;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Borrowed<'a> {
+            str: &'a str,
+            #[serde(borrow)]
+            cow: Cow<'a, str>,
+        }
+
+        let v = Borrowed {
+            str: "abc",
+            cow: "def".into(),
+        };
+        let bytes = crate::serialize(&v).unwrap();
+        let decoded: Borrowed = crate::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, v);
+
+        let within = |s: &str| bytes.as_ptr_range().contains(&s.as_ptr());
+        assert!(within(decoded.str));
+        assert!(matches!(&decoded.cow, Cow::Borrowed(s) if within(s)));
+    }
+
+    #[test]
+    fn bytes() {
+        #[derive(Debug, PartialEq)]
+        struct Bytes(Vec<u8>);
+        impl serde::Serialize for Bytes {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(&self.0)
+            }
+        }
+        impl<'de> serde::Deserialize<'de> for Bytes {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = Bytes;
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("bytes")
+                    }
+                    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                        Ok(Bytes(v))
+                    }
+                }
+                deserializer.deserialize_byte_buf(Visitor)
+            }
+        }
+
+        // Empty sequences come first to make sure the element type is learned at the same time
+        // as with a seq of u8.
+        let values = [vec![], vec![1u8, 2, 3], vec![255; 100]];
+        let vecs: Vec<_> = values.iter().map(|v| (v.clone(), 5u16)).collect();
+        let bytes: Vec<_> = values.iter().map(|v| (Bytes(v.clone()), 5u16)).collect();
+
+        let ser = crate::serialize(&bytes).unwrap();
+        assert_eq!(ser, crate::serialize(&vecs).unwrap());
+        assert_eq!(
+            ser,
+            crate::serialize(
+                &vecs
+                    .iter()
+                    .map(|(v, n)| (v.as_slice(), *n))
+                    .collect::<Vec<_>>()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            bytes,
+            crate::deserialize::<Vec<(Bytes, u16)>>(&ser).unwrap()
+        );
+        assert_eq!(
+            vecs,
+            crate::deserialize::<Vec<(Vec<u8>, u16)>>(&ser).unwrap()
+        );
+    }
+
+    #[test]
+    fn bytes_wrong_size_hint() {
+        // Serializes its bytes with an iterator that claims to have `self.1` of them.
+        struct Lying(Vec<u8>, usize);
+        impl serde::Serialize for Lying {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                struct Iter<'a>(std::slice::Iter<'a, u8>, usize);
+                impl<'a> Iterator for Iter<'a> {
+                    type Item = &'a u8;
+                    fn next(&mut self) -> Option<Self::Item> {
+                        self.0.next()
+                    }
+                    fn size_hint(&self) -> (usize, Option<usize>) {
+                        (self.1, Some(self.1))
+                    }
+                }
+                serializer.collect_seq(Iter(self.0.iter(), self.1))
+            }
+        }
+
+        for (v, hint) in [
+            (vec![1u8, 2, 3], 1),
+            (vec![1, 2, 3], 10),
+            (vec![], 5),
+            (vec![4], 0),
+        ] {
+            let expected = crate::serialize(&v).unwrap();
+            assert_eq!(crate::serialize(&Lying(v, hint)).unwrap(), expected);
+        }
+    }
+
     #[test]
     #[should_panic = "next_value_seed before next_key_seed"]
     fn map_incorrect_len_values() {
@@ -647,6 +1202,7 @@ mod tests {
         let w = super::DecoderWrapper {
             decoder: &mut super::SerdeDecoder::Unspecified { length: 1 },
             input: &mut input.as_slice(),
+            self_describing: false,
         };
 
         struct Visitor;
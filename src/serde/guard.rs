@@ -1,11 +1,11 @@
 use crate::coder::Result;
-use crate::error::err;
+use crate::error::{err_kind, ErrorKind};
 
 pub const ZST_LIMIT: usize = 1 << 16;
 
 fn check_zst_len(len: usize) -> Result<()> {
     if len > ZST_LIMIT {
-        err("too many zero sized types")
+        err_kind(ErrorKind::LimitExceeded, "too many zero sized types")
     } else {
         Ok(())
     }
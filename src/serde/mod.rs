@@ -4,6 +4,7 @@ use std::fmt::Display;
 mod de;
 mod guard;
 mod ser;
+mod shape;
 mod variant;
 
 pub use de::*;
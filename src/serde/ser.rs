@@ -1,9 +1,12 @@
 use crate::bool::BoolEncoder;
-use crate::coder::{Buffer, Encoder, Result};
-use crate::error::{err, error, Error};
+use crate::buffer::non_static_type_id;
+use crate::coder::{Encoder, EncoderBuffer, Result};
+use crate::error::{err_kind, error_kind, Error, ErrorKind};
 use crate::f32::F32Encoder;
+use crate::fast::{PushUnchecked, VecImpl};
 use crate::int::IntEncoder;
 use crate::length::LengthEncoder;
+use crate::serde::shape::{write_len, Shape};
 use crate::serde::variant::VariantEncoder;
 use crate::serde::{default_box_slice, get_mut_or_resize, type_changed};
 use crate::str::StrEncoder;
@@ -31,6 +34,7 @@ mod inner {
         t.serialize(EncoderWrapper {
             lazy: &mut lazy,
             index_alloc: &mut index_alloc,
+            self_describing: false,
         })?;
 
         // If we just wrote out the buffers in field order we wouldn't be able to deserialize them
@@ -50,14 +54,41 @@ mod inner {
         }
         Ok(bytes)
     }
+
+    /// Like [`serialize`], but prefixes the output with a header describing its types. This allows
+    /// [`deserialize_self_describing`][`crate::deserialize_self_describing`] to support
+    /// [`Deserializer::deserialize_any`][`serde::Deserializer::deserialize_any`] which is required
+    /// by types such as internally tagged enums (`#[serde(tag = "t")]`).
+    ///
+    /// **Warning:** The format is incompatible with [`deserialize`][`crate::deserialize`] and
+    /// subject to change between major versions.
+    pub fn serialize_self_describing<T: Serialize + ?Sized>(t: &T) -> Result<Vec<u8>, Error> {
+        let mut lazy = LazyEncoder::Unspecified {
+            reserved: NonZeroUsize::new(1),
+        };
+        t.serialize(EncoderWrapper {
+            lazy: &mut lazy,
+            index_alloc: &mut 0,
+            self_describing: true,
+        })?;
+
+        // Unlike serialize, the deserializer knows all the types up front from the header, so the
+        // buffers can be written in the same order as the header instead of being reordered.
+        let mut bytes = vec![];
+        lazy.write_shape(&mut bytes);
+        lazy.collect_pre_order(&mut bytes);
+        Ok(bytes)
+    }
 }
-pub use inner::serialize;
+pub use inner::{serialize, serialize_self_describing};
 
 #[derive(Debug)]
 enum SpecifiedEncoder {
     Bool(BoolEncoder),
+    Char(IntEncoder<u32>),
     Enum((VariantEncoder, Vec<LazyEncoder>)), // (variants, values)
     F32(F32Encoder),
+    F64(IntEncoder<u64>),
     // Serialize needs separate signed integer encoders to be able to pack [0, -1, 0, -1, 0, -1].
     I8(IntEncoder<i8>),
     I16(IntEncoder<i16>),
@@ -65,6 +96,7 @@ enum SpecifiedEncoder {
     I64(IntEncoder<i64>),
     I128(IntEncoder<i128>),
     Map((LengthEncoder, Box<(LazyEncoder, LazyEncoder)>)), // (lengths, (keys, values))
+    Option((VariantEncoder, Vec<LazyEncoder>)),            // Same as Enum.
     Seq((LengthEncoder, Box<LazyEncoder>)),                // (lengths, values)
     Str(StrEncoder),
     Tuple(Box<[LazyEncoder]>), // [field0, field1, ..]
@@ -73,17 +105,24 @@ enum SpecifiedEncoder {
     U32(IntEncoder<u32>),
     U64(IntEncoder<u64>),
     U128(IntEncoder<u128>),
+    // Only specified when self describing so units can be told apart from other types in a Union.
+    Unit(()),
+    // Only when self describing: values of different types in the same place (e.g. internally
+    // tagged enums). Values encoded before it became a Union are implicitly the first alternative.
+    Union((VariantEncoder, Vec<LazyEncoder>)), // (alternative indices of later values, alternatives)
 }
 
 impl SpecifiedEncoder {
     fn reserve(&mut self, additional: NonZeroUsize) {
         match self {
             Self::Bool(v) => v.reserve(additional),
-            Self::Enum(v) => {
+            Self::Char(v) => v.reserve(additional),
+            Self::Enum(v) | Self::Option(v) => {
                 v.0.reserve(additional);
                 // We don't know the variants of the enums, so we can't reserve more.
             }
             Self::F32(v) => v.reserve(additional),
+            Self::F64(v) => v.reserve(additional),
             Self::I8(v) => v.reserve(additional),
             Self::I16(v) => v.reserve(additional),
             Self::I32(v) => v.reserve(additional),
@@ -106,6 +145,11 @@ impl SpecifiedEncoder {
             Self::U32(v) => v.reserve(additional),
             Self::U64(v) => v.reserve(additional),
             Self::U128(v) => v.reserve(additional),
+            Self::Unit(_) => (),
+            Self::Union(v) => {
+                v.0.reserve(additional);
+                // We don't know the types of the values, so we can't reserve more.
+            }
         }
     }
 }
@@ -128,16 +172,18 @@ impl Default for LazyEncoder {
 }
 
 impl LazyEncoder {
-    fn reorder<'a>(&'a mut self, buffers: &mut [Option<&'a mut dyn Buffer>]) {
+    fn reorder<'a>(&'a mut self, buffers: &mut [Option<&'a mut dyn EncoderBuffer>]) {
         match self {
             Self::Specified { specified, index } => {
                 buffers[*index] = Some(match specified {
                     SpecifiedEncoder::Bool(v) => v,
-                    SpecifiedEncoder::Enum(v) => {
+                    SpecifiedEncoder::Char(v) => v,
+                    SpecifiedEncoder::Enum(v) | SpecifiedEncoder::Option(v) => {
                         v.1.iter_mut().for_each(|v| v.reorder(buffers));
                         &mut v.0
                     }
                     SpecifiedEncoder::F32(v) => v,
+                    SpecifiedEncoder::F64(v) => v,
                     SpecifiedEncoder::I8(v) => v,
                     SpecifiedEncoder::I16(v) => v,
                     SpecifiedEncoder::I32(v) => v,
@@ -162,12 +208,110 @@ impl LazyEncoder {
                     SpecifiedEncoder::U32(v) => v,
                     SpecifiedEncoder::U64(v) => v,
                     SpecifiedEncoder::U128(v) => v,
+                    SpecifiedEncoder::Unit(_) | SpecifiedEncoder::Union(_) => {
+                        unreachable!("only self describing")
+                    }
                 });
             }
             Self::Unspecified { .. } => (),
         }
     }
 
+    /// Writes the [`Shape`] of `self` and its children in pre-order. Unspecified encoders are
+    /// either units or were never used (so have length 0).
+    fn write_shape(&self, out: &mut Vec<u8>) {
+        let Self::Specified { specified, .. } = self else {
+            Shape::Unit.write(out);
+            return;
+        };
+        let (shape, children): (_, &[LazyEncoder]) = match specified {
+            SpecifiedEncoder::Bool(_) => (Shape::Bool, &[]),
+            SpecifiedEncoder::Char(_) => (Shape::Char, &[]),
+            SpecifiedEncoder::Enum(v) => (Shape::Enum, &v.1),
+            SpecifiedEncoder::F32(_) => (Shape::F32, &[]),
+            SpecifiedEncoder::F64(_) => (Shape::F64, &[]),
+            SpecifiedEncoder::I8(_) => (Shape::I8, &[]),
+            SpecifiedEncoder::I16(_) => (Shape::I16, &[]),
+            SpecifiedEncoder::I32(_) => (Shape::I32, &[]),
+            SpecifiedEncoder::I64(_) => (Shape::I64, &[]),
+            SpecifiedEncoder::I128(_) => (Shape::I128, &[]),
+            SpecifiedEncoder::Map(v) => {
+                Shape::Map.write(out);
+                v.1 .0.write_shape(out);
+                v.1 .1.write_shape(out);
+                return;
+            }
+            SpecifiedEncoder::Option(v) => (Shape::Option, &v.1),
+            SpecifiedEncoder::Seq(v) => {
+                Shape::Seq.write(out);
+                v.1.write_shape(out);
+                return;
+            }
+            SpecifiedEncoder::Str(_) => (Shape::Str, &[]),
+            SpecifiedEncoder::Tuple(v) => (Shape::Tuple, v),
+            SpecifiedEncoder::U8(_) => (Shape::U8, &[]),
+            SpecifiedEncoder::U16(_) => (Shape::U16, &[]),
+            SpecifiedEncoder::U32(_) => (Shape::U32, &[]),
+            SpecifiedEncoder::U64(_) => (Shape::U64, &[]),
+            SpecifiedEncoder::U128(_) => (Shape::U128, &[]),
+            SpecifiedEncoder::Unit(_) => (Shape::Unit, &[]),
+            SpecifiedEncoder::Union(v) => (Shape::Union, &v.1),
+        };
+        shape.write(out);
+        if matches!(
+            shape,
+            Shape::Enum | Shape::Option | Shape::Tuple | Shape::Union
+        ) {
+            write_len(children.len(), out);
+        }
+        if let SpecifiedEncoder::Union(v) = specified {
+            write_len(v.0.len(), out); // Number of values with an explicit alternative index.
+        }
+        children.iter().for_each(|v| v.write_shape(out));
+    }
+
+    /// Collects the buffers of `self` and its children in pre-order (the order of
+    /// [`Self::write_shape`]).
+    fn collect_pre_order(&mut self, out: &mut Vec<u8>) {
+        let Self::Specified { specified, .. } = self else {
+            return;
+        };
+        match specified {
+            SpecifiedEncoder::Bool(v) => v.collect_into(out),
+            SpecifiedEncoder::Char(v) => v.collect_into(out),
+            SpecifiedEncoder::Enum(v)
+            | SpecifiedEncoder::Option(v)
+            | SpecifiedEncoder::Union(v) => {
+                v.0.collect_into(out);
+                v.1.iter_mut().for_each(|v| v.collect_pre_order(out));
+            }
+            SpecifiedEncoder::F32(v) => v.collect_into(out),
+            SpecifiedEncoder::F64(v) => v.collect_into(out),
+            SpecifiedEncoder::I8(v) => v.collect_into(out),
+            SpecifiedEncoder::I16(v) => v.collect_into(out),
+            SpecifiedEncoder::I32(v) => v.collect_into(out),
+            SpecifiedEncoder::I64(v) => v.collect_into(out),
+            SpecifiedEncoder::I128(v) => v.collect_into(out),
+            SpecifiedEncoder::Map(v) => {
+                v.0.collect_into(out);
+                v.1 .0.collect_pre_order(out);
+                v.1 .1.collect_pre_order(out);
+            }
+            SpecifiedEncoder::Seq(v) => {
+                v.0.collect_into(out);
+                v.1.collect_pre_order(out);
+            }
+            SpecifiedEncoder::Str(v) => v.collect_into(out),
+            SpecifiedEncoder::Tuple(v) => v.iter_mut().for_each(|v| v.collect_pre_order(out)),
+            SpecifiedEncoder::U8(v) => v.collect_into(out),
+            SpecifiedEncoder::U16(v) => v.collect_into(out),
+            SpecifiedEncoder::U32(v) => v.collect_into(out),
+            SpecifiedEncoder::U64(v) => v.collect_into(out),
+            SpecifiedEncoder::U128(v) => v.collect_into(out),
+            SpecifiedEncoder::Unit(_) => (),
+        }
+    }
+
     /// OLD COMMENT:
     /// Only reserves if the type is unspecified to save time. Speeds up large 1 time collections
     /// without slowing down many small collections too much. Takes a `usize` instead of a
@@ -187,20 +331,35 @@ impl LazyEncoder {
     }
 }
 
+/// Takes the variant to create followed by other variants with the same encoder that are also
+/// accepted, e.g. `specify!(self, F64 | U64)`. Returns from the enclosing function with an error if
+/// a self describing union has too many alternatives.
 macro_rules! specify {
-    ($wrapper:ident, $variant:ident) => {{
-        let lazy = &mut *$wrapper.lazy;
+    ($wrapper:ident, $variant:ident $(| $other:ident)*) => {{
+        let mut lazy = &mut *$wrapper.lazy;
         match lazy {
             // Check if it's already the correct encoder. This results in 1 branch in the hot path.
-            LazyEncoder::Specified { specified: SpecifiedEncoder::$variant(_), .. } => (),
+            LazyEncoder::Specified {
+                specified: SpecifiedEncoder::$variant(_) $(| SpecifiedEncoder::$other(_))*,
+                ..
+            } => (),
             _ => {
                 // Either create the correct encoder if unspecified or panic if we already have an
-                // encoder since it must be a different type.
+                // encoder since it must be a different type (unless self describing).
                 #[cold]
-                fn cold(
-                    me: &mut LazyEncoder,
+                fn cold<'a>(
+                    mut me: &'a mut LazyEncoder,
                     index_alloc: &mut usize,
-                ) {
+                    self_describing: bool,
+                ) -> Result<&'a mut LazyEncoder> {
+                    if self_describing && matches!(me, LazyEncoder::Specified { .. }) {
+                        me = union_alternative(me, |s| {
+                            matches!(s, SpecifiedEncoder::$variant(_) $(| SpecifiedEncoder::$other(_))*)
+                        })?;
+                        if matches!(me, LazyEncoder::Specified { .. }) {
+                            return Ok(me);
+                        }
+                    }
                     let &mut LazyEncoder::Unspecified { reserved } = me else {
                         type_changed!();
                     };
@@ -214,46 +373,155 @@ macro_rules! specify {
                     if let Some(reserved) = reserved {
                         specified.reserve(reserved);
                     }
+                    Ok(me)
                 }
-                cold(lazy, &mut *$wrapper.index_alloc);
+                lazy = cold(lazy, &mut *$wrapper.index_alloc, $wrapper.self_describing)?;
             }
         }
-        let LazyEncoder::Specified { specified: SpecifiedEncoder::$variant(b), .. } = lazy else {
+        let LazyEncoder::Specified {
+            specified: SpecifiedEncoder::$variant(b) $(| SpecifiedEncoder::$other(b))*,
+            ..
+        } = lazy else {
             // Safety: `cold` gets called when lazy isn't the correct encoder. `cold` either diverges
-            // or sets lazy to the correct encoder.
+            // or returns the correct encoder.
             unsafe { std::hint::unreachable_unchecked() };
         };
         b
     }};
 }
 
+/// Self describing only: Turns `me` into a [`SpecifiedEncoder::Union`] (if it isn't one already)
+/// and returns the alternative that `is_alternative`. Returns a new unspecified alternative if
+/// none of them are, or an error if there would be more than 256 alternatives.
+fn union_alternative(
+    me: &mut LazyEncoder,
+    is_alternative: impl Fn(&SpecifiedEncoder) -> bool,
+) -> Result<&mut LazyEncoder> {
+    if !matches!(
+        me,
+        LazyEncoder::Specified {
+            specified: SpecifiedEncoder::Union(_),
+            ..
+        }
+    ) {
+        // The values encoded so far are implicitly the first alternative.
+        let first = std::mem::take(me);
+        *me = LazyEncoder::Specified {
+            specified: SpecifiedEncoder::Union((Default::default(), vec![first])),
+            index: usize::MAX, // We never use index when self describing.
+        };
+    }
+    let LazyEncoder::Specified {
+        specified: SpecifiedEncoder::Union((indices, alternatives)),
+        ..
+    } = me else {
+        unreachable!();
+    };
+    let i = alternatives
+        .iter()
+        .position(
+            |a| matches!(a, LazyEncoder::Specified { specified, .. } if is_alternative(specified)),
+        )
+        .unwrap_or(alternatives.len());
+    let Ok(index) = u8::try_from(i) else {
+        return err_kind(
+            ErrorKind::Unsupported,
+            "more than 256 different types in one place",
+        );
+    };
+    if i == alternatives.len() {
+        alternatives.push(Default::default());
+    }
+    indices.reserve(NonZeroUsize::MIN);
+    indices.encode(&index);
+
+    // Reservations made for the Union don't apply to its alternatives, so reserve the value here.
+    let alternative = &mut alternatives[i];
+    alternative.reserve_fast(1);
+    Ok(alternative)
+}
+
 struct EncoderWrapper<'a> {
     lazy: &'a mut LazyEncoder,
     index_alloc: &'a mut usize,
+    self_describing: bool,
 }
 
 impl<'a> EncoderWrapper<'a> {
     #[inline(always)]
     fn serialize_enum(self, variant_index: u32) -> Result<EncoderWrapper<'a>> {
-        let variant_index = variant_index
-            .try_into()
-            .map_err(|_| error("enums with more than 256 variants are unsupported"))?;
-        let b = specify!(self, Enum);
+        let variant_index = variant_index.try_into().map_err(|_| {
+            error_kind(
+                ErrorKind::Unsupported,
+                "enums with more than 256 variants are unsupported",
+            )
+        })?;
+        let b = specify!(self, Enum | Option);
+        Ok(Self::variant(
+            b,
+            variant_index,
+            self.index_alloc,
+            self.self_describing,
+        ))
+    }
+
+    /// Same format as [`Self::serialize_enum`], but is specified as an option so it can be told
+    /// apart from enums when self describing.
+    #[inline(always)]
+    fn serialize_option(self, variant_index: u8) -> Result<EncoderWrapper<'a>> {
+        let b = specify!(self, Option | Enum);
+        Ok(Self::variant(
+            b,
+            variant_index,
+            self.index_alloc,
+            self.self_describing,
+        ))
+    }
+
+    #[inline(always)]
+    fn variant(
+        b: &'a mut (VariantEncoder, Vec<LazyEncoder>),
+        variant_index: u8,
+        index_alloc: &'a mut usize,
+        self_describing: bool,
+    ) -> Self {
         b.0.encode(&variant_index);
         let lazy = get_mut_or_resize(&mut b.1, variant_index as usize);
         lazy.reserve_fast(1); // TODO use push instead.
-        Ok(Self {
+        Self {
             lazy,
+            index_alloc,
+            self_describing,
+        }
+    }
+
+    /// Writes the length of a seq of u8 and returns the bytes with `len` reserved. Has the same
+    /// format as [`Serializer::serialize_seq`] followed by [`Serializer::serialize_u8`]s.
+    ///
+    /// Returns `None` if `len == 0` since serialize_seq wouldn't learn the element type.
+    #[inline(always)]
+    fn serialize_byte_seq(self, len: usize) -> Result<Option<&'a mut VecImpl<u8>>> {
+        let b = specify!(self, Seq);
+        b.0.encode(&len);
+        if len == 0 {
+            return Ok(None);
+        }
+        let elements = EncoderWrapper {
+            lazy: &mut b.1,
             index_alloc: self.index_alloc,
-        })
+            self_describing: self.self_describing,
+        };
+        let bytes = Encoder::<u8>::as_primitive(specify!(elements, U8)).unwrap();
+        bytes.reserve(len);
+        Ok(Some(bytes))
     }
 }
 
 macro_rules! impl_ser {
-    ($name:ident, $t:ty, $variant:ident) => {
+    ($name:ident, $t:ty, $($variant:ident)|+) => {
         // TODO #[inline(always)] makes benchmark slower because collect_seq isn't inlined.
         fn $name(self, v: $t) -> Result<()> {
-            specify!(self, $variant).encode(&v);
+            specify!(self, $($variant)|+).encode(&v);
             Ok(())
         }
     };
@@ -281,22 +549,87 @@ impl<'a> Serializer for EncoderWrapper<'a> {
     impl_ser!(serialize_str, &str, Str);
     impl_ser!(serialize_u8, u8, U8);
     impl_ser!(serialize_u16, u16, U16);
-    impl_ser!(serialize_u32, u32, U32);
-    impl_ser!(serialize_u64, u64, U64);
+    impl_ser!(serialize_u32, u32, U32 | Char);
+    impl_ser!(serialize_u64, u64, U64 | F64);
     impl_ser!(serialize_u128, u128, U128);
 
-    // IntEncoder works on f64/char.
-    impl_ser!(serialize_f64, f64, U64);
-    impl_ser!(serialize_char, char, U32);
+    // IntEncoder works on f64/char. They have their own variants so they can be told apart from
+    // u64/u32 when self describing.
+    impl_ser!(serialize_f64, f64, F64 | U64);
+    impl_ser!(serialize_char, char, Char | U32);
 
     #[inline(always)]
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
-        v.serialize(self)
+        if let Some(bytes) = self.serialize_byte_seq(v.len())? {
+            // Safety: serialize_byte_seq reserved `v.len()` bytes.
+            unsafe {
+                let ptr = bytes.end_ptr();
+                std::ptr::copy_nonoverlapping(v.as_ptr(), ptr, v.len());
+                bytes.set_end_ptr(ptr.add(v.len()));
+            }
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn collect_seq<I>(self, iter: I) -> Result<Self::Ok>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        // Fast path: Vec<u8>, &[u8], VecDeque<u8>, etc. don't need to go through serialize_u8.
+        let id = non_static_type_id::<I::Item>();
+        if id == non_static_type_id::<u8>() || id == non_static_type_id::<&u8>() {
+            let mut iter = iter.into_iter().map(|v| {
+                // Safety: I::Item is u8 or &u8 (lifetimes don't matter since we only read from it).
+                unsafe {
+                    if std::mem::size_of::<I::Item>() == 1 {
+                        std::mem::transmute_copy::<I::Item, u8>(&v)
+                    } else {
+                        *std::mem::transmute_copy::<I::Item, &u8>(&v)
+                    }
+                }
+            });
+            let b = specify!(self, Seq);
+            let Some(first) = iter.next() else {
+                // Don't specify elements since serialize_seq wouldn't either.
+                b.0.encode(&0);
+                return Ok(());
+            };
+            let elements = EncoderWrapper {
+                lazy: &mut b.1,
+                index_alloc: self.index_alloc,
+                self_describing: self.self_describing,
+            };
+            let bytes = Encoder::<u8>::as_primitive(specify!(elements, U8)).unwrap();
+
+            // Safe code can lie about size_hint, so it's only used to reserve and the bytes are
+            // counted instead.
+            bytes.reserve(iter.size_hint().0.saturating_add(1));
+            let mut len = 0;
+            for v in std::iter::once(first).chain(iter) {
+                bytes.reserve(1);
+                // Safety: reserved 1 byte.
+                unsafe { bytes.push_unchecked(v) };
+                len += 1;
+            }
+            b.0.encode(&len);
+            return Ok(());
+        }
+
+        let iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        let len = (Some(lower) == upper).then_some(lower);
+        let mut seq = self.serialize_seq(len)?;
+        for v in iter {
+            seq.serialize_element(&v)?;
+        }
+        seq.end()
     }
 
     #[inline(always)]
     fn serialize_none(self) -> Result<Self::Ok> {
-        self.serialize_enum(0)?;
+        self.serialize_option(0)?;
         Ok(())
     }
 
@@ -305,17 +638,20 @@ impl<'a> Serializer for EncoderWrapper<'a> {
     where
         T: Serialize,
     {
-        v.serialize(self.serialize_enum(1)?)
+        v.serialize(self.serialize_option(1)?)
     }
 
     #[inline(always)]
     fn serialize_unit(self) -> Result<Self::Ok> {
+        if self.self_describing {
+            specify!(self, Unit);
+        }
         Ok(())
     }
 
     #[inline(always)]
     fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
-        Ok(())
+        self.serialize_unit()
     }
 
     #[inline(always)]
@@ -360,30 +696,46 @@ impl<'a> Serializer for EncoderWrapper<'a> {
         Ok(SeqSerializer {
             lazy: &mut b.1,
             index_alloc: self.index_alloc,
+            self_describing: self.self_describing,
             len,
         })
     }
 
     #[inline(always)]
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
-        // Fast path: avoid overhead of tuple for 1 element.
-        if len == 1 {
+        // Fast path: avoid overhead of tuple for 1 element. Not taken when self describing since
+        // deserialize_any has to be able to tell a 1 element tuple apart from its element.
+        if len == 1 && !self.self_describing {
             return Ok(TupleSerializer {
                 encoders: std::slice::from_mut(self.lazy),
                 index_alloc: self.index_alloc,
+                self_describing: self.self_describing,
             });
         }
 
         // Copy of specify! macro that takes an additional len parameter to cold.
-        let lazy = &mut *self.lazy;
+        let mut lazy = &mut *self.lazy;
         match lazy {
             LazyEncoder::Specified {
-                specified: SpecifiedEncoder::Tuple(_),
+                specified: SpecifiedEncoder::Tuple(encoders),
                 ..
-            } => (),
+            } if encoders.len() == len => (),
             _ => {
                 #[cold]
-                fn cold(me: &mut LazyEncoder, len: usize) {
+                fn cold(
+                    mut me: &mut LazyEncoder,
+                    self_describing: bool,
+                    len: usize,
+                ) -> Result<&mut LazyEncoder> {
+                    if self_describing && matches!(me, LazyEncoder::Specified { .. }) {
+                        me = union_alternative(
+                            me,
+                            |s| matches!(s, SpecifiedEncoder::Tuple(v) if v.len() == len),
+                        )?;
+                        if matches!(me, LazyEncoder::Specified { .. }) {
+                            return Ok(me);
+                        }
+                    }
                     let &mut LazyEncoder::Unspecified { reserved } = me else {
                         type_changed!();
                     };
@@ -397,8 +749,9 @@ impl<'a> Serializer for EncoderWrapper<'a> {
                     if let Some(reserved) = reserved {
                         specified.reserve(reserved);
                     }
+                    Ok(me)
                 }
-                cold(lazy, len);
+                lazy = cold(lazy, self.self_describing, len)?;
             }
         };
         let LazyEncoder::Specified {
@@ -414,6 +767,7 @@ impl<'a> Serializer for EncoderWrapper<'a> {
         Ok(TupleSerializer {
             encoders,
             index_alloc: self.index_alloc,
+            self_describing: self.self_describing,
         })
     }
 
@@ -447,6 +801,7 @@ impl<'a> Serializer for EncoderWrapper<'a> {
         Ok(MapSerializer {
             encoders: &mut b.1,
             index_alloc: self.index_alloc,
+            self_describing: self.self_describing,
             len,
             key_serialized: false, // No keys have been serialized yet, so serialize_value can't be called.
         })
@@ -487,6 +842,7 @@ macro_rules! ok_error_end {
 struct SeqSerializer<'a> {
     lazy: &'a mut LazyEncoder,
     index_alloc: &'a mut usize,
+    self_describing: bool,
     len: usize,
 }
 
@@ -499,6 +855,7 @@ impl SerializeSeq for SeqSerializer<'_> {
         value.serialize(EncoderWrapper {
             lazy: &mut *self.lazy,
             index_alloc: &mut *self.index_alloc,
+            self_describing: self.self_describing,
         })
     }
 }
@@ -506,6 +863,7 @@ impl SerializeSeq for SeqSerializer<'_> {
 struct TupleSerializer<'a> {
     encoders: &'a mut [LazyEncoder], // [field0, field1, ..]
     index_alloc: &'a mut usize,
+    self_describing: bool,
 }
 
 macro_rules! impl_tuple {
@@ -521,12 +879,13 @@ macro_rules! impl_tuple {
                 value.serialize(EncoderWrapper {
                     lazy,
                     index_alloc: &mut *self.index_alloc,
+                    self_describing: self.self_describing,
                 })
             }
 
             $(
                 fn skip_field(&mut self, $key: &'static str) -> Result<()> {
-                    err("skip field is not supported")
+                    err_kind(ErrorKind::Unsupported, "skip field is not supported")
                 }
             )?
         }
@@ -541,6 +900,7 @@ impl_tuple!(SerializeStructVariant, serialize_field, _key);
 struct MapSerializer<'a> {
     encoders: &'a mut (LazyEncoder, LazyEncoder), // (keys, values)
     index_alloc: &'a mut usize,
+    self_describing: bool,
     len: usize,
     key_serialized: bool,
 }
@@ -559,6 +919,7 @@ impl SerializeMap for MapSerializer<'_> {
         key.serialize(EncoderWrapper {
             lazy: &mut self.encoders.0,
             index_alloc: &mut *self.index_alloc,
+            self_describing: self.self_describing,
         })
     }
 
@@ -575,6 +936,7 @@ impl SerializeMap for MapSerializer<'_> {
         value.serialize(EncoderWrapper {
             lazy: &mut self.encoders.1,
             index_alloc: &mut *self.index_alloc,
+            self_describing: self.self_describing,
         })
     }
     // TODO implement serialize_entry to avoid checking key_serialized.
@@ -643,6 +1005,7 @@ mod tests {
                     reserved: NonZeroUsize::new(1),
                 },
                 index_alloc: &mut 0,
+                self_describing: false,
             }
         };
     }
@@ -0,0 +1,88 @@
+use crate::coder::Result;
+use crate::consume::{consume_byte, consume_byte_arrays};
+use crate::error::error;
+
+/// Header nesting limit to avoid overflowing the stack on malicious input.
+pub const MAX_DEPTH: usize = 1024;
+
+/// The type of a node in the tree of encoders. A self describing header is the tree of shapes in
+/// pre-order (the same order the buffers are written in) so the deserializer can specify all of
+/// its decoders up front. [`Shape::Enum`], [`Shape::Option`], [`Shape::Tuple`] and
+/// [`Shape::Union`] are followed by their number of children. [`Shape::Union`] is then followed by
+/// its number of values that have an explicit alternative index.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Shape {
+    Unit,
+    Bool,
+    Char,
+    Enum,
+    F32,
+    F64,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Map,
+    Option,
+    Seq,
+    Str,
+    Tuple,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Union,
+}
+
+impl Shape {
+    const ALL: [Self; 22] = [
+        Self::Unit,
+        Self::Bool,
+        Self::Char,
+        Self::Enum,
+        Self::F32,
+        Self::F64,
+        Self::I8,
+        Self::I16,
+        Self::I32,
+        Self::I64,
+        Self::I128,
+        Self::Map,
+        Self::Option,
+        Self::Seq,
+        Self::Str,
+        Self::Tuple,
+        Self::U8,
+        Self::U16,
+        Self::U32,
+        Self::U64,
+        Self::U128,
+        Self::Union,
+    ];
+
+    pub fn write(self, out: &mut Vec<u8>) {
+        out.push(self as u8);
+    }
+
+    pub fn read(input: &mut &[u8]) -> Result<Self> {
+        let b = consume_byte(input)?;
+        Self::ALL
+            .get(b as usize)
+            .copied()
+            .ok_or_else(|| error("invalid shape"))
+    }
+}
+
+pub fn write_len(len: usize, out: &mut Vec<u8>) {
+    out.extend_from_slice(&u32::try_from(len).unwrap().to_le_bytes());
+}
+
+pub fn read_len(input: &mut &[u8]) -> Result<usize> {
+    let [bytes] = consume_byte_arrays::<4>(input, 1)? else {
+        unreachable!();
+    };
+    Ok(u32::from_le_bytes(*bytes) as usize)
+}
@@ -1,4 +1,4 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::fast::{CowSlice, NextUnchecked, PushUnchecked, VecImpl};
 use crate::pack::{pack_bytes, unpack_bytes};
 use std::marker::PhantomData;
@@ -9,6 +9,13 @@ pub struct VariantEncoder {
     data: VecImpl<u8>,
 }
 
+impl VariantEncoder {
+    /// Returns the number of variants encoded since the last [`EncoderBuffer::collect_into`].
+    pub fn len(&self) -> usize {
+        self.data.as_slice().len()
+    }
+}
+
 impl Encoder<u8> for VariantEncoder {
     #[inline(always)]
     fn encode(&mut self, v: &u8) {
@@ -16,7 +23,7 @@ impl Encoder<u8> for VariantEncoder {
     }
 }
 
-impl Buffer for VariantEncoder {
+impl EncoderBuffer for VariantEncoder {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         pack_bytes(self.data.as_mut_slice(), out);
         self.data.clear();
@@ -1,12 +1,16 @@
-use crate::coder::{Buffer, Decoder, Encoder, Result, View};
+use crate::coder::{Decoder, Encoder, EncoderBuffer, Result, View};
 use crate::consume::consume_bytes;
 use crate::derive::vec::VecEncoder;
-use crate::error::err;
+use crate::error::{err_kind, ErrorKind};
 use crate::fast::{NextUnchecked, SliceImpl};
 use crate::length::LengthDecoder;
+use crate::options::decode_options;
 use crate::u8_char::U8Char;
+use std::borrow::Cow;
 use std::num::NonZeroUsize;
+use std::rc::Rc;
 use std::str::{from_utf8, from_utf8_unchecked};
+use std::sync::Arc;
 
 #[derive(Debug, Default)]
 pub struct StrEncoder(pub(crate) VecEncoder<U8Char>); // pub(crate) for arrayvec.rs
@@ -16,7 +20,7 @@ fn str_as_u8_chars(s: &str) -> &[U8Char] {
     bytemuck::must_cast_slice(s.as_bytes())
 }
 
-impl Buffer for StrEncoder {
+impl EncoderBuffer for StrEncoder {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         self.0.collect_into(out);
     }
@@ -77,20 +81,26 @@ pub struct StrDecoder<'a> {
     strings: SliceImpl<'a, u8>,
 }
 
-impl<'a> View<'a> for StrDecoder<'a> {
-    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+impl<'a> StrDecoder<'a> {
+    /// Populates `self` and returns true if the strings are valid UTF-8. If they aren't, `self`
+    /// must not be used to decode `&str`s.
+    fn populate_unvalidated(&mut self, input: &mut &'a [u8], length: usize) -> Result<bool> {
         // TODO take NonZeroUsize length in View::populate.
         let Some(length) = NonZeroUsize::new(length) else {
-            return Ok(());
+            return Ok(true);
         };
         self.lengths.populate(input, length.get())?;
+        // Safety: `length` was just passed to populate.
+        let max = decode_options().max_str_len;
+        unsafe { self.lengths.check_max(length.get(), max, "str too long")? };
         let bytes = consume_bytes(input, self.lengths.length())?;
+        self.strings = bytes.into();
 
         // Fast path: If bytes are ASCII then they're valid UTF-8 and no char boundary can be invalid.
         // TODO(optimization):
         // - Worst case when bytes doesn't fit in CPU cache, this will load bytes 3 times from RAM.
         // - We should subdivide it into chunks in that case.
-        if is_ascii_simd(bytes)
+        Ok(is_ascii_simd(bytes)
             || from_utf8(bytes).is_ok_and(|s| {
                 // Check that gaps between individual strings are on char boundaries in larger string.
                 // Indices 0 and s.len() are not checked since s: &str guarantees them.
@@ -104,12 +114,16 @@ impl<'a> View<'a> for StrDecoder<'a> {
                     }
                 }
                 true
-            })
-        {
-            self.strings = bytes.into();
+            }))
+    }
+}
+
+impl<'a> View<'a> for StrDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        if self.populate_unvalidated(input, length)? {
             Ok(())
         } else {
-            err("invalid utf8")
+            err_kind(ErrorKind::InvalidUtf8, "invalid utf8")
         }
     }
 }
@@ -134,6 +148,80 @@ impl<'a> Decoder<'a, String> for StrDecoder<'a> {
     }
 }
 
+/// Decodes `String`s, which (unlike `&str`s) can be decoded with [`DecodeOptions::lossy_text`][`crate::DecodeOptions::lossy_text`].
+#[derive(Debug, Default)]
+pub struct StringDecoder<'a> {
+    str: StrDecoder<'a>,
+    lossy: bool,
+}
+
+impl<'a> View<'a> for StringDecoder<'a> {
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<()> {
+        let valid = self.str.populate_unvalidated(input, length)?;
+        self.lossy = !valid;
+        if !valid && !decode_options().lossy_text {
+            return err_kind(ErrorKind::InvalidUtf8, "invalid utf8");
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Decoder<'a, String> for StringDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> String {
+        if self.lossy {
+            let str = &mut self.str;
+            let bytes = unsafe { str.strings.chunk_unchecked(str.lengths.decode()) };
+            String::from_utf8_lossy(bytes).into_owned()
+        } else {
+            self.str.decode()
+        }
+    }
+}
+
+impl Encoder<Cow<'_, str>> for StrEncoder {
+    #[inline(always)]
+    fn encode(&mut self, t: &Cow<'_, str>) {
+        self.encode(t.as_ref());
+    }
+}
+
+impl<'a> Decoder<'a, Cow<'a, str>> for StringDecoder<'a> {
+    #[inline(always)]
+    fn decode(&mut self) -> Cow<'a, str> {
+        // Only allocates if invalid UTF-8 has to be replaced.
+        if self.lossy {
+            let str = &mut self.str;
+            let bytes = unsafe { str.strings.chunk_unchecked(str.lengths.decode()) };
+            String::from_utf8_lossy(bytes)
+        } else {
+            Cow::Borrowed(self.str.decode())
+        }
+    }
+}
+
+macro_rules! impl_shared_str {
+    ($($ptr:ident),+) => {
+        $(
+            impl<'a> Decoder<'a, $ptr<str>> for StringDecoder<'a> {
+                #[inline(always)]
+                fn decode(&mut self) -> $ptr<str> {
+                    // Allocates once (unlike decoding a String and converting it).
+                    if self.lossy {
+                        let str = &mut self.str;
+                        let bytes = unsafe { str.strings.chunk_unchecked(str.lengths.decode()) };
+                        $ptr::from(String::from_utf8_lossy(bytes).as_ref())
+                    } else {
+                        let s: &str = self.str.decode();
+                        $ptr::from(s)
+                    }
+                }
+            }
+        )+
+    };
+}
+impl_shared_str!(Rc, Arc);
+
 /// Tests 128 bytes a time instead of `<[u8]>::is_ascii` which only tests 8.
 /// 390% faster on 8KB, 27% faster on 1GB (RAM bottleneck).
 fn is_ascii_simd(v: &[u8]) -> bool {
@@ -178,6 +266,39 @@ mod tests {
         assert_eq!(decode::<[&str; 2]>(&encode(&[c, c])).unwrap(), [c, c]);
     }
 
+    #[test]
+    fn lossy() {
+        use crate::{decode_with_options, DecodeOptions};
+        let lossy = DecodeOptions::new().lossy_text(true);
+
+        let encoded = encode(&[vec![U8Char(b'a'), U8Char(255)], vec![], vec![U8Char(b'b')]]);
+        assert!(decode::<[String; 3]>(&encoded).is_err());
+        assert_eq!(
+            decode_with_options::<[String; 3]>(&encoded, &lossy).unwrap(),
+            ["a\u{FFFD}", "", "b"]
+        );
+        assert_eq!(
+            decode_with_options::<Box<str>>(&encode(&vec![U8Char(255)]), &lossy).unwrap(),
+            "\u{FFFD}".into()
+        );
+        // Can't borrow the replaced strings.
+        assert!(decode_with_options::<[&str; 3]>(&encoded, &lossy).is_err());
+
+        // A char split between strings is replaced in both.
+        let full = super::str_as_u8_chars("☺");
+        let split = encode(&[full[..1].to_vec(), full[1..].to_vec()]);
+        assert!(decode::<[String; 2]>(&split).is_err());
+        let decoded = decode_with_options::<[String; 2]>(&split, &lossy).unwrap();
+        assert_eq!(decoded, ["\u{FFFD}", "\u{FFFD}\u{FFFD}"]);
+
+        // Valid strings are unchanged.
+        let valid = encode(&["☺", "a"]);
+        assert_eq!(
+            decode_with_options::<[String; 2]>(&valid, &lossy).unwrap(),
+            ["☺", "a"]
+        );
+    }
+
     #[test]
     fn test_is_ascii_simd() {
         assert!(is_ascii_simd(&[0x7F; 128]));
@@ -214,3 +335,15 @@ mod tests2 {
     }
     crate::bench_encode_decode!(str_vec: Vec<String>);
 }
+
+#[cfg(test)]
+mod tests3 {
+    // Longer than the vectored fast path's max length like chat messages.
+    fn bench_data() -> Vec<String> {
+        crate::random_data::<u8>(4000)
+            .into_iter()
+            .map(|n| "a".repeat(65 + n as usize))
+            .collect()
+    }
+    crate::bench_encode_decode!(long_str_vec: Vec<String>);
+}
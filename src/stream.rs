@@ -0,0 +1,410 @@
+use crate::coder::{Encoder, EncoderBuffer};
+use crate::consume::consume_byte_arrays;
+use crate::error::{err_kind, ErrorKind};
+use crate::length::LengthEncoder;
+use crate::{Buffer, DecodeOwned, Encode, Error};
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+
+/// Number of bytes in the length prefix of a frame.
+pub(crate) const PREFIX_LEN: usize = 4; // pub(crate) for codec.rs and io.rs
+
+/// The default maximum length of a frame (excluding its length prefix) that can be decoded.
+/// pub(crate) for codec.rs, io.rs and tokio.rs.
+pub(crate) const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// Encodes a `T:` [`Encode`] into a frame that can be decoded by [`StreamingDecoder`].
+///
+/// A frame is the length of [`encode`][`crate::encode`]'s output as a 4 byte little endian
+/// integer, followed by that output.
+///
+/// **Panics** if the encoding is larger than [`u32::MAX`] bytes.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn encode_frame<T: Encode + ?Sized>(t: &T) -> Vec<u8> {
+    let mut frame = vec![];
+    write_frame(&mut frame, |out| Buffer::new().encode_append(t, out));
+    frame
+}
+
+/// Overwrites `out` with a frame of the bytes appended by `encode`.
+///
+/// **Panics** if `encode` appends more than [`u32::MAX`] bytes.
+fn write_frame(out: &mut Vec<u8>, encode: impl FnOnce(&mut Vec<u8>)) {
+    out.clear();
+    out.extend_from_slice(&[0; PREFIX_LEN]);
+    encode(out);
+    let len = u32::try_from(out.len() - PREFIX_LEN).expect("frame too large");
+    out[..PREFIX_LEN].copy_from_slice(&len.to_le_bytes());
+}
+
+/// Encodes a sequence whose encoding is too large to buffer as frames of up to `chunk_len` items,
+/// passing each frame to `flush` as soon as it's encoded.
+///
+/// Each frame is independently decodable as a `(u64, Vec<T>)` of the chunk's index and its items,
+/// e.g. with a `StreamingDecoder<(u64, Vec<T>)>`.
+///
+/// **Panics** if `chunk_len` is 0 or a frame is larger than [`u32::MAX`] bytes.
+///
+/// ```rust
+/// use bitcode::{encode_chunks, StreamStatus, StreamingDecoder};
+///
+/// let items: Vec<u32> = (0..1000).collect();
+/// let mut decoder = StreamingDecoder::<(u64, Vec<u32>)>::new();
+/// let mut decoded = vec![];
+/// encode_chunks(&items, 100, |frame| {
+///     let StreamStatus::Decoded((i, chunk)) = decoder.feed(frame).unwrap() else {
+///         unreachable!();
+///     };
+///     assert_eq!(i as usize, decoded.len() / 100);
+///     decoded.extend(chunk);
+/// });
+/// assert_eq!(decoded, items);
+/// ```
+pub fn encode_chunks<T: Encode>(items: &[T], chunk_len: usize, mut flush: impl FnMut(&[u8])) {
+    assert_ne!(chunk_len, 0, "chunk_len must be > 0");
+    let mut buffer = Buffer::new();
+    let mut frame = vec![];
+    for (index, chunk) in items.chunks(chunk_len).enumerate() {
+        write_frame(&mut frame, |out| {
+            // A tuple is encoded as its fields' encodings one after another, so this is the same
+            // as encoding `(index, chunk)` (which would require cloning chunk into a Vec).
+            buffer.encode_append(&(index as u64), out);
+            buffer.encode_append(chunk, out);
+        });
+        flush(&frame);
+    }
+}
+
+/// Like [`encode_chunks`], but items are pushed one at a time, so they don't have to be in memory
+/// at once (e.g. rows of a database export). Produces the same frames as [`encode_chunks`].
+///
+/// Items are encoded as they're pushed, so only the current chunk's columns are held in memory.
+/// Call [`SequenceEncoder::finish`] to flush the last chunk.
+///
+/// ```rust
+/// use bitcode::{encode_chunks, SequenceEncoder};
+///
+/// let mut frames = vec![];
+/// let mut encoder = SequenceEncoder::new(100, |frame: &[u8]| frames.push(frame.to_vec()));
+/// for i in 0..1000u32 {
+///     encoder.push(&i);
+/// }
+/// encoder.finish();
+///
+/// let items: Vec<u32> = (0..1000).collect();
+/// let mut expected = vec![];
+/// encode_chunks(&items, 100, |frame| expected.push(frame.to_vec()));
+/// assert_eq!(frames, expected);
+/// ```
+pub struct SequenceEncoder<T: Encode, F: FnMut(&[u8])> {
+    buffer: Buffer,
+    lengths: LengthEncoder,
+    elements: T::Encoder,
+    chunk_len: usize,
+    len: usize,
+    index: u64,
+    frame: Vec<u8>,
+    flush: F,
+}
+
+impl<T: Encode, F: FnMut(&[u8])> SequenceEncoder<T, F> {
+    /// Constructs a new encoder that passes each frame of `chunk_len` items to `flush` as soon as
+    /// it's encoded.
+    ///
+    /// **Panics** if `chunk_len` is 0.
+    pub fn new(chunk_len: usize, flush: F) -> Self {
+        assert_ne!(chunk_len, 0, "chunk_len must be > 0");
+        Self {
+            buffer: Default::default(),
+            lengths: Default::default(),
+            elements: Default::default(),
+            chunk_len,
+            len: 0,
+            index: 0,
+            frame: vec![],
+            flush,
+        }
+    }
+
+    /// Encodes `t` as the next item, flushing a frame if it completes a chunk.
+    ///
+    /// **Panics** if the frame is larger than [`u32::MAX`] bytes.
+    pub fn push(&mut self, t: &T) {
+        self.elements.reserve(NonZeroUsize::MIN);
+        self.elements.encode(t);
+        self.len += 1;
+        if self.len == self.chunk_len {
+            self.flush_chunk();
+        }
+    }
+
+    /// Flushes the items pushed since the last frame (if any).
+    ///
+    /// **Panics** if the frame is larger than [`u32::MAX`] bytes.
+    pub fn finish(mut self) {
+        if self.len != 0 {
+            self.flush_chunk();
+        }
+    }
+
+    fn flush_chunk(&mut self) {
+        write_frame(&mut self.frame, |out| {
+            // Same as encoding `(index, chunk)` (see encode_chunks). A Vec is encoded as its
+            // length followed by its elements.
+            self.buffer.encode_append(&self.index, out);
+            self.lengths.reserve(NonZeroUsize::MIN);
+            self.lengths.encode(&self.len);
+            self.lengths.collect_into(out);
+            self.elements.collect_into(out);
+        });
+        (self.flush)(&self.frame);
+        self.index += 1;
+        self.len = 0;
+    }
+}
+
+/// The result of [`StreamingDecoder::feed`].
+#[derive(Debug, PartialEq)]
+pub enum StreamStatus<T> {
+    /// At least this many more bytes are required to decode the next frame.
+    NeedMoreData(usize),
+    /// The next frame was decoded.
+    Decoded(T),
+}
+
+/// Decodes frames from [`encode_frame`] as their bytes arrive, e.g. from a socket.
+///
+/// Frames larger than [`StreamingDecoder::max_frame_len`] are rejected before they're buffered, so
+/// a peer can't make the decoder allocate arbitrary amounts of memory.
+///
+/// ```rust
+/// use bitcode::{encode_frame, StreamStatus, StreamingDecoder};
+///
+/// let frame = encode_frame("Hello world!");
+/// let (a, b) = frame.split_at(5);
+///
+/// let mut decoder = StreamingDecoder::<String>::new();
+/// assert!(matches!(decoder.feed(a).unwrap(), StreamStatus::NeedMoreData(_)));
+/// assert_eq!(decoder.feed(b).unwrap(), StreamStatus::Decoded("Hello world!".to_owned()));
+/// ```
+pub struct StreamingDecoder<T> {
+    buffer: Buffer,
+    bytes: Vec<u8>,
+    /// The bytes before `start` have already been decoded. They're removed once per `feed` instead
+    /// of once per frame, since removing them moves the rest of `bytes`.
+    start: usize,
+    /// The number of bytes of a rejected frame that haven't been received yet.
+    skip: usize,
+    max_frame_len: usize,
+    _spooky: PhantomData<fn() -> T>,
+}
+
+// Can't derive since it would bound T: Default.
+impl<T> Default for StreamingDecoder<T> {
+    fn default() -> Self {
+        Self {
+            buffer: Default::default(),
+            bytes: Default::default(),
+            start: 0,
+            skip: 0,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            _spooky: PhantomData,
+        }
+    }
+}
+
+impl<T> StreamingDecoder<T> {
+    /// Sets the maximum length of a frame (excluding its length prefix). Larger frames return
+    /// [`ErrorKind::LimitExceeded`] and are skipped.
+    pub fn max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+}
+
+impl<T: DecodeOwned> StreamingDecoder<T> {
+    /// Constructs a new decoder with a maximum frame length of 8 MiB.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `chunk` to the received bytes and decodes the next frame if it has been fully
+    /// received. One call decodes at most one frame, so call `feed(&[])` until it returns
+    /// [`StreamStatus::NeedMoreData`] to decode any other frames that have already been received.
+    ///
+    /// If it returns an error, the invalid frame is skipped.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<StreamStatus<T>, Error> {
+        if !chunk.is_empty() {
+            self.bytes.drain(..self.start);
+            self.start = 0;
+            // Bytes of a rejected frame are dropped instead of buffered.
+            let skipped = self.skip.min(chunk.len());
+            self.skip -= skipped;
+            self.bytes.extend_from_slice(&chunk[skipped..]);
+        }
+        if self.skip != 0 {
+            return Ok(StreamStatus::NeedMoreData(self.skip + PREFIX_LEN));
+        }
+
+        let mut input = &self.bytes[self.start..];
+        let Ok([prefix]) = consume_byte_arrays::<PREFIX_LEN>(&mut input, 1) else {
+            return Ok(StreamStatus::NeedMoreData(PREFIX_LEN - input.len()));
+        };
+        let len = u32::from_le_bytes(*prefix) as usize;
+        if len > self.max_frame_len {
+            let skipped = len.min(input.len());
+            self.start += PREFIX_LEN + skipped;
+            self.skip = len - skipped;
+            return err_kind(ErrorKind::LimitExceeded, "frame too large");
+        }
+        let Some(frame) = input.get(..len) else {
+            return Ok(StreamStatus::NeedMoreData(len - input.len()));
+        };
+
+        let decoded = self.buffer.decode(frame);
+        self.start += PREFIX_LEN + len;
+        decoded.map(StreamStatus::Decoded)
+    }
+
+    /// Returns the number of bytes that have been received but not decoded yet.
+    pub fn buffered_len(&self) -> usize {
+        self.bytes.len() - self.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_data;
+
+    #[test]
+    fn byte_at_a_time() {
+        let values: Vec<Vec<(u16, String)>> = (0..10)
+            .map(|i| (0..i).map(|j: u16| (j, "a".repeat(j as usize))).collect())
+            .collect();
+        let bytes: Vec<u8> = values.iter().flat_map(encode_frame).collect();
+
+        let mut decoder = StreamingDecoder::<Vec<(u16, String)>>::new();
+        let mut decoded = vec![];
+        for &b in &bytes {
+            match decoder.feed(&[b]).unwrap() {
+                StreamStatus::NeedMoreData(n) => assert_ne!(n, 0),
+                StreamStatus::Decoded(v) => decoded.push(v),
+            }
+        }
+        assert_eq!(decoded, values);
+        assert_eq!(decoder.buffered_len(), 0);
+    }
+
+    #[test]
+    fn many_frames_per_chunk() {
+        let values: Vec<u64> = random_data(100);
+        let bytes: Vec<u8> = values.iter().flat_map(encode_frame).collect();
+
+        let mut decoder = StreamingDecoder::<u64>::new();
+        let mut decoded = vec![];
+        for chunk in bytes.chunks(37) {
+            let mut chunk = chunk;
+            while let StreamStatus::Decoded(v) = decoder.feed(std::mem::take(&mut chunk)).unwrap() {
+                decoded.push(v);
+            }
+        }
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn chunks() {
+        let items: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+        for chunk_len in [1, 7, 999, 1000, 5000] {
+            let mut frames = vec![];
+            encode_chunks(&items, chunk_len, |f| frames.push(f.to_vec()));
+            assert_eq!(
+                frames.len(),
+                crate::nightly::div_ceil_usize(items.len(), chunk_len)
+            );
+
+            // Chunks are independently decodable.
+            let mut decoded = vec![String::new(); items.len()];
+            for frame in frames.iter().rev() {
+                let mut decoder = StreamingDecoder::<(u64, Vec<String>)>::new();
+                let StreamStatus::Decoded((i, chunk)) = decoder.feed(frame).unwrap() else {
+                    panic!("frame not decoded");
+                };
+                let start = i as usize * chunk_len;
+                decoded[start..start + chunk.len()].clone_from_slice(&chunk);
+            }
+            assert_eq!(decoded, items);
+        }
+
+        let mut frames = 0;
+        encode_chunks(&Vec::<u8>::new(), 1, |_| frames += 1);
+        assert_eq!(frames, 0);
+    }
+
+    #[test]
+    fn sequence_encoder() {
+        let items: Vec<(u8, String)> = (0..1000).map(|i| (i as u8, i.to_string())).collect();
+        for chunk_len in [1, 7, 999, 1000, 5000] {
+            let mut expected = vec![];
+            encode_chunks(&items, chunk_len, |f| expected.push(f.to_vec()));
+
+            let mut frames = vec![];
+            let mut encoder = SequenceEncoder::new(chunk_len, |f: &[u8]| frames.push(f.to_vec()));
+            for item in &items {
+                encoder.push(item);
+            }
+            encoder.finish();
+            assert_eq!(frames, expected);
+        }
+
+        let mut frames = 0;
+        SequenceEncoder::<u8, _>::new(1, |_| frames += 1).finish();
+        assert_eq!(frames, 0);
+    }
+
+    #[test]
+    fn invalid_frame() {
+        let mut bytes = 2u32.to_le_bytes().to_vec();
+        bytes.extend([0, 0]); // A bool with a trailing byte.
+        bytes.extend(encode_frame(&true));
+
+        let mut decoder = StreamingDecoder::<bool>::new();
+        assert!(decoder.feed(&bytes).is_err());
+        assert_eq!(decoder.feed(&[]).unwrap(), StreamStatus::Decoded(true));
+        assert_eq!(decoder.feed(&[]).unwrap(), StreamStatus::NeedMoreData(4));
+    }
+
+    #[test]
+    fn max_frame_len() {
+        // Values span the whole u8 range so they can't be packed.
+        let large_value: Vec<u8> = (0..100u8).map(|i| i.wrapping_mul(151)).collect();
+        let large = encode_frame(&large_value);
+        let small = encode_frame(&vec![2u8; 10]);
+        assert!(large.len() > 50 + PREFIX_LEN && small.len() < 50);
+        let bytes: Vec<u8> = [small.as_slice(), &large, &small].concat();
+
+        // Whether the large frame is already buffered or still arriving, it's skipped.
+        for chunk_len in [1, 7, bytes.len()] {
+            let mut decoder = StreamingDecoder::<Vec<u8>>::new().max_frame_len(50);
+            let mut results = vec![];
+            for chunk in bytes.chunks(chunk_len) {
+                let mut chunk = chunk;
+                loop {
+                    match decoder.feed(std::mem::take(&mut chunk)) {
+                        Ok(StreamStatus::NeedMoreData(n)) => {
+                            assert_ne!(n, 0);
+                            break;
+                        }
+                        Ok(StreamStatus::Decoded(v)) => results.push(Ok(v)),
+                        Err(e) => results.push(Err(e.kind())),
+                    }
+                }
+            }
+            let small = Ok(vec![2u8; 10]);
+            let expected = [small.clone(), Err(ErrorKind::LimitExceeded), small];
+            assert_eq!(results, expected);
+            assert_eq!(decoder.buffered_len(), 0);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+/// Memory that [`Buffer::encode_into`][`crate::Buffer::encode_into`] writes an encoding to, such
+/// as a shared memory segment or a slot of a ring buffer.
+///
+/// Columns are packed based on all of their values, so an encoding's length is only known once
+/// it's complete. It's encoded into the [`Buffer`][`crate::Buffer`]'s reused memory and then
+/// copied to the target in one `memcpy`.
+pub trait EncodeTarget {
+    /// Returns at least `len` bytes to write an encoding of `len` bytes to. Can grow the target
+    /// (e.g. by remapping a segment) or return `None` if it can't fit `len` bytes.
+    fn reserve(&mut self, len: usize) -> Option<&mut [u8]>;
+}
+
+/// A fixed size target that can't grow.
+impl EncodeTarget for [u8] {
+    fn reserve(&mut self, len: usize) -> Option<&mut [u8]> {
+        self.get_mut(..len)
+    }
+}
+
+/// Overwrites the `Vec` with the encoding (growing it if needed).
+impl EncodeTarget for Vec<u8> {
+    fn reserve(&mut self, len: usize) -> Option<&mut [u8]> {
+        self.resize(len, 0);
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EncodeTarget;
+    use crate::{decode, Buffer};
+
+    /// Stands in for a shared memory segment which is remapped to a larger size when full.
+    struct Segment {
+        memory: Box<[u8]>,
+        remaps: usize,
+    }
+
+    impl EncodeTarget for Segment {
+        fn reserve(&mut self, len: usize) -> Option<&mut [u8]> {
+            if self.memory.len() < len {
+                self.memory = vec![0; len.next_power_of_two()].into();
+                self.remaps += 1;
+            }
+            self.memory.get_mut(..len)
+        }
+    }
+
+    #[test]
+    fn encode_into() {
+        let v: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let mut buffer = Buffer::new();
+        let expected = buffer.encode(&v).to_vec();
+
+        let mut fixed = [0u8; 1000];
+        let n = buffer.encode_into(&v, fixed.as_mut_slice()).unwrap();
+        assert_eq!(&fixed[..n], expected);
+        assert!(buffer.encode_into(&v, &mut fixed[..n - 1]).is_err());
+
+        let mut segment = Segment {
+            memory: vec![0; 16].into(),
+            remaps: 0,
+        };
+        for _ in 0..2 {
+            let n = buffer.encode_into(&v, &mut segment).unwrap();
+            assert_eq!(decode::<Vec<String>>(&segment.memory[..n]).unwrap(), v);
+        }
+        assert_eq!(segment.remaps, 1);
+
+        let mut vec = vec![1, 2, 3];
+        let n = buffer.encode_into(&v, &mut vec).unwrap();
+        assert_eq!((n, vec), (expected.len(), expected));
+    }
+}
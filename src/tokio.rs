@@ -0,0 +1,180 @@
+use crate::stream::{DEFAULT_MAX_FRAME_LEN, PREFIX_LEN};
+use crate::{DecodeOwned, Encode};
+use std::io::{Error, ErrorKind};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Encodes a `T:` [`Encode`] as a frame (in the format of [`encode_frame`][`crate::encode_frame`])
+/// and writes it to `writer`, e.g. a `tokio::net::TcpStream`.
+///
+/// Isn't cancel safe: if the future is dropped before it completes, part of the frame may have
+/// been written.
+///
+/// **Panics** if the encoding is larger than [`u32::MAX`] bytes.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub async fn encode_async<T: Encode + ?Sized, W: AsyncWrite + Unpin>(
+    t: &T,
+    mut writer: W,
+) -> Result<(), Error> {
+    writer.write_all(&crate::encode_frame(t)).await
+}
+
+/// Reads a frame written by [`encode_async`] from `reader` and decodes it into a
+/// `T:` [`DecodeOwned`]. Partial reads are retried until the whole frame has been read, and only
+/// the frame's bytes are read, so it can be called again to read the next frame. Decode errors
+/// are returned as [`ErrorKind::InvalidData`].
+///
+/// Frames longer than 8 MiB (excluding their length prefix) are rejected, see
+/// [`decode_async_with_max_len`].
+///
+/// Isn't cancel safe: if the future is dropped before it completes, part of the frame may have
+/// been read.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub async fn decode_async<T: DecodeOwned, R: AsyncRead + Unpin>(reader: R) -> Result<T, Error> {
+    decode_async_with_max_len(reader, DEFAULT_MAX_FRAME_LEN).await
+}
+
+/// Like [`decode_async`] but frames longer than `max_frame_len` (excluding their length prefix)
+/// are rejected instead of 8 MiB ones. A rejected frame returns [`ErrorKind::InvalidData`] wrapping
+/// a [`crate::Error`] of kind [`crate::ErrorKind::LimitExceeded`], and only its length prefix has
+/// been read from `reader`.
+pub async fn decode_async_with_max_len<T: DecodeOwned, R: AsyncRead + Unpin>(
+    mut reader: R,
+    max_frame_len: usize,
+) -> Result<T, Error> {
+    let mut prefix = [0; PREFIX_LEN];
+    reader.read_exact(&mut prefix).await?;
+    let len = u32::from_le_bytes(prefix) as usize;
+    if len > max_frame_len {
+        let e = crate::error::error_kind(crate::ErrorKind::LimitExceeded, "frame too large");
+        return Err(Error::new(ErrorKind::InvalidData, e));
+    }
+
+    // Grows as bytes are read instead of trusting len with an allocation.
+    let mut bytes = vec![];
+    reader.take(len as u64).read_to_end(&mut bytes).await?;
+    if bytes.len() != len {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    crate::decode(&bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode_frame;
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use tokio::io::ReadBuf;
+
+    struct NoopWaker;
+
+    // Waker::noop requires Rust 1.85.
+    #[allow(unknown_lints, clippy::manual_noop_waker)]
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Polls `f` until it completes. The futures in these tests never wait on anything but
+    /// [`Trickle`].
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = pin!(f);
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = f.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// Reads one byte at a time, returning [`Poll::Pending`] before every byte.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        pending: bool,
+    }
+
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            if let Some((&b, rest)) = self.bytes.split_first() {
+                buf.put_slice(&[b]);
+                self.bytes = rest;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn encode_decode_async() {
+        let values: Vec<Vec<(u16, String)>> = (0..10)
+            .map(|i| (0..i).map(|j: u16| (j, "a".repeat(j as usize))).collect())
+            .collect();
+        let mut bytes = vec![];
+        for v in &values {
+            block_on(encode_async(v, &mut bytes)).unwrap();
+        }
+        let expected: Vec<u8> = values.iter().flat_map(encode_frame).collect();
+        assert_eq!(bytes, expected);
+
+        let mut reader = Trickle {
+            bytes: &bytes,
+            pending: false,
+        };
+        for v in &values {
+            let decoded: Vec<(u16, String)> = block_on(decode_async(&mut reader)).unwrap();
+            assert_eq!(&decoded, v);
+        }
+        let eof = block_on(decode_async::<Vec<(u16, String)>, _>(&mut reader));
+        assert_eq!(eof.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn decode_async_errors() {
+        // The length prefix claims more bytes than there are.
+        let huge = [u32::MAX.to_le_bytes().as_slice(), &[0; 10]].concat();
+        let err = block_on(decode_async_with_max_len::<u8, _>(huge.as_slice(), !0)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        // The length prefix is over the limit.
+        let err = block_on(decode_async::<u8, _>(huge.as_slice())).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let inner = err
+            .into_inner()
+            .unwrap()
+            .downcast::<crate::Error>()
+            .unwrap();
+        assert_eq!(inner.kind(), crate::ErrorKind::LimitExceeded);
+        let frame = encode_frame(&5u32);
+        let max_len = frame.len() - PREFIX_LEN;
+        assert_eq!(
+            block_on(decode_async_with_max_len::<u32, _>(
+                frame.as_slice(),
+                max_len
+            ))
+            .unwrap(),
+            5
+        );
+        let err = block_on(decode_async_with_max_len::<u32, _>(
+            frame.as_slice(),
+            max_len - 1,
+        ));
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // A bool with a trailing byte.
+        let invalid = [2u32.to_le_bytes().as_slice(), &[0, 0]].concat();
+        let err = block_on(decode_async::<bool, _>(invalid.as_slice())).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
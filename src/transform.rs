@@ -0,0 +1,177 @@
+use crate::coder::{Encoder, EncoderBuffer};
+use crate::error::error_from_display;
+use crate::io::WRITE_SIZE;
+use crate::{DecodeOwned, Encode, Error};
+use std::fmt::Display;
+use std::num::NonZeroUsize;
+
+/// Transforms the bytes of an encoding after it's encoded and before it's decoded, e.g. to encrypt,
+/// obfuscate or compress it. Used by [`encode_transformed`] and [`decode_transformed`].
+///
+/// ```rust
+/// /// Xors the encoding with a repeating key.
+/// struct Xor(&'static [u8], usize);
+///
+/// impl bitcode::Transform for Xor {
+///     type Error = std::convert::Infallible;
+///
+///     fn forward(&mut self, chunk: &[u8], out: &mut Vec<u8>) {
+///         for &b in chunk {
+///             out.push(b ^ self.0[self.1 % self.0.len()]);
+///             self.1 += 1;
+///         }
+///     }
+///
+///     fn inverse(&mut self, bytes: &[u8], out: &mut Vec<u8>) -> Result<(), Self::Error> {
+///         self.1 = 0;
+///         self.forward(bytes, out);
+///         Ok(())
+///     }
+/// }
+///
+/// let transformed = bitcode::encode_transformed(&vec![1u32, 2, 3], &mut Xor(b"key", 0));
+/// assert_ne!(transformed, bitcode::encode(&vec![1u32, 2, 3]));
+/// let v: Vec<u32> = bitcode::decode_transformed(&transformed, &mut Xor(b"key", 0)).unwrap();
+/// assert_eq!(v, [1, 2, 3]);
+/// ```
+pub trait Transform {
+    /// The error returned by [`Transform::inverse`], which [`decode_transformed`] returns as an
+    /// [`ErrorKind::Custom`][`crate::ErrorKind::Custom`].
+    type Error: Display;
+
+    /// Transforms the next `chunk` of the encoding, appending the result to `out`. Called with
+    /// every chunk in order, so a transform can keep state (such as a cipher's keystream) between
+    /// calls.
+    fn forward(&mut self, chunk: &[u8], out: &mut Vec<u8>);
+
+    /// Called after the last chunk to append anything that has to come at the end of `out`, such
+    /// as a message authentication code.
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        let _ = out;
+    }
+
+    /// Undoes [`Transform::forward`] and [`Transform::finish`] on all the `bytes` they output,
+    /// appending the original encoding to `out`.
+    fn inverse(&mut self, bytes: &[u8], out: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// Encodes a `T:` [`Encode`] and passes the encoding through `transform`.
+///
+/// Columns are transformed as soon as they're packed (in chunks of at least 64 KiB) instead of
+/// collecting the whole encoding first, so it doesn't have to be held in memory twice.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn encode_transformed<T: Encode + ?Sized>(t: &T, transform: &mut impl Transform) -> Vec<u8> {
+    let mut encoder = T::Encoder::default();
+    encoder.reserve(NonZeroUsize::MIN);
+    encoder.encode(t);
+
+    let mut chunk = vec![];
+    let mut out = vec![];
+    encoder.collect_into_flushing(&mut chunk, &mut |chunk| {
+        if chunk.len() >= WRITE_SIZE {
+            transform.forward(chunk, &mut out);
+            chunk.clear();
+        }
+    });
+    if !chunk.is_empty() {
+        transform.forward(&chunk, &mut out);
+    }
+    transform.finish(&mut out);
+    out
+}
+
+/// Undoes the `transform` of [`encode_transformed`] and decodes the result into a `T:`
+/// [`DecodeOwned`].
+///
+/// Unlike encoding, `bytes` is inverse transformed all at once into a temporary buffer before it's
+/// decoded. Decoders borrow their columns from the original encoding until decoding finishes, so
+/// the whole original encoding has to be in memory anyway. The buffer is dropped before returning,
+/// which is why `T` can't borrow from it.
+///
+/// **Warning:** The format is subject to change between major versions.
+pub fn decode_transformed<T: DecodeOwned>(
+    bytes: &[u8],
+    transform: &mut impl Transform,
+) -> Result<T, Error> {
+    let mut encoded = vec![];
+    transform
+        .inverse(bytes, &mut encoded)
+        .map_err(error_from_display)?;
+    crate::decode(&encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_transformed, encode_transformed, Transform};
+    use crate::{encode, ErrorKind};
+
+    /// Adds 1 to every byte and appends the length, recording the length of each chunk.
+    #[derive(Default)]
+    struct AddOne(Vec<usize>);
+
+    impl Transform for AddOne {
+        type Error = &'static str;
+
+        fn forward(&mut self, chunk: &[u8], out: &mut Vec<u8>) {
+            self.0.push(chunk.len());
+            out.extend(chunk.iter().map(|b| b.wrapping_add(1)));
+        }
+
+        fn finish(&mut self, out: &mut Vec<u8>) {
+            let len = out.len() as u32;
+            out.extend_from_slice(&len.to_le_bytes());
+        }
+
+        fn inverse(&mut self, bytes: &[u8], out: &mut Vec<u8>) -> Result<(), Self::Error> {
+            let (bytes, len) = bytes.split_at(bytes.len().checked_sub(4).ok_or("too short")?);
+            if u32::from_le_bytes(len.try_into().unwrap()) as usize != bytes.len() {
+                return Err("wrong length");
+            }
+            out.extend(bytes.iter().map(|b| b.wrapping_sub(1)));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn transformed() {
+        let values: Vec<(u64, String)> = (0..50000).map(|i| (i, i.to_string())).collect();
+        let encoded = encode(&values);
+        let mut transform = AddOne::default();
+        let transformed = encode_transformed(&values, &mut transform);
+        assert_eq!(transformed.len(), encoded.len() + 4);
+        assert_eq!(transformed[0], encoded[0].wrapping_add(1));
+        // Transformed in chunks instead of all at once.
+        assert!(transform.0.len() > 1, "{:?}", transform.0);
+        assert_eq!(transform.0.iter().sum::<usize>(), encoded.len());
+
+        let decoded: Vec<(u64, String)> = decode_transformed(&transformed, &mut transform).unwrap();
+        assert_eq!(decoded, values);
+        let error = decode_transformed::<Vec<(u64, String)>>(&transformed[1..], &mut transform);
+        assert_eq!(error.unwrap_err().kind(), ErrorKind::Custom);
+
+        let transformed = encode_transformed(&(), &mut transform);
+        assert_eq!(
+            decode_transformed::<()>(&transformed, &mut transform),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn transformed_nested() {
+        fn check<T: crate::Encode + ?Sized>(t: &T) {
+            let mut transform = AddOne::default();
+            let transformed = encode_transformed(t, &mut transform);
+            assert_eq!(transformed.len(), encode(t).len() + 4);
+            // Encoders that contain other encoders transform their columns in chunks too.
+            assert!(transform.0.len() > 1, "{:?}", transform.0);
+        }
+        let values: Vec<(u64, String)> = (0..50000).map(|i| (i, i.to_string())).collect();
+        check(&Some(values.clone()));
+        check(&Ok::<_, ()>(values.clone()));
+        check(&Box::new(values.clone()));
+        check(&[values.clone(), values.clone()]);
+        check(&std::cmp::Reverse(values.clone()));
+        check(&(values.clone()..values));
+    }
+}
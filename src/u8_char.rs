@@ -1,4 +1,4 @@
-use crate::coder::{Buffer, Encoder};
+use crate::coder::{Encoder, EncoderBuffer};
 use crate::derive::Encode;
 use crate::fast::{PushUnchecked, VecImpl};
 use std::num::NonZeroUsize;
@@ -31,7 +31,7 @@ impl Encoder<U8Char> for U8CharEncoder {
     }
 }
 
-impl Buffer for U8CharEncoder {
+impl EncoderBuffer for U8CharEncoder {
     fn collect_into(&mut self, out: &mut Vec<u8>) {
         out.extend_from_slice(bytemuck::must_cast_slice(self.0.as_slice()));
         self.0.clear();
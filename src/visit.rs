@@ -0,0 +1,493 @@
+use crate::bool::BoolDecoder;
+use crate::coder::{Decoder, View};
+use crate::consume::{expect_eof, mul_length};
+use crate::derive::variant::VariantDecoder;
+use crate::error::{err_kind, ErrorKind};
+use crate::f32::F32Decoder;
+use crate::int::IntDecoder;
+use crate::length::LengthDecoder;
+use crate::str::StrDecoder;
+use crate::Error;
+
+/// The type of an encoded value, which is required to [`visit`] it since the format isn't self
+/// describing (see [`encode_self_describing`][`crate::encode_self_describing`] to include it).
+/// Each variant is encoded exactly like the Rust type(s) it names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schema {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+    /// `String` or `&str`.
+    Str,
+    /// `Vec<T>` (or any other sequence such as `HashSet<T>`).
+    Vec(Box<Schema>),
+    /// `[T; N]`.
+    Array(Box<Schema>, usize),
+    /// `HashMap<K, V>` or `BTreeMap<K, V>`.
+    Map(Box<Schema>, Box<Schema>),
+    /// `Option<T>`.
+    Option(Box<Schema>),
+    /// A tuple or `#[derive(Encode)]` struct with these fields.
+    Tuple(Vec<Schema>),
+    /// A `#[derive(Encode)]` enum whose variants have these fields (`Tuple(vec![])` for unit
+    /// variants).
+    Enum(Vec<Schema>),
+    /// A name for the schema, such as the name of a type, field or enum variant. Encoded exactly
+    /// like the schema it names.
+    Named(String, Box<Schema>),
+}
+
+/// A primitive value passed to [`Visitor::value`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    F32(f32),
+    F64(f64),
+    Str(&'a str),
+}
+
+/// Callbacks for [`visit`], which are called in the order the values appear in the Rust type.
+pub trait Visitor<'a> {
+    /// Called for each primitive value.
+    fn value(&mut self, value: Value<'a>);
+
+    /// Called before the `len` elements of a [`Schema::Vec`] or [`Schema::Array`], or the `len`
+    /// key/value pairs of a [`Schema::Map`].
+    fn begin_seq(&mut self, len: usize) {
+        let _ = len;
+    }
+
+    /// Called after the elements of a sequence.
+    fn end_seq(&mut self) {}
+
+    /// Called before the fields of an enum variant. [`Schema::Option`] is `None` = 0 and
+    /// `Some` = 1.
+    fn variant(&mut self, index: usize) {
+        let _ = index;
+    }
+}
+
+/// Walks over the value encoded in `bytes` (as described by `schema`) without decoding it into
+/// Rust values. Strings are borrowed from `bytes`. The whole input is validated before any
+/// callbacks are called.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// use bitcode::{visit, Schema, Value, Visitor};
+///
+/// /// Sums all the u32s.
+/// struct Sum(u64);
+/// impl Visitor<'_> for Sum {
+///     fn value(&mut self, value: Value) {
+///         if let Value::U32(v) = value {
+///             self.0 += v as u64;
+///         }
+///     }
+/// }
+///
+/// let encoded = bitcode::encode(&vec![("a", 1u32), ("b", 2), ("c", 3)]);
+/// let schema = Schema::Vec(Box::new(Schema::Tuple(vec![Schema::Str, Schema::U32])));
+/// let mut sum = Sum(0);
+/// visit(&encoded, &schema, &mut sum).unwrap();
+/// assert_eq!(sum.0, 6);
+/// ```
+pub fn visit<'a>(
+    mut bytes: &'a [u8],
+    schema: &Schema,
+    visitor: &mut impl Visitor<'a>,
+) -> Result<(), Error> {
+    let mut column = Column::new(schema);
+    column.populate(&mut bytes, 1)?;
+    expect_eof(bytes)?;
+    column.visit(visitor);
+    Ok(())
+}
+
+/// The decoder of a [`Schema`].
+enum Column<'a> {
+    Bool(BoolDecoder<'a>),
+    U8(IntDecoder<'a, u8>),
+    U16(IntDecoder<'a, u16>),
+    U32(IntDecoder<'a, u32>),
+    U64(IntDecoder<'a, u64>),
+    U128(IntDecoder<'a, u128>),
+    I8(IntDecoder<'a, i8>),
+    I16(IntDecoder<'a, i16>),
+    I32(IntDecoder<'a, i32>),
+    I64(IntDecoder<'a, i64>),
+    I128(IntDecoder<'a, i128>),
+    F32(F32Decoder<'a>),
+    F64(IntDecoder<'a, u64>),
+    Str(StrDecoder<'a>),
+    Vec(LengthDecoder<'a>, Box<Self>),
+    Array(usize, Box<Self>),
+    Map(LengthDecoder<'a>, Box<Self>, Box<Self>),
+    Option(VariantDecoder<'a, 2, false>, Box<Self>),
+    Tuple(Vec<Self>),
+    Enum(Variants<'a>, Vec<Self>),
+}
+
+/// Enums are encoded with a `VariantDecoder<N>` where `N` is the number of variants. Its packing
+/// only depends on which of these `N`s is the next one up, so we use that and check that the
+/// variants are less than the actual `N`.
+enum Variants<'a> {
+    /// 0 or 1 variants which aren't encoded.
+    None,
+    V2(VariantDecoder<'a, 2, false>),
+    V3(VariantDecoder<'a, 3, false>),
+    V4(VariantDecoder<'a, 4, false>),
+    V6(VariantDecoder<'a, 6, false>),
+    V16(VariantDecoder<'a, 16, false>),
+    V256(Box<VariantDecoder<'a, 256, false>>), // Boxed since it's large.
+}
+
+macro_rules! variants {
+    ($self:ident, $v:ident => $e:expr, $none:expr) => {
+        match $self {
+            Variants::None => $none,
+            Variants::V2($v) => $e,
+            Variants::V3($v) => $e,
+            Variants::V4($v) => $e,
+            Variants::V6($v) => $e,
+            Variants::V16($v) => $e,
+            Variants::V256($v) => $e,
+        }
+    };
+}
+
+impl<'a> Variants<'a> {
+    fn new(n: usize) -> Self {
+        match n {
+            0..=1 => Self::None,
+            2 => Self::V2(Default::default()),
+            3 => Self::V3(Default::default()),
+            4 => Self::V4(Default::default()),
+            5..=6 => Self::V6(Default::default()),
+            7..=16 => Self::V16(Default::default()),
+            _ => Self::V256(Default::default()),
+        }
+    }
+
+    /// Returns the number of each of the `n` variants.
+    fn populate(
+        &mut self,
+        input: &mut &'a [u8],
+        length: usize,
+        n: usize,
+    ) -> Result<Vec<usize>, Error> {
+        if n == 0 && length != 0 {
+            return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
+        }
+        variants!(self, v => {
+            v.populate(input, length)?;
+            Ok(v.lengths().to_vec())
+        }, Ok(vec![length; n]))
+    }
+
+    fn decode(&mut self) -> usize {
+        variants!(self, v => v.decode() as usize, 0)
+    }
+}
+
+impl<'a> Column<'a> {
+    fn new(schema: &Schema) -> Self {
+        let new = |schema: &Schema| Box::new(Self::new(schema));
+        match schema {
+            Schema::Bool => Self::Bool(Default::default()),
+            Schema::U8 => Self::U8(Default::default()),
+            Schema::U16 => Self::U16(Default::default()),
+            Schema::U32 => Self::U32(Default::default()),
+            Schema::U64 => Self::U64(Default::default()),
+            Schema::U128 => Self::U128(Default::default()),
+            Schema::I8 => Self::I8(Default::default()),
+            Schema::I16 => Self::I16(Default::default()),
+            Schema::I32 => Self::I32(Default::default()),
+            Schema::I64 => Self::I64(Default::default()),
+            Schema::I128 => Self::I128(Default::default()),
+            Schema::F32 => Self::F32(Default::default()),
+            Schema::F64 => Self::F64(Default::default()),
+            Schema::Str => Self::Str(Default::default()),
+            Schema::Vec(t) => Self::Vec(Default::default(), new(t)),
+            Schema::Array(t, n) => Self::Array(*n, new(t)),
+            Schema::Map(k, v) => Self::Map(Default::default(), new(k), new(v)),
+            Schema::Option(t) => Self::Option(Default::default(), new(t)),
+            Schema::Tuple(fields) => Self::Tuple(fields.iter().map(Self::new).collect()),
+            Schema::Enum(variants) => Self::Enum(
+                Variants::new(variants.len()),
+                variants.iter().map(Self::new).collect(),
+            ),
+            Schema::Named(_, t) => Self::new(t),
+        }
+    }
+
+    /// Populates the columns in the same order as the Rust type's decoder.
+    fn populate(&mut self, input: &mut &'a [u8], length: usize) -> Result<(), Error> {
+        match self {
+            Self::Bool(d) => d.populate(input, length),
+            Self::U8(d) => d.populate(input, length),
+            Self::U16(d) => d.populate(input, length),
+            Self::U32(d) => d.populate(input, length),
+            Self::U64(d) => d.populate(input, length),
+            Self::U128(d) => d.populate(input, length),
+            Self::I8(d) => d.populate(input, length),
+            Self::I16(d) => d.populate(input, length),
+            Self::I32(d) => d.populate(input, length),
+            Self::I64(d) => d.populate(input, length),
+            Self::I128(d) => d.populate(input, length),
+            Self::F32(d) => d.populate(input, length),
+            Self::F64(d) => d.populate(input, length),
+            Self::Str(d) => d.populate(input, length),
+            Self::Vec(lengths, t) => {
+                lengths.populate(input, length)?;
+                t.populate(input, lengths.length())
+            }
+            Self::Array(n, t) => t.populate(input, mul_length(length, *n)?),
+            Self::Map(lengths, k, v) => {
+                lengths.populate(input, length)?;
+                k.populate(input, lengths.length())?;
+                v.populate(input, lengths.length())
+            }
+            Self::Option(variants, t) => {
+                variants.populate(input, length)?;
+                t.populate(input, variants.length(1))
+            }
+            Self::Tuple(fields) => fields
+                .iter_mut()
+                .try_for_each(|f| f.populate(input, length)),
+            Self::Enum(variants, fields) => {
+                let lengths = variants.populate(input, length, fields.len())?;
+                if lengths
+                    .get(fields.len()..)
+                    .is_some_and(|l| l.iter().any(|&n| n != 0))
+                {
+                    return err_kind(ErrorKind::InvalidVariant, "invalid enum variant");
+                }
+                for (field, length) in fields.iter_mut().zip(lengths) {
+                    field.populate(input, length)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn visit<V: Visitor<'a>>(&mut self, visitor: &mut V) {
+        match self {
+            Self::Bool(d) => visitor.value(Value::Bool(d.decode())),
+            Self::U8(d) => visitor.value(Value::U8(d.decode())),
+            Self::U16(d) => visitor.value(Value::U16(d.decode())),
+            Self::U32(d) => visitor.value(Value::U32(d.decode())),
+            Self::U64(d) => visitor.value(Value::U64(d.decode())),
+            Self::U128(d) => visitor.value(Value::U128(d.decode())),
+            Self::I8(d) => visitor.value(Value::I8(d.decode())),
+            Self::I16(d) => visitor.value(Value::I16(d.decode())),
+            Self::I32(d) => visitor.value(Value::I32(d.decode())),
+            Self::I64(d) => visitor.value(Value::I64(d.decode())),
+            Self::I128(d) => visitor.value(Value::I128(d.decode())),
+            Self::F32(d) => visitor.value(Value::F32(d.decode())),
+            Self::F64(d) => visitor.value(Value::F64(d.decode())),
+            Self::Str(d) => visitor.value(Value::Str(d.decode())),
+            Self::Vec(lengths, t) => {
+                let n = lengths.decode();
+                visitor.begin_seq(n);
+                (0..n).for_each(|_| t.visit(visitor));
+                visitor.end_seq();
+            }
+            Self::Array(n, t) => {
+                visitor.begin_seq(*n);
+                (0..*n).for_each(|_| t.visit(visitor));
+                visitor.end_seq();
+            }
+            Self::Map(lengths, k, v) => {
+                let n = lengths.decode();
+                visitor.begin_seq(n);
+                for _ in 0..n {
+                    k.visit(visitor);
+                    v.visit(visitor);
+                }
+                visitor.end_seq();
+            }
+            Self::Option(variants, t) => {
+                let variant = variants.decode();
+                visitor.variant(variant as usize);
+                if variant != 0 {
+                    t.visit(visitor);
+                }
+            }
+            Self::Tuple(fields) => fields.iter_mut().for_each(|f| f.visit(visitor)),
+            Self::Enum(variants, fields) => {
+                let variant = variants.decode();
+                visitor.variant(variant);
+                fields[variant].visit(visitor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, Encode};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq)]
+    enum Event<'a> {
+        V(Value<'a>),
+        BeginSeq(usize),
+        EndSeq,
+        Variant(usize),
+    }
+
+    impl<'a> Visitor<'a> for Vec<Event<'a>> {
+        fn value(&mut self, value: Value<'a>) {
+            self.push(Event::V(value));
+        }
+        fn begin_seq(&mut self, len: usize) {
+            self.push(Event::BeginSeq(len));
+        }
+        fn end_seq(&mut self) {
+            self.push(Event::EndSeq);
+        }
+        fn variant(&mut self, index: usize) {
+            self.push(Event::Variant(index));
+        }
+    }
+
+    fn events<'a>(bytes: &'a [u8], schema: &Schema) -> Result<Vec<Event<'a>>, Error> {
+        let mut events = vec![];
+        visit(bytes, schema, &mut events)?;
+        Ok(events)
+    }
+
+    #[derive(Encode)]
+    enum Shape {
+        Point,
+        Circle(f32),
+        Line { start: [i16; 2], end: [i16; 2] },
+    }
+
+    #[derive(Encode)]
+    struct Drawing {
+        name: String,
+        shapes: Vec<Shape>,
+        tags: BTreeMap<u8, bool>,
+        parent: Option<u64>,
+    }
+
+    #[test]
+    fn test_visit() {
+        use Event::*;
+        let drawing = Drawing {
+            name: "abc".into(),
+            shapes: vec![
+                Shape::Circle(1.5),
+                Shape::Point,
+                Shape::Line {
+                    start: [1, -2],
+                    end: [3, 4],
+                },
+            ],
+            tags: BTreeMap::from([(7, true)]),
+            parent: Some(1000),
+        };
+        let point = Schema::Array(Box::new(Schema::I16), 2);
+        let schema = Schema::Tuple(vec![
+            Schema::Str,
+            Schema::Vec(Box::new(Schema::Enum(vec![
+                Schema::Tuple(vec![]),
+                Schema::F32,
+                Schema::Tuple(vec![point.clone(), point]),
+            ]))),
+            Schema::Map(Box::new(Schema::U8), Box::new(Schema::Bool)),
+            Schema::Option(Box::new(Schema::U64)),
+        ]);
+        let bytes = encode(&drawing);
+        assert_eq!(
+            events(&bytes, &schema).unwrap(),
+            [
+                V(Value::Str("abc")),
+                BeginSeq(3),
+                Variant(1),
+                V(Value::F32(1.5)),
+                Variant(0),
+                Variant(2),
+                BeginSeq(2),
+                V(Value::I16(1)),
+                V(Value::I16(-2)),
+                EndSeq,
+                BeginSeq(2),
+                V(Value::I16(3)),
+                V(Value::I16(4)),
+                EndSeq,
+                EndSeq,
+                BeginSeq(1),
+                V(Value::U8(7)),
+                V(Value::Bool(true)),
+                EndSeq,
+                Variant(1),
+                V(Value::U64(1000)),
+            ]
+        );
+
+        // Truncated, trailing bytes and a schema that doesn't match.
+        assert!(events(&bytes[..bytes.len() - 1], &schema).is_err());
+        assert!(events(&[&bytes[..], &[0]].concat(), &schema).is_err());
+        assert!(events(&bytes, &Schema::U64).is_err());
+    }
+
+    #[test]
+    fn test_visit_enum() {
+        #[allow(unused)]
+        #[derive(Encode)]
+        enum Six {
+            A,
+            B,
+            C,
+            D,
+            E(u8),
+            F,
+        }
+        let unit = || Schema::Tuple(vec![]);
+        let mut variants = vec![unit(), unit(), unit(), unit(), Schema::U8, unit()];
+        let bytes = encode(&vec![Six::E(3), Six::A, Six::F]);
+        let schema = Schema::Vec(Box::new(Schema::Enum(variants.clone())));
+        assert_eq!(
+            events(&bytes, &schema).unwrap()[1..5],
+            [
+                Event::Variant(4),
+                Event::V(Value::U8(3)),
+                Event::Variant(0),
+                Event::Variant(5),
+            ]
+        );
+
+        // 5 and 6 variants are packed the same way, but variant 5 doesn't exist.
+        variants.pop();
+        let schema = Schema::Vec(Box::new(Schema::Enum(variants)));
+        assert!(events(&bytes, &schema).is_err());
+
+        // Enums with 0 or 1 variants don't encode their variant.
+        assert!(events(&encode(&()), &Schema::Enum(vec![unit()])).is_ok());
+        assert!(events(&encode(&()), &Schema::Enum(vec![])).is_err());
+    }
+}
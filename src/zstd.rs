@@ -0,0 +1,176 @@
+use crate::error::{err, err_kind, error, ErrorKind};
+use crate::{Buffer, Decode, Encode, Error};
+use zstd::bulk::{Compressor, Decompressor};
+use zstd::zstd_safe::{get_dict_id_from_dict, get_dict_id_from_frame, get_frame_content_size};
+
+/// Trains a zstd dictionary of up to `max_size` bytes from `samples` of encoded messages (e.g. the
+/// outputs of [`encode`][`crate::encode`] for a few thousand typical messages). Store/distribute
+/// the returned bytes and load them with [`ZstdDictionary::new`].
+///
+/// Returns an error if there aren't enough samples to train a dictionary.
+pub fn train_zstd_dictionary(
+    samples: &[impl AsRef<[u8]>],
+    max_size: usize,
+) -> Result<Vec<u8>, Error> {
+    zstd::dict::from_samples(samples, max_size).map_err(|_| error("zstd training failed"))
+}
+
+/// Encodes/decodes messages compressed with a zstd dictionary from [`train_zstd_dictionary`].
+///
+/// Small messages compress poorly on their own since there's nothing earlier in the message to
+/// reference. A dictionary trained on similar messages gives the compressor that context. The
+/// output is a single zstd frame whose header contains the dictionary's id, so decoding a message
+/// with the wrong dictionary is an error.
+///
+/// Like [`Buffer`], it saves allocations (and the loaded dictionary) between calls.
+///
+/// **Warning:** The format is subject to change between major versions.
+///
+/// ```rust
+/// # #[cfg(not(miri))] { // Miri can't call zstd's C functions.
+/// use bitcode::{train_zstd_dictionary, ZstdDictionary};
+///
+/// let messages: Vec<Vec<u8>> = (0..1000u32)
+///     .map(|i| bitcode::encode(&(i, format!("player_{}", i % 37), i % 3 == 0)))
+///     .collect();
+/// let dictionary = train_zstd_dictionary(&messages, 4096).unwrap();
+///
+/// let mut zstd = ZstdDictionary::new(&dictionary, 3).unwrap();
+/// let compressed = zstd.encode(&(5u32, "player_5", false)).to_vec();
+/// assert_eq!(zstd.decode::<(u32, &str, bool)>(&compressed).unwrap(), (5, "player_5", false));
+/// # }
+/// ```
+pub struct ZstdDictionary {
+    id: u32,
+    compressor: Compressor<'static>,
+    decompressor: Decompressor<'static>,
+    buffer: Buffer,
+    out: Vec<u8>,
+    max_decoded_len: usize,
+}
+
+impl ZstdDictionary {
+    /// Loads a `dictionary` from [`train_zstd_dictionary`] and compresses with zstd's compression
+    /// `level`. Returns an error if `dictionary` wasn't trained (since it wouldn't have an id).
+    pub fn new(dictionary: &[u8], level: i32) -> Result<Self, Error> {
+        let Some(id) = get_dict_id_from_dict(dictionary) else {
+            return err("invalid zstd dictionary");
+        };
+        let zstd_error = |_| error("invalid zstd dictionary");
+        Ok(Self {
+            id: id.get(),
+            compressor: Compressor::with_dictionary(level, dictionary).map_err(zstd_error)?,
+            decompressor: Decompressor::with_dictionary(dictionary).map_err(zstd_error)?,
+            buffer: Buffer::new(),
+            out: vec![],
+            max_decoded_len: 8 * 1024 * 1024,
+        })
+    }
+
+    /// Sets the maximum length of a message after decompression (8 MiB by default). Larger
+    /// messages are rejected before they're decompressed.
+    pub fn max_decoded_len(mut self, max_decoded_len: usize) -> Self {
+        self.max_decoded_len = max_decoded_len;
+        self
+    }
+
+    /// Returns the dictionary's id.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the id of the dictionary that `compressed` was encoded with (if it's a zstd frame
+    /// encoded with a dictionary), e.g. to pick one of multiple [`ZstdDictionary`]s to decode it.
+    pub fn id_of(compressed: &[u8]) -> Option<u32> {
+        get_dict_id_from_frame(compressed).map(|id| id.get())
+    }
+
+    /// Encodes `t` and compresses it with the dictionary.
+    pub fn encode<T: Encode + ?Sized>(&mut self, t: &T) -> &[u8] {
+        let encoded = self.buffer.encode(t);
+        self.out.clear();
+        self.out
+            .reserve(zstd::zstd_safe::compress_bound(encoded.len()));
+        self.compressor
+            .compress_to_buffer(encoded, &mut self.out)
+            .expect("compress_bound is enough");
+        &self.out
+    }
+
+    /// Decompresses `compressed` with the dictionary and decodes it.
+    pub fn decode<'a, T: Decode<'a>>(&'a mut self, compressed: &[u8]) -> Result<T, Error> {
+        if Self::id_of(compressed) != Some(self.id) {
+            return err("wrong zstd dictionary");
+        }
+        let Ok(Some(len)) = get_frame_content_size(compressed) else {
+            return err("invalid zstd frame");
+        };
+        if len > self.max_decoded_len as u64 {
+            return err_kind(ErrorKind::LimitExceeded, "zstd frame too large");
+        }
+        self.out.clear();
+        self.out.reserve(len as usize);
+        self.decompressor
+            .decompress_to_buffer(compressed, &mut self.out)
+            .map_err(|_| error("invalid zstd frame"))?;
+        self.buffer.decode(&self.out)
+    }
+}
+
+#[cfg(all(test, not(miri)))] // Miri can't call zstd's C functions.
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Vec<u8>> {
+        (0..2000u32)
+            .map(|i| crate::encode(&(i % 100, format!("message {}", i % 50), vec![i as u8; 5])))
+            .collect()
+    }
+
+    #[test]
+    fn zstd_dictionary() {
+        let messages = messages();
+        let dictionary = train_zstd_dictionary(&messages, 2048).unwrap();
+        let mut zstd = ZstdDictionary::new(&dictionary, 3).unwrap();
+
+        let message = (42u32, "message 42", vec![42u8; 5]);
+        let compressed = zstd.encode(&message).to_vec();
+        assert_eq!(ZstdDictionary::id_of(&compressed), Some(zstd.id()));
+        let without_dictionary = zstd::bulk::compress(&crate::encode(&message), 3).unwrap();
+        assert!(compressed.len() < without_dictionary.len());
+
+        let decoded: (u32, &str, Vec<u8>) = zstd.decode(&compressed).unwrap();
+        assert_eq!(decoded, message);
+
+        // Truncated, corrupted or too large.
+        assert!(zstd
+            .decode::<(u32, &str, Vec<u8>)>(&compressed[..compressed.len() - 1])
+            .is_err());
+        assert!(zstd
+            .decode::<(u32, &str, Vec<u8>)>(&without_dictionary)
+            .is_err());
+        let mut small = ZstdDictionary::new(&dictionary, 3)
+            .unwrap()
+            .max_decoded_len(5);
+        assert!(small.decode::<(u32, &str, Vec<u8>)>(&compressed).is_err());
+
+        // Untrained dictionary.
+        assert!(ZstdDictionary::new(b"not a dictionary", 3).is_err());
+    }
+
+    #[test]
+    fn wrong_dictionary() {
+        let a = train_zstd_dictionary(&messages(), 2048).unwrap();
+        let b_messages: Vec<_> = (0..2000u64)
+            .map(|i| crate::encode(&(i * 1_000_000, i.to_string())))
+            .collect();
+        let b = train_zstd_dictionary(&b_messages, 2048).unwrap();
+        let mut a = ZstdDictionary::new(&a, 3).unwrap();
+        let mut b = ZstdDictionary::new(&b, 3).unwrap();
+        assert_ne!(a.id(), b.id());
+
+        let compressed = a.encode("hello").to_vec();
+        assert_eq!(a.decode::<&str>(&compressed).unwrap(), "hello");
+        assert!(b.decode::<&str>(&compressed).is_err());
+    }
+}